@@ -0,0 +1,328 @@
+//! Finding the first commit at which a semver check starts to fail.
+
+use std::collections::BTreeSet;
+use std::ops::RangeInclusive;
+
+use anyhow::bail;
+use itertools::Itertools;
+
+use crate::{
+    CancellationToken, Check, GlobalConfig, Rustdoc, RustdocSource, ScopeMode, SemverQuery,
+};
+
+/// A commit found by [`Check::bisect()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BisectedCommit {
+    id: String,
+    summary: String,
+}
+
+impl BisectedCommit {
+    /// The full hash of the commit.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The first line of the commit's message.
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+}
+
+pub(crate) fn bisect(
+    check: &Check,
+    config: &mut GlobalConfig,
+    current_rev: &str,
+    lint: Option<&str>,
+) -> anyhow::Result<BisectedCommit> {
+    let RustdocSource::Revision(repo_root, baseline_rev) = &check.baseline.source else {
+        bail!("bisecting requires a git revision baseline, such as one set by `--baseline-rev`");
+    };
+    if let Some(lint) = lint
+        && !SemverQuery::all_queries().contains_key(lint)
+    {
+        bail!("unknown lint `{lint}`, run `cargo semver-checks --list` to see all lints");
+    }
+
+    let mut commits = commits_between(repo_root, baseline_rev, current_rev)?;
+    if commits.is_empty() {
+        bail!(
+            "{current_rev} has no commits that aren't in {baseline_rev}, so there's nothing to bisect"
+        );
+    }
+
+    let mut check = check.clone();
+    // Crate names can't be deduced from a git revision, so look them up in the current project.
+    if let ScopeMode::DenyList(_) = check.scope.mode
+        && let RustdocSource::Root(project_root) = &check.current.source
+    {
+        let metadata = crate::manifest_metadata(
+            project_root,
+            check.current_toolchain.as_deref(),
+            check.is_offline(),
+        )?;
+        let (selected, _) = check.scope.selected_packages(&metadata);
+        let packages: Vec<String> = selected
+            .iter()
+            .map(|package| package.name.to_string())
+            .collect();
+        check.set_packages(packages);
+    }
+
+    let mut outcome_at = |config: &mut GlobalConfig, commit: &BisectedCommit| {
+        check.current = Rustdoc::from_git_revision(repo_root, &commit.id);
+        // Only whether the check fails matters here, so don't print each commit's report.
+        let report = match config.with_stdout_discarded(|config| check.check_release(config)) {
+            Ok(report) => report,
+            Err(err)
+                if check
+                    .cancellation
+                    .as_ref()
+                    .is_some_and(CancellationToken::is_cancelled) =>
+            {
+                return Err(err);
+            }
+            Err(err) => {
+                config.shell_warn(format_args!(
+                    "skipping commit {}, which couldn't be checked: {err:#}",
+                    commit.id
+                ))?;
+                return Ok(Outcome::Untestable);
+            }
+        };
+        let fails = match lint {
+            Some(lint) => report.crate_reports().values().any(|crate_report| {
+                crate_report.lint_results.iter().any(|result| {
+                    result.semver_query.id == lint && !result.query_results.is_empty()
+                })
+            }),
+            None => !report.success(),
+        };
+        anyhow::Ok(if fails {
+            Outcome::Fails
+        } else {
+            Outcome::Passes
+        })
+    };
+
+    config.shell_status(
+        "Bisecting",
+        format_args!(
+            "{} commits between {baseline_rev} and {current_rev}",
+            commits.len()
+        ),
+    )?;
+    match outcome_at(config, &commits[commits.len() - 1])? {
+        Outcome::Fails => {}
+        Outcome::Passes => {
+            bail!("the check passes at {current_rev}, so there's no failing commit to find")
+        }
+        Outcome::Untestable => {
+            bail!("the check couldn't run at {current_rev}, so there's no failing commit to find")
+        }
+    }
+
+    let search = first_failing(commits.len(), |low, high, index| {
+        config.shell_status(
+            "Bisecting",
+            format_args!(
+                "{} commits left, checking {}",
+                high - low + 1,
+                commits[index].id
+            ),
+        )?;
+        outcome_at(config, &commits[index])
+    })?;
+    match search {
+        Search::Found(index) => Ok(commits.swap_remove(index)),
+        Search::Ambiguous(candidates) => {
+            let candidates = commits[candidates]
+                .iter()
+                .map(|commit| format!("  {} {}", commit.id, commit.summary))
+                .join("\n");
+            bail!(
+                "only commits that couldn't be checked are left, \
+                 so the first failing commit could be any of:\n{candidates}"
+            )
+        }
+    }
+}
+
+/// The result of checking one commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Passes,
+    Fails,
+    /// The check couldn't run, e.g. because the crate didn't build. Like `git bisect skip`.
+    Untestable,
+}
+
+/// Where [`first_failing()`] found the first failing commit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Search {
+    Found(usize),
+    /// Only untestable commits precede the last one in this range,
+    /// so any commit in it may be the first failing one.
+    Ambiguous(RangeInclusive<usize>),
+}
+
+/// Binary search for the first failing one of `len` commits, the last of which is known to fail.
+///
+/// `test` checks the commit at the given index, between the given bounds of the search.
+/// Like `git bisect`, this assumes every commit after a failing one fails too.
+/// Untestable commits are narrowed around by testing the closest commit to the middle instead.
+fn first_failing(
+    len: usize,
+    mut test: impl FnMut(usize, usize, usize) -> anyhow::Result<Outcome>,
+) -> anyhow::Result<Search> {
+    // The first failing commit is in `low..=high`, and `high` is known to fail.
+    let (mut low, mut high) = (0, len - 1);
+    let mut untestable = BTreeSet::new();
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let Some(index) = (low..high)
+            .filter(|index| !untestable.contains(index))
+            .min_by_key(|index| index.abs_diff(middle))
+        else {
+            return Ok(Search::Ambiguous(low..=high));
+        };
+        match test(low, high, index)? {
+            Outcome::Fails => high = index,
+            Outcome::Passes => low = index + 1,
+            Outcome::Untestable => {
+                untestable.insert(index);
+            }
+        }
+    }
+    Ok(Search::Found(high))
+}
+
+/// The first-parent history from `baseline_rev` (exclusive) to `current_rev` (inclusive),
+/// oldest first.
+fn commits_between(
+    repo_root: &std::path::Path,
+    baseline_rev: &str,
+    current_rev: &str,
+) -> anyhow::Result<Vec<BisectedCommit>> {
+    let repo = gix::ThreadSafeRepository::discover_with_environment_overrides(repo_root)
+        .map(gix::Repository::from)?;
+    let baseline = repo
+        .rev_parse_single(&*format!("{baseline_rev}^{{commit}}"))?
+        .detach();
+    let current = repo
+        .rev_parse_single(&*format!("{current_rev}^{{commit}}"))?
+        .detach();
+
+    let mut commits = repo
+        .rev_walk([current])
+        .with_hidden([baseline])
+        .first_parent_only()
+        .all()?
+        .map(|info| {
+            let info = info?;
+            let commit = info.object()?;
+            Ok(BisectedCommit {
+                id: info.id.to_string(),
+                summary: commit.message()?.summary().to_string(),
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    commits.reverse();
+    Ok(commits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The outcome a test commit's summary says it has, such as `passes 2`.
+    fn outcome_from_summary(summary: &str) -> Outcome {
+        match summary.split_once(' ').map(|(outcome, _)| outcome) {
+            Some("passes") => Outcome::Passes,
+            Some("fails") => Outcome::Fails,
+            Some("untestable") => Outcome::Untestable,
+            _ => panic!("unexpected summary: {summary}"),
+        }
+    }
+
+    #[test]
+    fn search_narrows_around_untestable_commits() {
+        let repo = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-bisect-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        fs_err::create_dir_all(&repo).expect("failed to create temp dir");
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(["-c", "commit.gpgsign=false"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .expect("failed to run git");
+            assert!(output.status.success(), "{output:?}");
+        };
+
+        git(&["init", "--quiet"]);
+        for summary in [
+            "initial",
+            "untestable 1",
+            "passes 2",
+            "passes 3",
+            "fails 4",
+            "untestable 5",
+            "fails 6",
+        ] {
+            git(&["commit", "--quiet", "--allow-empty", "-m", summary]);
+            if summary == "initial" {
+                git(&["tag", "baseline"]);
+            }
+        }
+
+        let commits = commits_between(&repo, "baseline", "HEAD").expect("failed to list commits");
+        let summaries: Vec<&str> = commits.iter().map(BisectedCommit::summary).collect();
+        assert_eq!(
+            summaries,
+            [
+                "untestable 1",
+                "passes 2",
+                "passes 3",
+                "fails 4",
+                "untestable 5",
+                "fails 6"
+            ]
+        );
+
+        let mut tested = Vec::new();
+        let search = first_failing(commits.len(), |_, _, index| {
+            tested.push(commits[index].summary());
+            Ok(outcome_from_summary(commits[index].summary()))
+        })
+        .expect("search failed");
+        assert_eq!(search, Search::Found(3));
+        assert_eq!(tested, ["passes 3", "untestable 5", "fails 4"]);
+
+        fs_err::remove_dir_all(&repo).expect("failed to remove temp dir");
+    }
+
+    #[test]
+    fn search_reports_every_candidate_when_only_untestable_commits_are_left() {
+        let outcomes = [
+            Outcome::Passes,
+            Outcome::Untestable,
+            Outcome::Untestable,
+            Outcome::Fails,
+            Outcome::Fails,
+        ];
+        let search = first_failing(outcomes.len(), |_, _, index| Ok(outcomes[index]))
+            .expect("search failed");
+        assert_eq!(search, Search::Ambiguous(1..=3));
+    }
+
+    #[test]
+    fn search_propagates_errors() {
+        let search = first_failing(3, |_, _, _| anyhow::bail!("cancelled"));
+        assert!(search.is_err());
+    }
+}
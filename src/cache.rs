@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context as _;
+
+/// Suffix of the cached zstd-compressed rustdoc JSON file of an entry.
+const JSON_SUFFIX: &str = ".json.zst";
+
+/// Suffix of rustdoc JSON files cached uncompressed by older versions.
+const LEGACY_JSON_SUFFIX: &str = ".json";
+
+/// Suffix of the cached `cargo metadata` file of an entry.
+const METADATA_SUFFIX: &str = ".metadata.json";
+
+/// The on-disk cache of generated rustdoc JSON, as populated by `check-release`.
+///
+/// Each entry is a `{slug}.json.zst` and `{slug}.metadata.json` pair, where the slug
+/// identifies the crate name, version, target and build settings of the rustdoc.
+#[derive(Debug, Clone)]
+pub struct RustdocCache {
+    dir: PathBuf,
+}
+
+/// A single rustdoc JSON artifact in the [`RustdocCache`].
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    slug: String,
+    crate_name: String,
+    version: String,
+    target: String,
+    size: u64,
+    modified: SystemTime,
+    files: Vec<PathBuf>,
+}
+
+impl CacheEntry {
+    /// The unique identifier of this entry within the cache.
+    pub fn slug(&self) -> &str {
+        &self.slug
+    }
+
+    /// The crate name, with non-alphanumeric characters replaced by `_`.
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+
+    /// The crate version, with non-alphanumeric characters replaced by `_`.
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// The target triple the rustdoc was generated for,
+    /// with non-alphanumeric characters replaced by `_`.
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Total size of the entry's files, in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// When the entry was last written to the cache.
+    pub fn modified(&self) -> SystemTime {
+        self.modified
+    }
+
+    /// Best-effort parse of the version, for ordering versions of the same crate.
+    fn parsed_version(&self) -> Option<semver::Version> {
+        let mut parts = self.version.split('_');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next()?.parse().ok()?;
+        let mut version = semver::Version::new(major, minor, patch);
+
+        // The slug doesn't distinguish pre-release from build metadata separators,
+        // so treat everything left over as a pre-release. This orders pre-releases
+        // before their release, which is what matters when keeping the newest versions.
+        let rest = parts.collect::<Vec<_>>();
+        if !rest.is_empty() {
+            version.pre = semver::Prerelease::new(&rest.join(".")).ok()?;
+        }
+        Some(version)
+    }
+}
+
+/// Which entries [`RustdocCache::prune`] removes.
+///
+/// Limits are applied in order: first entries older than the maximum age are removed,
+/// then all but the newest versions of each crate, and finally the least recently
+/// written entries until the cache fits within the maximum size.
+#[derive(Debug, Clone, Default)]
+pub struct PrunePolicy {
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    keep_versions: Option<NonZeroUsize>,
+}
+
+impl PrunePolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remove the least recently written entries until the cache is at most this many bytes.
+    pub fn set_max_size(&mut self, max_size: u64) -> &mut Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Remove entries written longer ago than this.
+    pub fn set_max_age(&mut self, max_age: Duration) -> &mut Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keep only entries for this many of the newest versions of each crate.
+    /// Entries for the same version with different targets or features are kept together.
+    pub fn set_keep_versions(&mut self, keep_versions: NonZeroUsize) -> &mut Self {
+        self.keep_versions = Some(keep_versions);
+        self
+    }
+}
+
+impl RustdocCache {
+    /// The cache in the given directory.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache used when neither the baseline nor the current crate is in a cargo project,
+    /// such as when checking a rustdoc JSON file against a registry baseline.
+    ///
+    /// Checks of cargo projects cache rustdoc in `target/semver-checks/cache` instead.
+    pub fn user_cache() -> anyhow::Result<Self> {
+        Ok(Self::new(crate::get_cache_dir()?.join("cache")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// All entries in the cache, ordered by slug.
+    pub fn entries(&self) -> anyhow::Result<Vec<CacheEntry>> {
+        let read_dir = match fs_err::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries: BTreeMap<String, CacheEntry> = BTreeMap::new();
+        for dir_entry in read_dir {
+            let dir_entry = dir_entry?;
+            let file_name = dir_entry.file_name();
+            let Some(file_name) = file_name.to_str() else {
+                continue;
+            };
+            // Skips in-progress or abandoned temporary files from `atomic_write`.
+            if file_name.starts_with('.') {
+                continue;
+            }
+            let Some(slug) = file_name
+                .strip_suffix(METADATA_SUFFIX)
+                .or_else(|| file_name.strip_suffix(JSON_SUFFIX))
+                .or_else(|| file_name.strip_suffix(LEGACY_JSON_SUFFIX))
+            else {
+                continue;
+            };
+
+            let file_metadata = dir_entry.metadata()?;
+            if !file_metadata.is_file() {
+                continue;
+            }
+            let modified = file_metadata.modified()?;
+
+            let entry = entries.entry(slug.to_owned()).or_insert_with(|| {
+                // Slugs are `{name}-{version}-{target}-{fingerprint}`,
+                // where the components themselves don't contain `-`.
+                let mut parts = slug.splitn(4, '-');
+                let crate_name = parts.next().unwrap_or_default().to_owned();
+                let version = parts.next().unwrap_or_default().to_owned();
+                let target = parts.next().unwrap_or_default().to_owned();
+                CacheEntry {
+                    slug: slug.to_owned(),
+                    crate_name,
+                    version,
+                    target,
+                    size: 0,
+                    modified,
+                    files: Vec::new(),
+                }
+            });
+            entry.size += file_metadata.len();
+            entry.modified = entry.modified.max(modified);
+            entry.files.push(dir_entry.path());
+        }
+
+        Ok(entries.into_values().collect())
+    }
+
+    /// Total size of all entries in the cache, in bytes.
+    pub fn size(&self) -> anyhow::Result<u64> {
+        Ok(self.entries()?.iter().map(CacheEntry::size).sum())
+    }
+
+    /// Remove entries according to the policy, returning the removed entries.
+    pub fn prune(&self, policy: &PrunePolicy) -> anyhow::Result<Vec<CacheEntry>> {
+        let mut kept = self.entries()?;
+        let mut removed = Vec::new();
+
+        if let Some(max_age) = policy.max_age {
+            let now = SystemTime::now();
+            let (old, recent): (Vec<_>, Vec<_>) = kept.into_iter().partition(|entry| {
+                now.duration_since(entry.modified)
+                    .is_ok_and(|age| age > max_age)
+            });
+            removed.extend(old);
+            kept = recent;
+        }
+
+        if let Some(keep_versions) = policy.keep_versions {
+            let mut by_crate: BTreeMap<String, Vec<CacheEntry>> = BTreeMap::new();
+            for entry in kept {
+                by_crate
+                    .entry(entry.crate_name.clone())
+                    .or_default()
+                    .push(entry);
+            }
+
+            kept = Vec::new();
+            for (_, mut entries) in by_crate {
+                // Newest first: by version where we can parse it, then by write time.
+                entries.sort_by(|a, b| {
+                    b.parsed_version()
+                        .cmp(&a.parsed_version())
+                        .then(b.modified.cmp(&a.modified))
+                });
+                let mut newest_versions = Vec::new();
+                for entry in entries {
+                    if newest_versions.contains(&entry.version) {
+                        kept.push(entry);
+                    } else if newest_versions.len() < keep_versions.get() {
+                        newest_versions.push(entry.version.clone());
+                        kept.push(entry);
+                    } else {
+                        removed.push(entry);
+                    }
+                }
+            }
+        }
+
+        if let Some(max_size) = policy.max_size {
+            // Oldest first, so we remove the least recently written entries.
+            kept.sort_by_key(|entry| entry.modified);
+            let mut total_size: u64 = kept.iter().map(CacheEntry::size).sum();
+            for entry in kept {
+                if total_size <= max_size {
+                    break;
+                }
+                total_size -= entry.size;
+                removed.push(entry);
+            }
+        }
+
+        for entry in &removed {
+            remove_entry(entry)?;
+        }
+        removed.sort_by(|a, b| a.slug.cmp(&b.slug));
+        Ok(removed)
+    }
+
+    /// Remove everything in the cache, including abandoned temporary files.
+    /// Returns the number of bytes freed.
+    pub fn clear(&self) -> anyhow::Result<u64> {
+        let read_dir = match fs_err::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut freed = 0;
+        for dir_entry in read_dir {
+            let dir_entry = dir_entry?;
+            let file_metadata = dir_entry.metadata()?;
+            if file_metadata.is_file() {
+                fs_err::remove_file(dir_entry.path())?;
+                freed += file_metadata.len();
+            }
+        }
+        Ok(freed)
+    }
+}
+
+fn remove_entry(entry: &CacheEntry) -> anyhow::Result<()> {
+    for file in &entry.files {
+        match fs_err::remove_file(file) {
+            Ok(()) => {}
+            // Another process pruned it concurrently, which is just as good.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to remove cache entry {}", entry.slug));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+    use std::path::PathBuf;
+    use std::time::{Duration, SystemTime};
+
+    use super::{PrunePolicy, RustdocCache};
+
+    struct TestCache {
+        cache: RustdocCache,
+    }
+
+    impl TestCache {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "cargo-semver-checks-{name}-{}-{:032x}",
+                std::process::id(),
+                rand::random::<u128>(),
+            ));
+            fs_err::create_dir_all(&dir).expect("failed to create test cache dir");
+            Self {
+                cache: RustdocCache::new(dir),
+            }
+        }
+
+        fn add(&self, slug: &str, size: usize, age: Duration) -> PathBuf {
+            let json = self.cache.dir().join(format!("{slug}.json.zst"));
+            let metadata = self.cache.dir().join(format!("{slug}.metadata.json"));
+            fs_err::write(&json, vec![b'x'; size]).expect("failed to write entry");
+            fs_err::write(&metadata, b"{}").expect("failed to write entry metadata");
+
+            let modified = SystemTime::now() - age;
+            for path in [&json, &metadata] {
+                std::fs::File::options()
+                    .write(true)
+                    .open(path)
+                    .and_then(|file| file.set_modified(modified))
+                    .expect("failed to set entry mtime");
+            }
+            json
+        }
+
+        fn slugs(&self) -> Vec<String> {
+            self.cache
+                .entries()
+                .expect("failed to list entries")
+                .iter()
+                .map(|entry| entry.slug().to_owned())
+                .collect()
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = fs_err::remove_dir_all(self.cache.dir());
+        }
+    }
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    #[test]
+    fn entries_pair_json_and_metadata_files() {
+        let test = TestCache::new("cache-entries");
+        test.add("serde-1_0_0-x86_64_unknown_linux_gnu-abc", 10, DAY);
+        fs_err::write(test.cache.dir().join(".serde.json.tmp-1-2"), b"partial")
+            .expect("failed to write temp file");
+
+        let entries = test.cache.entries().expect("failed to list entries");
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.crate_name(), "serde");
+        assert_eq!(entry.version(), "1_0_0");
+        assert_eq!(entry.target(), "x86_64_unknown_linux_gnu");
+        assert_eq!(entry.size(), 12);
+        assert_eq!(test.cache.size().expect("failed to get size"), 12);
+
+        assert_eq!(test.cache.clear().expect("failed to clear"), 19);
+        assert!(test.slugs().is_empty());
+    }
+
+    #[test]
+    fn uncompressed_entries_are_pruned() {
+        let test = TestCache::new("cache-legacy-entries");
+        let json = test.add("serde-1_0_0-t-0", 10, 30 * DAY);
+        fs_err::remove_file(&json).expect("failed to remove entry");
+        // Older versions cached rustdoc JSON uncompressed.
+        fs_err::write(test.cache.dir().join("serde-1_0_0-t-0.json"), b"{}")
+            .expect("failed to write entry");
+
+        let entries = test.cache.entries().expect("failed to list entries");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].size(), 4);
+
+        test.cache
+            .prune(PrunePolicy::new().set_max_size(0))
+            .expect("failed to prune");
+        assert!(test.slugs().is_empty());
+        assert_eq!(
+            fs_err::read_dir(test.cache.dir())
+                .expect("failed to read cache dir")
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn prune_by_age_and_size() {
+        let test = TestCache::new("cache-prune-age-size");
+        test.add("a-1_0_0-t-0", 100, 30 * DAY);
+        test.add("b-1_0_0-t-0", 100, 3 * DAY);
+        test.add("c-1_0_0-t-0", 100, 2 * DAY);
+        test.add("d-1_0_0-t-0", 100, DAY);
+
+        let removed = test
+            .cache
+            .prune(PrunePolicy::new().set_max_age(7 * DAY).set_max_size(250))
+            .expect("failed to prune");
+        let removed = removed.iter().map(|entry| entry.slug()).collect::<Vec<_>>();
+        assert_eq!(removed, ["a-1_0_0-t-0", "b-1_0_0-t-0"]);
+        assert_eq!(test.slugs(), ["c-1_0_0-t-0", "d-1_0_0-t-0"]);
+    }
+
+    #[test]
+    fn prune_keeps_newest_versions_per_crate() {
+        let test = TestCache::new("cache-prune-versions");
+        // Written out of version order, to check that versions win over write times.
+        test.add("serde-1_0_10-t-0", 1, 3 * DAY);
+        test.add("serde-1_0_10-u-0", 1, 3 * DAY);
+        test.add("serde-1_0_9-t-0", 1, DAY);
+        test.add("serde-1_0_2-t-0", 1, 2 * DAY);
+        test.add("serde-1_0_11_alpha_1-t-0", 1, 2 * DAY);
+        test.add("syn-2_0_0-t-0", 1, 10 * DAY);
+
+        let keep = NonZeroUsize::new(2).expect("nonzero");
+        test.cache
+            .prune(PrunePolicy::new().set_keep_versions(keep))
+            .expect("failed to prune");
+        assert_eq!(
+            test.slugs(),
+            [
+                "serde-1_0_10-t-0",
+                "serde-1_0_10-u-0",
+                "serde-1_0_11_alpha_1-t-0",
+                "syn-2_0_0-t-0",
+            ]
+        );
+    }
+}
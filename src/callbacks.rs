@@ -1,9 +1,11 @@
 use std::collections::BTreeMap;
 
 use crate::GlobalConfig;
+use crate::timings::{CrateTimings, TimingPhase};
 
 pub(crate) struct Callbacks<'a> {
     config: &'a mut GlobalConfig,
+    timings: &'a mut CrateTimings,
 
     // (crate_name, version, is_baseline) keys
     generate_starts: BTreeMap<(String, String, bool), std::time::Instant>,
@@ -11,9 +13,10 @@ pub(crate) struct Callbacks<'a> {
 }
 
 impl<'a> Callbacks<'a> {
-    pub(crate) fn new(config: &'a mut GlobalConfig) -> Self {
+    pub(crate) fn new(config: &'a mut GlobalConfig, timings: &'a mut CrateTimings) -> Self {
         Self {
             config,
+            timings,
             generate_starts: Default::default(),
             parse_starts: Default::default(),
         }
@@ -42,11 +45,14 @@ impl<'a> crate::data_generation::ProgressCallbacks<'a> for Callbacks<'a> {
             .generate_starts
             .remove(&(crate_name.to_owned(), version.to_owned(), is_baseline))
             .expect("success on generation task that never started");
+        let elapsed = start.elapsed();
+        self.timings
+            .record(TimingPhase::GenerateRustdoc, kind, elapsed);
 
         // Ignore terminal printing failures.
         let _ = self.config.shell_status(
             "Built",
-            format_args!("[{:>8.3}s] ({kind})", start.elapsed().as_secs_f32()),
+            format_args!("[{:>8.3}s] ({kind})", elapsed.as_secs_f32()),
         );
     }
 
@@ -74,7 +80,7 @@ impl<'a> crate::data_generation::ProgressCallbacks<'a> for Callbacks<'a> {
 
     fn parse_rustdoc_success(
         &mut self,
-        _cached: bool,
+        cached: bool,
         crate_name: &str,
         version: &str,
         is_baseline: bool,
@@ -84,11 +90,19 @@ impl<'a> crate::data_generation::ProgressCallbacks<'a> for Callbacks<'a> {
             .parse_starts
             .remove(&(crate_name.to_owned(), version.to_owned(), is_baseline))
             .expect("success on parse task that never started");
+        let elapsed = start.elapsed();
+        let label = if cached {
+            format!("{kind}, cached")
+        } else {
+            kind.to_owned()
+        };
+        self.timings
+            .record(TimingPhase::ParseRustdoc, label, elapsed);
 
         // Ignore terminal printing failures.
         let _ = self.config.shell_status(
             "Parsed",
-            format_args!("[{:>8.3}s] ({kind})", start.elapsed().as_secs_f32()),
+            format_args!("[{:>8.3}s] ({kind})", elapsed.as_secs_f32()),
         );
     }
 
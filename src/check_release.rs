@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::io::Write as _;
+use std::rc::Rc;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -14,12 +15,15 @@ use trustfall::{FieldValue, TransparentValue};
 
 use crate::data_generation::DataStorage;
 use crate::query::{
-    ActualSemverUpdate, LintLevel, OverrideStack, RequiredSemverUpdate, SemverQuery,
+    ActualSemverUpdate, LintLevel, LintRegistry, OverrideStack, RequiredSemverUpdate, SemverQuery,
 };
-use crate::witness_gen;
+use crate::query_limits::{Interruption, QueryLimits, run_query_with_limits};
+use crate::timings::{CrateTimings, TimingPhase};
 use crate::{
-    Bumps, CrateReport, GlobalConfig, ReleaseType, RustdocIndexingMode, WitnessGeneration,
+    Bumps, CancellationToken, CrateReport, GlobalConfig, PrereleasePolicy, ReleaseType,
+    RustdocIndexingMode, VersioningPolicy, WitnessGeneration, WitnessStatistics,
 };
+use crate::{feature_gating, witness_gen};
 
 /// Represents a change between two semantic versions
 #[derive(Debug, PartialEq, Eq)]
@@ -57,7 +61,7 @@ fn classify_minimum_semver_version_change(
                 kind: VersionChangeKind::Minimum,
             };
         }
-        return get_minimum_version_change(&current_version);
+        return get_minimum_version_change(&current_version, VersioningPolicy::Cargo);
     }
 
     // From the cargo reference:
@@ -104,7 +108,81 @@ fn classify_minimum_semver_version_change(
     }
 }
 
-fn get_minimum_version_change(version: &semver::Version) -> VersionChange {
+/// Classifies the change between two versions like [`classify_minimum_semver_version_change`],
+/// except that changes after a pre-release of the same version are as the pre-release policy
+/// allows, and other changes are as the versioning policy allows.
+fn classify_version_change_with_policy(
+    baseline_version: &str,
+    current_version: &str,
+    prerelease_policy: PrereleasePolicy,
+    versioning_policy: VersioningPolicy,
+) -> VersionChange {
+    let change = classify_minimum_semver_version_change(baseline_version, current_version);
+
+    let baseline = semver::Version::parse(baseline_version).expect("baseline not a valid version");
+    let current = semver::Version::parse(current_version).expect("current not a valid version");
+    let same_version = (baseline.major, baseline.minor, baseline.patch)
+        == (current.major, current.minor, current.patch);
+    // Only releases at or after the baseline pre-release: a later pre-release
+    // of the same version, the final release, or the same pre-release.
+    if same_version
+        && !baseline.pre.is_empty()
+        && (current.pre.is_empty() || current.pre >= baseline.pre)
+        && let Some(level) = prerelease_policy.update_after(&baseline.pre)
+    {
+        return VersionChange {
+            level,
+            kind: change.kind,
+        };
+    }
+    if baseline.cmp_precedence(&current) == Ordering::Equal && baseline.pre.is_empty() {
+        return get_minimum_version_change(&current, versioning_policy);
+    }
+    VersionChange {
+        level: versioning_policy.allowed_update(change.level, &current),
+        kind: change.kind,
+    }
+}
+
+/// The change allowed by an explicitly specified release type.
+///
+/// A pre-release follows the pre-release policy if the baseline is a pre-release too.
+/// Other release types are as the versioning policy allows, as of the current version
+/// if known, or else the baseline version.
+fn classify_release_type_with_policy(
+    release_type: ReleaseType,
+    baseline_version: Option<&str>,
+    current_version: Option<&str>,
+    prerelease_policy: PrereleasePolicy,
+    versioning_policy: VersioningPolicy,
+) -> VersionChange {
+    let level = match release_type {
+        ReleaseType::Prerelease => baseline_version
+            .and_then(|baseline| semver::Version::parse(baseline).ok())
+            .and_then(|baseline| prerelease_policy.update_after(&baseline.pre))
+            .unwrap_or(ReleaseType::Prerelease.into()),
+        release_type => {
+            let level = release_type.into();
+            match current_version
+                .or(baseline_version)
+                .and_then(|version| semver::Version::parse(version).ok())
+            {
+                Some(version) => versioning_policy.allowed_update(level, &version),
+                None => level,
+            }
+        }
+    };
+    VersionChange {
+        level,
+        kind: VersionChangeKind::Actual,
+    }
+}
+
+/// The change allowed by the smallest version bump after `version`.
+fn get_minimum_version_change(
+    version: &semver::Version,
+    versioning_policy: VersioningPolicy,
+) -> VersionChange {
     let update = match (version.major, version.minor) {
         // For 0.0.z: Minimum next change must be major
         (0, 0) => ActualSemverUpdate::Major,
@@ -115,7 +193,7 @@ fn get_minimum_version_change(version: &semver::Version) -> VersionChange {
     };
 
     VersionChange {
-        level: update,
+        level: versioning_policy.allowed_update(update, version),
         kind: VersionChangeKind::Minimum,
     }
 }
@@ -130,6 +208,9 @@ pub(crate) struct LintResult {
     /// Applied `OverrideStack`
     pub effective_required_update: RequiredSemverUpdate,
     pub effective_lint_level: LintLevel,
+    /// The configurations (build target and features) under which each of `query_results`
+    /// was found, in the same order. Empty unless several configurations were checked.
+    pub result_configurations: Vec<Vec<String>>,
 }
 
 /// Internal per-crate check result that is still pending post-processing
@@ -145,7 +226,125 @@ pub(crate) struct PendingCrateReport {
     pub(crate) report: CrateReport,
     /// Internal witness bookkeeping that still needs to be finalized into
     /// retained run artifacts and manifests after all crates have been checked.
-    pub(crate) witness_run_report: witness_gen::WitnessRunReport,
+    /// There's one per configuration the crate was checked in.
+    pub(crate) witness_run_reports: Vec<witness_gen::WitnessRunReport>,
+}
+
+impl PendingCrateReport {
+    /// Combine the reports from checking one crate in each of several configurations,
+    /// such as build targets or feature sets, into a single report.
+    /// Identical query results are deduplicated, recording the configurations they occur in.
+    pub(crate) fn merge_configurations(
+        reports: Vec<(String, PendingCrateReport)>,
+    ) -> PendingCrateReport {
+        let mut lint_results: Vec<LintResult> = Vec::new();
+        let mut checks_duration = Duration::ZERO;
+        let mut selected_checks = 0;
+        let mut skipped_checks = usize::MAX;
+        let mut detected_bump: Option<ActualSemverUpdate> = None;
+        let mut witness_statistics: Option<WitnessStatistics> = None;
+        let mut timings = CrateTimings::new();
+        let mut api_unchanged = true;
+        let mut witness_run_reports = Vec::new();
+
+        for (configuration, pending) in reports {
+            let report = pending.report;
+            for result in report.lint_results {
+                let merged = match lint_results
+                    .iter_mut()
+                    .find(|merged| merged.semver_query.id == result.semver_query.id)
+                {
+                    Some(merged) => {
+                        merged.query_duration += result.query_duration;
+                        merged
+                    }
+                    None => {
+                        lint_results.push(LintResult {
+                            query_results: Vec::new(),
+                            result_configurations: Vec::new(),
+                            ..result
+                        });
+                        lint_results.last_mut().expect("just pushed a result")
+                    }
+                };
+                for query_result in result.query_results {
+                    match merged
+                        .query_results
+                        .iter()
+                        .position(|existing| *existing == query_result)
+                    {
+                        Some(index) => {
+                            merged.result_configurations[index].push(configuration.clone())
+                        }
+                        None => {
+                            merged.query_results.push(query_result);
+                            merged
+                                .result_configurations
+                                .push(vec![configuration.clone()]);
+                        }
+                    }
+                }
+            }
+
+            checks_duration += report.checks_duration;
+            selected_checks = selected_checks.max(report.selected_checks);
+            skipped_checks = skipped_checks.min(report.skipped_checks);
+            // Configurations may check against different baseline versions. Findings from each
+            // required more than its own bump, so they also require more than the smallest one.
+            detected_bump = Some(match detected_bump {
+                Some(merged) => merged.smaller(report.detected_bump),
+                None => report.detected_bump,
+            });
+            witness_statistics = match (witness_statistics, report.witness_statistics) {
+                (Some(merged), Some(statistics)) => Some(merged.combined_with(&statistics)),
+                (merged, statistics) => merged.or(statistics),
+            };
+            timings.extend_for_configuration(report.timings, &configuration);
+            api_unchanged &= report.api_unchanged;
+            witness_run_reports.extend(pending.witness_run_reports);
+        }
+
+        let (required_bumps, suggested_bumps) = count_bumps(&lint_results);
+        PendingCrateReport {
+            report: CrateReport {
+                lint_results,
+                checks_duration,
+                selected_checks,
+                skipped_checks,
+                required_bumps,
+                suggested_bumps,
+                detected_bump: detected_bump.unwrap_or(ActualSemverUpdate::NotChanged),
+                witness_statistics,
+                timings,
+                api_unchanged,
+            },
+            witness_run_reports,
+        }
+    }
+
+    /// Add the result of a check that ran separately from the lints,
+    /// such as the one for newly feature-gated items, along with the time it took.
+    pub(crate) fn add_lint_result(&mut self, lint_result: LintResult, timings: CrateTimings) {
+        let report = &mut self.report;
+        report.checks_duration += lint_result.query_duration;
+        // The check isn't in the lint registry, so it adds to the selected checks.
+        report.selected_checks += 1;
+        report.api_unchanged &= lint_result.query_results.is_empty();
+        report
+            .timings
+            .extend_for_configuration(timings, "default features");
+        report.lint_results.push(lint_result);
+        (report.required_bumps, report.suggested_bumps) = count_bumps(&report.lint_results);
+    }
+}
+
+/// The source file that implements a lint, relative to the repository root.
+fn implementation_path(semver_query: &SemverQuery) -> String {
+    if semver_query.id == feature_gating::CHECK_ID {
+        "src/feature_gating.rs".to_string()
+    } else {
+        format!("src/lints/{}.ron", semver_query.id)
+    }
 }
 
 /// Helper function to print details about a triggered lint.
@@ -159,14 +358,14 @@ fn print_triggered_lint(
 
     if let Some(ref_link) = semver_query.reference_link.as_deref() {
         config.log_at_lint_level(lint_level, |config| {
-            writeln!(config.stdout(), "{}Description:{}\n{}\n{:>12} {}\n{:>12} https://github.com/obi1kenobi/cargo-semver-checks/tree/v{}/src/lints/{}.ron\n",
+            writeln!(config.stdout(), "{}Description:{}\n{}\n{:>12} {}\n{:>12} https://github.com/obi1kenobi/cargo-semver-checks/tree/v{}/{}\n",
                 Style::new().bold(), Reset,
                 semver_query.error_message,
                 "ref:",
                 ref_link,
                 "impl:",
                 crate_version!(),
-                semver_query.id,
+                implementation_path(semver_query),
             )?;
             Ok(())
         })?;
@@ -174,13 +373,13 @@ fn print_triggered_lint(
         config.log_at_lint_level(lint_level, |config| {
             writeln!(
                 config.stdout(),
-                "{}Description:{}\n{}\n{:>12} https://github.com/obi1kenobi/cargo-semver-checks/tree/v{}/src/lints/{}.ron",
+                "{}Description:{}\n{}\n{:>12} https://github.com/obi1kenobi/cargo-semver-checks/tree/v{}/{}",
                 Style::new().bold(),
                 Reset,
                 semver_query.error_message,
                 "impl:",
                 crate_version!(),
-                semver_query.id,
+                implementation_path(semver_query),
             )?;
             Ok(())
         })?;
@@ -196,7 +395,7 @@ fn print_triggered_lint(
         Ok(())
     })?;
 
-    for semver_violation_result in &lint_result.query_results {
+    for (result_index, semver_violation_result) in lint_result.query_results.iter().enumerate() {
         let pretty_result: BTreeMap<&str, TransparentValue> = semver_violation_result
             .iter()
             .map(|(k, v)| (&**k, v.clone().into()))
@@ -212,6 +411,7 @@ fn print_triggered_lint(
                 writeln!(config.stdout(), "  {message}")?;
                 Ok(())
             })?;
+            print_result_configurations(config, lint_result, result_index)?;
 
             config.log_extra_verbose(|config| {
                 let serde_pretty =
@@ -235,6 +435,7 @@ fn print_triggered_lint(
                 )?;
                 Ok(())
             })?;
+            print_result_configurations(config, lint_result, result_index)?;
         }
 
         if let Some(witness) = &semver_query.witness
@@ -262,23 +463,83 @@ fn print_triggered_lint(
     Ok(())
 }
 
-pub(super) struct CheckReleaseSettings {
+/// Note which configurations a query result was found in, if several were checked.
+fn print_result_configurations(
+    config: &mut GlobalConfig,
+    lint_result: &LintResult,
+    result_index: usize,
+) -> anyhow::Result<()> {
+    let Some(configurations) = lint_result.result_configurations.get(result_index) else {
+        return Ok(());
+    };
+    config.log_at_lint_level(lint_result.effective_lint_level, |config| {
+        writeln!(
+            config.stdout(),
+            "    found in: {}",
+            configurations.join("; ")
+        )?;
+        Ok(())
+    })
+}
+
+/// Count the lints that found semver violations, by required and suggested bump.
+fn count_bumps(lint_results: &[LintResult]) -> (Bumps, Bumps) {
+    let mut required_bumps = Bumps { major: 0, minor: 0 };
+    let mut suggested_bumps = Bumps { major: 0, minor: 0 };
+    for result in lint_results {
+        if !result.query_results.is_empty() {
+            let bump_stats = match result.effective_lint_level {
+                LintLevel::Deny => &mut required_bumps,
+                LintLevel::Warn => &mut suggested_bumps,
+                LintLevel::Allow => unreachable!(
+                    "`LintLevel::Allow` lint was unexpectedly not skipped: {:?}",
+                    result.semver_query
+                ),
+            };
+            match result.effective_required_update {
+                RequiredSemverUpdate::Major => bump_stats.major += 1,
+                RequiredSemverUpdate::Minor => bump_stats.minor += 1,
+            };
+        }
+    }
+    (required_bumps, suggested_bumps)
+}
+
+pub(super) struct CheckReleaseSettings<'a> {
     pub(super) release_type: Option<ReleaseType>,
+    pub(super) prerelease_policy: PrereleasePolicy,
+    pub(super) versioning_policy: VersioningPolicy,
     pub(super) rustdoc_indexing_mode: RustdocIndexingMode,
+    /// Whether to skip all lints when the baseline and current public APIs are identical.
+    pub(super) skip_unchanged_api: bool,
+    /// Describes the configuration (build target and features) being checked,
+    /// when checking several of them. Only used to tell them apart in the output.
+    pub(super) reported_configuration: Option<&'a str>,
+    /// How long each lint may run. Unset means no limit.
+    pub(super) query_timeout: Option<Duration>,
+    pub(super) cancellation: Option<&'a CancellationToken>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub(super) fn run_check_release(
     config: &mut GlobalConfig,
     data_storage: &DataStorage,
     crate_name: &str,
-    settings: CheckReleaseSettings,
+    settings: CheckReleaseSettings<'_>,
     overrides: &OverrideStack,
     witness_generation: &WitnessGeneration,
     witness_data: witness_gen::WitnessGenerationData,
+    mut timings: CrateTimings,
 ) -> anyhow::Result<PendingCrateReport> {
     let CheckReleaseSettings {
         release_type,
+        prerelease_policy,
+        versioning_policy,
         rustdoc_indexing_mode,
+        skip_unchanged_api,
+        reported_configuration,
+        query_timeout,
+        cancellation,
     } = settings;
 
     let current_version = data_storage.current_crate().crate_version();
@@ -286,15 +547,21 @@ pub(super) fn run_check_release(
 
     let version_change = match release_type {
         // Case 1: User explicitly specified a release type
-        Some(rt) => VersionChange {
-            level: rt.into(),
-            kind: VersionChangeKind::Actual,
-        },
+        Some(release_type) => classify_release_type_with_policy(
+            release_type,
+            baseline_version,
+            current_version,
+            prerelease_policy,
+            versioning_policy,
+        ),
         // Case 2: Try to determine from version strings
         None => match (baseline_version, current_version) {
-            (Some(baseline), Some(current)) => {
-                classify_minimum_semver_version_change(baseline, current)
-            }
+            (Some(baseline), Some(current)) => classify_version_change_with_policy(
+                baseline,
+                current,
+                prerelease_policy,
+                versioning_policy,
+            ),
             // Case 3: Fall back to assuming no change
             _ => {
                 config
@@ -326,29 +593,74 @@ pub(super) fn run_check_release(
         VersionChangeKind::Minimum => format!("no change; {assume}{change}"),
     };
 
-    let index_storage = data_storage.create_indexes(rustdoc_indexing_mode);
-    let adapter = index_storage.create_adapter();
+    let configuration_note = reported_configuration
+        .map(|configuration| format!(" [{configuration}]"))
+        .unwrap_or_default();
+    // Witness artifacts are kept per crate, so tell apart the ones for each configuration.
+    // The name ends up in artifact paths, so keep the configuration's spaces and commas out.
+    let witness_crate_name = match reported_configuration {
+        Some(configuration) => format!("{crate_name}-{}", crate::util::slugify(configuration)),
+        None => crate_name.to_string(),
+    };
 
-    let mut queries_to_run = SemverQuery::all_queries();
-    let all_queries_len = queries_to_run.len();
-    queries_to_run.retain(|_, query| {
-        !version_change
-            .level
-            .supports_requirement(overrides.effective_required_update(query))
-            && overrides.effective_lint_level(query) > LintLevel::Allow
-    });
-    let selected_checks = queries_to_run.len();
-    let skipped_checks = all_queries_len - selected_checks;
+    let lint_registry = LintRegistry::get();
+    let all_queries_len = lint_registry.queries().len();
 
     config.shell_status(
         "Checking",
         format_args!(
-            "{crate_name} v{} -> v{} ({})",
+            "{crate_name} v{} -> v{} ({}){configuration_note}",
             baseline_version.unwrap_or("unknown"),
             current_version.unwrap_or("unknown"),
             change_message
         ),
     )?;
+
+    if skip_unchanged_api && data_storage.api_unchanged() {
+        // Identical public APIs can't have semver violations, so skip all lints.
+        let report = CrateReport {
+            lint_results: Vec::new(),
+            checks_duration: Duration::ZERO,
+            selected_checks: 0,
+            skipped_checks: all_queries_len,
+            required_bumps: Bumps { major: 0, minor: 0 },
+            suggested_bumps: Bumps { major: 0, minor: 0 },
+            detected_bump: version_change.level,
+            witness_statistics: None,
+            timings,
+            api_unchanged: true,
+        };
+        return Ok(PendingCrateReport {
+            report,
+            witness_run_reports: vec![witness_gen::WitnessRunReport::empty(
+                &witness_crate_name,
+                witness_data.target_dir().to_owned(),
+            )],
+        });
+    }
+
+    let indexing_start = Instant::now();
+    let index_storage = data_storage.create_indexes(rustdoc_indexing_mode);
+    let adapter = index_storage.create_adapter();
+    timings.record(
+        TimingPhase::BuildIndexes,
+        "baseline and current",
+        indexing_start.elapsed(),
+    );
+
+    let queries_to_run: Vec<&SemverQuery> = lint_registry
+        .queries()
+        .values()
+        .filter(|query| {
+            !version_change
+                .level
+                .supports_requirement(overrides.effective_required_update(query))
+                && overrides.effective_lint_level(query) > LintLevel::Allow
+        })
+        .collect();
+    let selected_checks = queries_to_run.len();
+    let skipped_checks = all_queries_len - selected_checks;
+
     config
         .log_verbose(|config| {
             let current_num_threads = rayon::current_num_threads();
@@ -372,53 +684,79 @@ pub(super) fn run_check_release(
     let checks_start_instant = Instant::now();
     let mut lint_results = queries_to_run
         .into_par_iter()
-        .map(|(_, semver_query)| {
+        .map(|semver_query| {
             let start_instant = std::time::Instant::now();
+            let compiled_query = lint_registry.compiled_query(&adapter, semver_query)?;
+            // The limits are checked as the query runs, since a single result
+            // can take arbitrarily long to produce.
+            let limits = Rc::new(QueryLimits::new(
+                query_timeout.map(|query_timeout| start_instant + query_timeout),
+                cancellation.cloned(),
+            ));
             // trustfall::execute_query(...) -> dyn Iterator (without Send)
             // thus the result must be collect()'ed
-            let query_results = adapter
-                .run_query(&semver_query.query, semver_query.arguments.clone())?
-                .collect_vec();
+            let query_results: Vec<_> = run_query_with_limits(
+                &adapter,
+                compiled_query,
+                semver_query.arguments.clone(),
+                &limits,
+            )?
+            .collect();
+            match limits.interruption() {
+                None => {}
+                Some(Interruption::Cancelled) => anyhow::bail!(
+                    "check was cancelled while running lint `{}` on crate \
+                     {crate_name}{configuration_note}",
+                    semver_query.id,
+                ),
+                Some(Interruption::TimedOut) => anyhow::bail!(
+                    "lint `{}` ran for longer than its {}s time budget on crate \
+                     {crate_name}{configuration_note}\n\n\
+                     HELP: pass a longer `--query-timeout`, or allow the lint \
+                     in [package.metadata.cargo-semver-checks.lints] to skip it",
+                    semver_query.id,
+                    query_timeout
+                        .expect("queries only time out with a timeout")
+                        .as_secs(),
+                ),
+            }
             let query_duration = start_instant.elapsed();
             Ok(LintResult {
-                effective_required_update: overrides.effective_required_update(&semver_query),
-                effective_lint_level: overrides.effective_lint_level(&semver_query),
-                semver_query,
+                effective_required_update: overrides.effective_required_update(semver_query),
+                effective_lint_level: overrides.effective_lint_level(semver_query),
+                semver_query: semver_query.clone(),
                 query_duration,
                 query_results,
+                result_configurations: Vec::new(),
             })
         })
         .collect::<anyhow::Result<Vec<_>>>()?;
+    for result in &lint_results {
+        timings.record(
+            TimingPhase::RunQuery,
+            result.semver_query.id.as_str(),
+            result.query_duration,
+        );
+    }
 
+    let witness_start = Instant::now();
     let witness_run_report = witness_gen::run_witness_checks(
         config,
         witness_generation,
         witness_data,
-        crate_name,
+        &witness_crate_name,
         &adapter,
         &mut lint_results,
     );
+    timings.record(
+        TimingPhase::RunWitnesses,
+        "all lints",
+        witness_start.elapsed(),
+    );
 
     let checks_duration = checks_start_instant.elapsed();
 
-    let mut required_bumps = Bumps { major: 0, minor: 0 };
-    let mut suggested_bumps = Bumps { major: 0, minor: 0 };
-    for result in &lint_results {
-        if !result.query_results.is_empty() {
-            let bump_stats = match result.effective_lint_level {
-                LintLevel::Deny => &mut required_bumps,
-                LintLevel::Warn => &mut suggested_bumps,
-                LintLevel::Allow => unreachable!(
-                    "`LintLevel::Allow` lint was unexpectedly not skipped: {:?}",
-                    result.semver_query
-                ),
-            };
-            match result.effective_required_update {
-                RequiredSemverUpdate::Major => bump_stats.major += 1,
-                RequiredSemverUpdate::Minor => bump_stats.minor += 1,
-            };
-        }
-    }
+    let (required_bumps, suggested_bumps) = count_bumps(&lint_results);
 
     let report = CrateReport {
         lint_results,
@@ -429,16 +767,17 @@ pub(super) fn run_check_release(
         suggested_bumps,
         detected_bump: version_change.level,
         witness_statistics: witness_run_report.statistics.clone(),
+        timings,
+        api_unchanged: false,
     };
 
-    print_report(config, witness_generation, &report)?;
     Ok(PendingCrateReport {
         report,
-        witness_run_report,
+        witness_run_reports: vec![witness_run_report],
     })
 }
 
-fn print_report(
+pub(super) fn print_report(
     config: &mut GlobalConfig,
     witness_generation: &WitnessGeneration,
     report: &CrateReport,
@@ -624,6 +963,25 @@ fn print_report(
                 )?;
             }
         }
+    } else if report.api_unchanged {
+        config
+            .shell_print(
+                "Skipped",
+                format_args!(
+                    "all {} checks: no changes to the public API since the baseline",
+                    report.skipped_checks,
+                ),
+                Color::Ansi(AnsiColor::Green),
+                true,
+            )
+            .expect("print failed");
+
+        config.shell_print(
+            "Summary",
+            "no semver update required",
+            Color::Ansi(AnsiColor::Green),
+            true,
+        )?;
     } else {
         config
             .shell_print(
@@ -921,6 +1279,191 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn classify_pre_with_policy() {
+        let classify = |baseline, current, policy| {
+            classify_version_change_with_policy(baseline, current, policy, VersioningPolicy::Cargo)
+                .level
+        };
+
+        assert_eq!(
+            classify(
+                "2.0.0-alpha.1",
+                "2.0.0-alpha.2",
+                PrereleasePolicy::BreakingAllowed
+            ),
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify(
+                "2.0.0-alpha.1",
+                "2.0.0-alpha.2",
+                PrereleasePolicy::BreakingInAlpha
+            ),
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify(
+                "2.0.0-beta.1",
+                "2.0.0-rc.1",
+                PrereleasePolicy::BreakingInAlpha
+            ),
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify("2.0.0-alpha.1", "2.0.0", PrereleasePolicy::Compatible),
+            ActualSemverUpdate::Minor
+        );
+        // Moving to a new version is classified by the version numbers as usual.
+        assert_eq!(
+            classify("1.4.0", "2.0.0-alpha.1", PrereleasePolicy::Compatible),
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify("2.0.0-rc.1", "2.1.0-alpha.1", PrereleasePolicy::Compatible),
+            ActualSemverUpdate::Minor
+        );
+    }
+
+    #[test]
+    fn classify_same_pre_with_policy() {
+        let expected = VersionChange {
+            level: ActualSemverUpdate::Minor,
+            kind: VersionChangeKind::Minimum,
+        };
+        let actual = classify_version_change_with_policy(
+            "2.0.0-beta.1",
+            "2.0.0-beta.1",
+            PrereleasePolicy::BreakingInAlpha,
+            VersioningPolicy::Cargo,
+        );
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn classify_with_versioning_policy() {
+        let classify = |baseline, current, policy| {
+            classify_version_change_with_policy(
+                baseline,
+                current,
+                PrereleasePolicy::BreakingAllowed,
+                policy,
+            )
+        };
+
+        assert_eq!(
+            classify("0.3.1", "0.3.2", VersioningPolicy::StrictZeroVer).level,
+            ActualSemverUpdate::Patch
+        );
+        assert_eq!(
+            classify("0.3.1", "0.4.0", VersioningPolicy::StrictZeroVer).level,
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify("1.3.1", "1.4.0", VersioningPolicy::StrictZeroVer).level,
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify("1.3.1", "1.4.0", VersioningPolicy::Conservative).level,
+            ActualSemverUpdate::Patch
+        );
+        assert_eq!(
+            classify("2024.3.1", "2025.1.0", VersioningPolicy::Conservative).level,
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify("2025.3.1", "2025.3.2", VersioningPolicy::Calendar).level,
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify("2025.3.1", "2025.4.0", VersioningPolicy::Calendar).level,
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify("2025.3.1", "2026.1.0", VersioningPolicy::Calendar).level,
+            ActualSemverUpdate::Major
+        );
+        // Breaking changes are only allowed where Cargo's rules allow them.
+        assert_eq!(
+            classify("0.3.1", "0.3.2", VersioningPolicy::Calendar).level,
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify("0.0.1", "0.0.2", VersioningPolicy::Calendar).level,
+            ActualSemverUpdate::Major
+        );
+        assert_eq!(
+            classify("2025.3.1", "2025.3.1", VersioningPolicy::Calendar),
+            VersionChange {
+                level: ActualSemverUpdate::Minor,
+                kind: VersionChangeKind::Minimum,
+            }
+        );
+        assert_eq!(
+            classify("0.3.1", "0.3.1", VersioningPolicy::StrictZeroVer),
+            VersionChange {
+                level: ActualSemverUpdate::Patch,
+                kind: VersionChangeKind::Minimum,
+            }
+        );
+    }
+
+    #[test]
+    fn classify_release_type_with_versioning_policy() {
+        let classify = |release_type, current, policy| {
+            classify_release_type_with_policy(
+                release_type,
+                Some("0.3.1"),
+                current,
+                PrereleasePolicy::BreakingAllowed,
+                policy,
+            )
+            .level
+        };
+
+        assert_eq!(
+            classify(ReleaseType::Minor, Some("0.3.1"), VersioningPolicy::Cargo),
+            ActualSemverUpdate::Minor
+        );
+        assert_eq!(
+            classify(
+                ReleaseType::Minor,
+                Some("0.3.1"),
+                VersioningPolicy::StrictZeroVer
+            ),
+            ActualSemverUpdate::Patch
+        );
+        assert_eq!(
+            classify(
+                ReleaseType::Minor,
+                Some("1.0.0"),
+                VersioningPolicy::StrictZeroVer
+            ),
+            ActualSemverUpdate::Minor
+        );
+        // Without a current version, the baseline version decides.
+        assert_eq!(
+            classify(ReleaseType::Minor, None, VersioningPolicy::StrictZeroVer),
+            ActualSemverUpdate::Patch
+        );
+        assert_eq!(
+            classify(
+                ReleaseType::Minor,
+                Some("1.0.0"),
+                VersioningPolicy::Conservative
+            ),
+            ActualSemverUpdate::Patch
+        );
+        assert_eq!(
+            classify(
+                ReleaseType::Major,
+                Some("0.3.1"),
+                VersioningPolicy::Conservative
+            ),
+            ActualSemverUpdate::Major
+        );
+    }
+
     #[test]
     fn crate_report_tracks_required_witness_errors_separately_from_semver_success() {
         let report = CrateReport {
@@ -932,6 +1475,8 @@ mod test {
             selected_checks: 1,
             skipped_checks: 0,
             witness_statistics: Some(crate::WitnessStatistics::new(0, 0, 0, 1)),
+            timings: CrateTimings::new(),
+            api_unchanged: false,
         };
 
         assert!(report.success());
@@ -955,6 +1500,8 @@ mod test {
                     0,
                     required_witness_errors,
                 )),
+                timings: CrateTimings::new(),
+                api_unchanged: false,
             }
         }
 
@@ -969,6 +1516,64 @@ mod test {
         assert!(report.has_required_witness_errors());
     }
 
+    #[test]
+    fn merge_configurations_notes_where_each_result_was_found() {
+        let semver_query = LintRegistry::get()
+            .queries()
+            .values()
+            .next()
+            .expect("no lints registered")
+            .clone();
+        let result = |name: &str| BTreeMap::from([(Arc::from("name"), FieldValue::from(name))]);
+        let pending = |query_results| PendingCrateReport {
+            report: CrateReport {
+                detected_bump: ActualSemverUpdate::Minor,
+                required_bumps: Bumps { major: 1, minor: 0 },
+                suggested_bumps: Bumps { major: 0, minor: 0 },
+                lint_results: vec![LintResult {
+                    semver_query: semver_query.clone(),
+                    query_results,
+                    query_duration: Duration::ZERO,
+                    effective_required_update: RequiredSemverUpdate::Major,
+                    effective_lint_level: LintLevel::Deny,
+                    result_configurations: Vec::new(),
+                }],
+                checks_duration: Duration::ZERO,
+                selected_checks: 1,
+                skipped_checks: 0,
+                witness_statistics: None,
+                timings: CrateTimings::new(),
+                api_unchanged: false,
+            },
+            witness_run_reports: Vec::new(),
+        };
+
+        let merged = PendingCrateReport::merge_configurations(vec![
+            (
+                "linux".to_string(),
+                pending(vec![result("both"), result("linux_only")]),
+            ),
+            ("windows".to_string(), pending(vec![result("both")])),
+        ]);
+
+        let [lint_result] = merged.report.lint_results.as_slice() else {
+            panic!("expected one merged lint result");
+        };
+        assert_eq!(
+            lint_result.query_results,
+            vec![result("both"), result("linux_only")]
+        );
+        assert_eq!(
+            lint_result.result_configurations,
+            vec![
+                vec!["linux".to_string(), "windows".to_string()],
+                vec!["linux".to_string()],
+            ]
+        );
+        assert_eq!(merged.report.required_bumps.major, 1);
+        assert_eq!(merged.report.detected_bump, ActualSemverUpdate::Minor);
+    }
+
     #[test]
     fn print_report_mentions_not_confirmed_by_witness() {
         let stdout = SharedBuffer::new();
@@ -987,6 +1592,8 @@ mod test {
             selected_checks: 1,
             skipped_checks: 0,
             witness_statistics: Some(crate::WitnessStatistics::new(1, 0, 0, 0)),
+            timings: CrateTimings::new(),
+            api_unchanged: false,
         };
 
         print_report(&mut config, &WitnessGeneration::new(), &report)
@@ -1013,6 +1620,8 @@ mod test {
             selected_checks: 1,
             skipped_checks: 0,
             witness_statistics: Some(crate::WitnessStatistics::new(0, 0, 0, 1)),
+            timings: CrateTimings::new(),
+            api_unchanged: false,
         };
 
         print_report(&mut config, &WitnessGeneration::new(), &report)
@@ -1022,4 +1631,35 @@ mod test {
         assert!(!stderr.contains("no semver update required"));
         assert!(stderr.contains("required witness validation failed"));
     }
+
+    #[test]
+    fn print_report_explains_skipped_check_for_unchanged_api() {
+        let stderr = SharedBuffer::new();
+        let mut config = GlobalConfig::new();
+        config.set_log_level(Some(log::Level::Info));
+        config.set_stdout(Box::new(SharedBuffer::new()));
+        config.set_stderr(Box::new(stderr.clone()));
+        config.set_color_choice(false);
+
+        let report = CrateReport {
+            detected_bump: ActualSemverUpdate::Patch,
+            required_bumps: Bumps { major: 0, minor: 0 },
+            suggested_bumps: Bumps { major: 0, minor: 0 },
+            lint_results: Vec::new(),
+            checks_duration: Duration::ZERO,
+            selected_checks: 0,
+            skipped_checks: 42,
+            witness_statistics: None,
+            timings: CrateTimings::new(),
+            api_unchanged: true,
+        };
+
+        print_report(&mut config, &WitnessGeneration::new(), &report)
+            .expect("failed to print report");
+
+        assert!(report.success());
+        let stderr = stderr.contents();
+        assert!(stderr.contains("all 42 checks: no changes to the public API since the baseline"));
+        assert!(stderr.contains("no semver update required"));
+    }
 }
@@ -2,7 +2,11 @@ use anstream::{AutoStream, ColorChoice};
 use anstyle::{AnsiColor, Color, Reset, Style};
 use clap::ValueEnum;
 use rand::RngExt;
-use std::{collections::HashSet, io::Write, sync::LazyLock};
+use std::{
+    collections::HashSet,
+    io::Write,
+    sync::{Arc, Mutex, OnceLock},
+};
 
 use crate::query::LintLevel;
 use crate::templating::make_handlebars_registry;
@@ -14,7 +18,7 @@ pub struct GlobalConfig {
     /// The unique ID for any particular run
     ///
     /// This value is lazily initialized on first access
-    run_id: LazyLock<String>,
+    run_id: OnceLock<String>,
     /// Minimum rustc version supported.
     ///
     /// This will be used to print an error if the user's rustc version is not high enough.
@@ -24,6 +28,24 @@ pub struct GlobalConfig {
     feature_flags: HashSet<FeatureFlag>,
 }
 
+fn generate_run_id() -> String {
+    let rng = rand::rng();
+    // We can show that based on the Birthday Problem (https://en.wikipedia.org/wiki/Birthday_problem)
+    // that 6 characters is enough for the use case of cargo-semver-checks. Primarily, given that we simply
+    // want a decent guarantee of not encountering collisions of generated files, and are not looking
+    // for cryptographic security, we're looking for odds of collisions to be somewhere in the range
+    // of ranges that is 1-100K up to 1-1M. We are using alphanumeric characters for the ID, which means
+    // each character has 62 possibilities, and at 6 characters, that gives us a total set of about
+    // 5.7 * 10^10 permutations. Now, the Birthday Problem shows that given many instances, the odds of a
+    // collision dramatically increase. However, at 62^6 permutations, it will take ~200k instances for
+    // the probability of a collision to reach 50%, which gives us exactly the sort of collision odds
+    // we're looking for.
+    rng.sample_iter(rand::distr::Alphanumeric)
+        .take(6)
+        .map(char::from)
+        .collect()
+}
+
 impl Default for GlobalConfig {
     fn default() -> Self {
         Self::new()
@@ -43,23 +65,7 @@ impl GlobalConfig {
         Self {
             level: None,
             handlebars: make_handlebars_registry(),
-            run_id: LazyLock::new(|| {
-                let rng = rand::rng();
-                // We can show that based on the Birthday Problem (https://en.wikipedia.org/wiki/Birthday_problem)
-                // that 6 characters is enough for the use case of cargo-semver-checks. Primarily, given that we simply
-                // want a decent guarantee of not encountering collisions of generated files, and are not looking
-                // for cryptographic security, we're looking for odds of collisions to be somewhere in the range
-                // of ranges that is 1-100K up to 1-1M. We are using alphanumeric characters for the ID, which means
-                // each character has 62 possibilities, and at 6 characters, that gives us a total set of about
-                // 5.7 * 10^10 permutations. Now, the Birthday Problem shows that given many instances, the odds of a
-                // collision dramatically increase. However, at 62^6 permutations, it will take ~200k instances for
-                // the probability of a collision to reach 50%, which gives us exactly the sort of collision odds
-                // we're looking for.
-                rng.sample_iter(rand::distr::Alphanumeric)
-                    .take(6)
-                    .map(char::from)
-                    .collect()
-            }),
+            run_id: OnceLock::new(),
             minimum_rustc_version: semver::Version::new(1, 93, 0),
             stdout: AutoStream::new(Box::new(std::io::stdout()), stdout_choice),
             stderr: AutoStream::new(Box::new(std::io::stderr()), stderr_choice),
@@ -72,7 +78,7 @@ impl GlobalConfig {
     }
 
     pub fn run_id(&self) -> &str {
-        &self.run_id
+        self.run_id.get_or_init(generate_run_id)
     }
 
     pub fn minimum_rustc_version(&self) -> &semver::Version {
@@ -258,6 +264,17 @@ impl GlobalConfig {
         self
     }
 
+    /// Runs `f` with this config's stdout discarded, then restores it.
+    pub(crate) fn with_stdout_discarded<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        let stdout = std::mem::replace(
+            &mut self.stdout,
+            AutoStream::never(Box::new(std::io::sink())),
+        );
+        let result = f(self);
+        self.stdout = stdout;
+        result
+    }
+
     /// Individually set the color choice setting for [`GlobalConfig::stderr`]
     ///
     /// Defaults to the global color choice in [`ColorChoice::global`], which can be set
@@ -378,6 +395,82 @@ impl GlobalConfig {
     pub fn feature_flags(&self) -> &HashSet<FeatureFlag> {
         &self.feature_flags
     }
+
+    /// Captures the settings needed to recreate this config on another thread.
+    pub(crate) fn worker_config(&self) -> WorkerConfig {
+        WorkerConfig {
+            level: self.level,
+            run_id: self.run_id().to_owned(),
+            minimum_rustc_version: self.minimum_rustc_version.clone(),
+            feature_flags: self.feature_flags.clone(),
+            out_color: self.out_color_choice(),
+            err_color: self.err_color_choice(),
+        }
+    }
+}
+
+/// The thread-safe settings of a [`GlobalConfig`].
+///
+/// Output streams can't be shared across threads, so work running concurrently
+/// uses a config that captures its output, to be printed afterward in a deterministic order.
+#[derive(Clone)]
+pub(crate) struct WorkerConfig {
+    level: Option<log::Level>,
+    run_id: String,
+    minimum_rustc_version: semver::Version,
+    feature_flags: HashSet<FeatureFlag>,
+    out_color: bool,
+    err_color: bool,
+}
+
+impl WorkerConfig {
+    /// Creates a [`GlobalConfig`] with these settings, writing to the given buffers.
+    pub(crate) fn into_captured(
+        self,
+        stdout: CapturedOutput,
+        stderr: CapturedOutput,
+    ) -> GlobalConfig {
+        let mut config = GlobalConfig {
+            level: self.level,
+            handlebars: make_handlebars_registry(),
+            run_id: OnceLock::from(self.run_id),
+            minimum_rustc_version: self.minimum_rustc_version,
+            stdout: AutoStream::never(Box::new(stdout)),
+            stderr: AutoStream::never(Box::new(stderr)),
+            feature_flags: self.feature_flags,
+        };
+        config.set_out_color_choice(self.out_color);
+        config.set_err_color_choice(self.err_color);
+        config
+    }
+}
+
+/// An in-memory output stream whose contents can be retrieved after writing.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedOutput {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Takes everything written so far, leaving the buffer empty.
+    pub(crate) fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().expect("captured output lock poisoned"))
+    }
+}
+
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .lock()
+            .expect("captured output lock poisoned")
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
 }
 
 /// A feature flag for gating unstable `cargo-semver-checks` features.
@@ -675,6 +768,32 @@ mod tests {
         assert!(config.out_color_choice());
     }
 
+    #[test]
+    fn worker_config_preserves_settings_and_captures_output() {
+        let mut config = GlobalConfig::new();
+        config.set_log_level(Some(log::Level::Debug));
+        config.set_color_choice(false);
+        config.enable_feature_flag(FeatureFlag::UNSTABLE_OPTIONS);
+
+        let out = CapturedOutput::new();
+        let err = CapturedOutput::new();
+        let mut worker = config
+            .worker_config()
+            .into_captured(out.clone(), err.clone());
+
+        assert_eq!(worker.run_id(), config.run_id());
+        assert!(worker.is_verbose());
+        assert!(worker.feature_flag_enabled(FeatureFlag::UNSTABLE_OPTIONS));
+        assert!(!worker.err_color_choice());
+
+        worker.shell_note("captured").expect("failed to write note");
+        write!(worker.stdout(), "report").expect("failed to write report");
+
+        assert_eq!(out.take(), b"report");
+        assert_eq!(String::from_utf8(err.take()).unwrap(), "note: captured\n");
+        assert!(err.take().is_empty());
+    }
+
     #[test]
     fn stable_flags_always_enabled() {
         let config = GlobalConfig::new();
@@ -0,0 +1,513 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use itertools::Itertools;
+use sha2::Digest as _;
+
+use super::error::TerminalError;
+use super::generate::{
+    GenerationSettings, Interruption, RustdocBuildEnvironment,
+    create_combined_placeholder_rustdoc_manifest, create_placeholder_rustdoc_manifest,
+    determine_rustdoc_dir, placeholder_metadata, run_interruptibly, rustdoc_json_path,
+    save_placeholder_rustdoc_manifest, seed_lockfile, set_toolchain,
+};
+use super::request::{CacheSettings, CacheUse, CrateDataRequest, RegistryRequest, RequestKind};
+
+/// The packages a package depends on, directly or not, with the features enabled on each.
+/// Keyed by package ID, and including the package itself.
+type ResolvedGraph = BTreeMap<String, BTreeSet<String>>;
+
+/// Generate rustdoc for the registry baselines of several crates in one combined placeholder
+/// project, so that their shared dependencies are only built once, and save it to the cache
+/// in `cache_dir`. The rustdoc is then loaded from the cache like any other cached rustdoc.
+///
+/// A crate is only included if its dependencies resolve, with the same features,
+/// exactly as they would in its own placeholder project. Otherwise feature unification
+/// could change its API. `announce` is called with the included crates, like `a v1.0.0`,
+/// before they are built.
+///
+/// Returns the crates whose rustdoc was generated and cached.
+pub(crate) fn prebuild_registry_baselines(
+    target_root: &Path,
+    cache_dir: &Path,
+    requests: &[&CrateDataRequest<'_>],
+    settings: &GenerationSettings,
+    announce: &mut dyn FnMut(&[String]),
+) -> anyhow::Result<Vec<String>> {
+    // Only requests built for the same target, with the same toolchain and lockfile,
+    // can share a `cargo doc` invocation. Vendored crates need cargo config of their own.
+    let mut groups: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for &request in requests {
+        if request.is_baseline
+            && matches!(
+                request.kind,
+                RequestKind::Registry(RegistryRequest {
+                    vendored_crates: None,
+                    ..
+                })
+            )
+        {
+            groups
+                .entry((request.build_target, request.toolchain, request.lockfile))
+                .or_default()
+                .push(request);
+        }
+    }
+
+    let mut prebuilt = Vec::new();
+    for group in groups.into_values() {
+        prebuilt.extend(prebuild_group(
+            target_root,
+            cache_dir,
+            &group,
+            settings,
+            announce,
+        )?);
+    }
+    Ok(prebuilt)
+}
+
+/// A crate whose rustdoc may be generated in the combined placeholder project.
+struct Candidate<'a, 'r> {
+    request: &'r CrateDataRequest<'a>,
+    description: String,
+    cache: CacheUse<'a>,
+    /// The build dir of the crate's own placeholder project.
+    build_dir: PathBuf,
+    /// The crate's own placeholder project's metadata, which is cached with its rustdoc.
+    metadata: cargo_metadata::Metadata,
+}
+
+fn prebuild_group<'a>(
+    target_root: &Path,
+    cache_dir: &'a Path,
+    requests: &[&CrateDataRequest<'a>],
+    settings: &GenerationSettings,
+    announce: &mut dyn FnMut(&[String]),
+) -> anyhow::Result<Vec<String>> {
+    let Some(&first) = requests.first() else {
+        return Ok(Vec::new());
+    };
+    let build_environment = RustdocBuildEnvironment::from_env_and_config_for_target(
+        first.build_target(),
+        first.toolchain(),
+    )?;
+
+    // Resolve the dependencies of each crate that isn't cached yet
+    // in the placeholder project it would otherwise be documented in.
+    let mut candidates = Vec::new();
+    for &request in requests {
+        let cache = CacheUse::new(
+            request,
+            &build_environment,
+            CacheSettings::ReadWrite(cache_dir),
+        )?;
+        if cache.read()?.is_some() {
+            continue;
+        }
+        let build_dir = target_root.join(request.build_path_slug(&build_environment)?);
+        let manifest_path = save_placeholder_rustdoc_manifest(
+            &build_dir,
+            create_placeholder_rustdoc_manifest(request)?,
+        )?;
+        seed_lockfile(request, &build_dir)?;
+        let metadata = placeholder_metadata(&manifest_path, request, settings)
+            .map_err(TerminalError::into_error)?;
+        candidates.push(Candidate {
+            request,
+            description: format!("{} v{}", request.kind.name()?, request.kind.version()?),
+            cache,
+            build_dir,
+            metadata,
+        });
+    }
+    if candidates.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    let combined_requests = candidates
+        .iter()
+        .map(|candidate| candidate.request)
+        .collect_vec();
+    let combined_dir = target_root.join(format!(
+        "registry-combined-{}",
+        combined_slug(&combined_requests, &build_environment)?
+    ));
+    let combined_manifest_path = save_placeholder_rustdoc_manifest(
+        &combined_dir,
+        create_combined_placeholder_rustdoc_manifest(&combined_requests)?,
+    )?;
+    seed_lockfile(first, &combined_dir)?;
+    let combined_metadata = placeholder_metadata(&combined_manifest_path, first, settings)
+        .map_err(TerminalError::into_error)
+        .context("failed to resolve dependencies of the combined placeholder project")?;
+    let target_dir = combined_metadata.target_directory.as_std_path();
+    let rustdoc_dir = determine_rustdoc_dir(
+        first,
+        target_dir,
+        first.kind.name()?,
+        first.kind.version()?.as_ref(),
+    )
+    .map_err(TerminalError::into_error)?;
+
+    // Keep only the crates that are built exactly as they would be on their own,
+    // and whose rustdoc JSON doesn't share a file name with another crate's.
+    let mut combined = Vec::new();
+    for candidate in candidates {
+        let name = candidate.request.kind.name()?;
+        let version = candidate.request.kind.version()?;
+        let standalone_graph = resolved_graph(&candidate.metadata, name, version.as_ref());
+        let combined_graph = resolved_graph(&combined_metadata, name, version.as_ref());
+        let json_path = find_package(&combined_metadata, name, version.as_ref())
+            .and_then(|package| rustdoc_json_path(&rustdoc_dir, package));
+        if let Some(json_path) = json_path
+            && standalone_graph.is_some()
+            && standalone_graph == combined_graph
+        {
+            combined.push((candidate, json_path));
+        }
+    }
+    retain_unique_json_paths(&mut combined);
+    if combined.len() < 2 {
+        return Ok(Vec::new());
+    }
+
+    announce(
+        &combined
+            .iter()
+            .map(|(candidate, _)| candidate.description.clone())
+            .collect_vec(),
+    );
+    let mut cmd = std::process::Command::new("cargo");
+    set_toolchain(&mut cmd, first.toolchain());
+    cmd.env("RUSTC_BOOTSTRAP", "1")
+        .env(
+            "RUSTDOCFLAGS",
+            build_environment.cargo_rustdocflags.as_ref(),
+        )
+        .env("RUSTFLAGS", build_environment.cargo_rustflags.as_ref())
+        .stdout(std::process::Stdio::null())
+        .stderr(settings.stderr())
+        .arg("doc")
+        .arg("--manifest-path")
+        .arg(&combined_manifest_path)
+        .arg("--target-dir")
+        .arg(target_dir);
+    for (candidate, _) in &combined {
+        cmd.arg("--package").arg(format!(
+            "{}@{}",
+            candidate.request.kind.name()?,
+            candidate.request.kind.version()?
+        ));
+    }
+    cmd.arg("--lib");
+    if let Some(build_target) = first.build_target {
+        cmd.arg("--target").arg(build_target);
+    }
+    cmd.arg("--no-deps");
+    cmd.arg(settings.color_flag());
+    cmd.args(settings.offline_flag());
+
+    let output = match run_interruptibly(&mut cmd, settings)? {
+        Ok(output) => output,
+        Err(Interruption::TimedOut(timeout)) => {
+            anyhow::bail!("'cargo doc' timed out after {}s", timeout.as_secs())
+        }
+        Err(Interruption::Cancelled) => anyhow::bail!("'cargo doc' was cancelled"),
+    };
+    if !output.status.success() {
+        anyhow::bail!(
+            "'cargo doc' failed on the combined placeholder project:\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let build_flags = build_environment.build_flags();
+    let mut prebuilt = Vec::new();
+    for (candidate, json_path) in combined {
+        if json_path.exists()
+            && candidate
+                .cache
+                .populate(&json_path, &candidate.metadata, &build_flags)?
+        {
+            prebuilt.push(candidate.description);
+            let _: std::io::Result<()> = std::fs::remove_dir_all(&candidate.build_dir);
+        }
+    }
+
+    // Like the build dir of a single crate, this isn't needed once its rustdoc is cached.
+    let _: std::io::Result<()> = std::fs::remove_dir_all(&combined_dir);
+    Ok(prebuilt)
+}
+
+/// Drop the crates whose rustdoc JSON would be written to the same file as another's,
+/// such as two crates with the same lib target name, since one would overwrite the other.
+fn retain_unique_json_paths<T>(combined: &mut Vec<(T, PathBuf)>) {
+    let json_path_counts = combined
+        .iter()
+        .counts_by(|(_, json_path)| json_path.clone());
+    combined.retain(|(_, json_path)| json_path_counts[json_path] == 1);
+}
+
+/// A path-safe identifier for the combined placeholder project of `requests`.
+fn combined_slug(
+    requests: &[&CrateDataRequest<'_>],
+    build_environment: &RustdocBuildEnvironment,
+) -> anyhow::Result<String> {
+    let mut hasher = sha2::Sha256::new();
+    for request in requests {
+        let slug = request.artifact_slug(build_environment)?;
+        hasher.update(slug.len().to_le_bytes());
+        hasher.update(slug.as_bytes());
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .take(8)
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn find_package<'m>(
+    metadata: &'m cargo_metadata::Metadata,
+    name: &str,
+    version: &str,
+) -> Option<&'m cargo_metadata::Package> {
+    metadata
+        .packages
+        .iter()
+        .find(|package| package.name.as_str() == name && package.version.to_string() == version)
+}
+
+/// How cargo resolved the dependencies of the package with the given name and version,
+/// or `None` if the package isn't in the resolved dependency graph.
+fn resolved_graph(
+    metadata: &cargo_metadata::Metadata,
+    name: &str,
+    version: &str,
+) -> Option<ResolvedGraph> {
+    let nodes: HashMap<_, _> = metadata
+        .resolve
+        .as_ref()?
+        .nodes
+        .iter()
+        .map(|node| (&node.id, node))
+        .collect();
+    let mut graph = ResolvedGraph::new();
+    let mut pending = vec![&find_package(metadata, name, version)?.id];
+    while let Some(id) = pending.pop() {
+        if graph.contains_key(&id.repr) {
+            continue;
+        }
+        let node = nodes.get(id)?;
+        graph.insert(
+            id.repr.clone(),
+            node.features.iter().map(ToString::to_string).collect(),
+        );
+        pending.extend(&node.dependencies);
+    }
+    Some(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+    use std::path::{Path, PathBuf};
+
+    use super::{
+        RustdocBuildEnvironment, combined_slug, prebuild_registry_baselines, resolved_graph,
+        retain_unique_json_paths,
+    };
+    use crate::data_generation::request::{CacheSettings, CacheUse};
+    use crate::data_generation::{CrateDataRequest, GenerationSettings};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "cargo-semver-checks-batch-{label}-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ))
+    }
+
+    fn build_environment(target_triple: &str) -> RustdocBuildEnvironment {
+        RustdocBuildEnvironment {
+            target_triple: target_triple.to_owned(),
+            cargo_rustflags: Cow::Borrowed("--cap-lints=allow"),
+            cargo_rustdocflags: Cow::Borrowed(""),
+            toolchain_version: "1.90.0".to_owned(),
+        }
+    }
+
+    /// Write a package to `dir/name` with the given dependencies, followed by `extra`
+    /// manifest sections.
+    fn write_package(dir: &Path, name: &str, dependencies: &str, extra: &str) {
+        std::fs::create_dir_all(dir.join(name).join("src")).expect("failed to create package");
+        std::fs::write(
+            dir.join(name).join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{name}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [dependencies]\n{dependencies}\n{extra}"
+            ),
+        )
+        .expect("failed to write manifest");
+        std::fs::write(dir.join(name).join("src/lib.rs"), "").expect("failed to write lib.rs");
+    }
+
+    /// The metadata of a placeholder project in `dir` that depends on `dependencies`.
+    fn resolve_placeholder(dir: &Path, name: &str, dependencies: &str) -> cargo_metadata::Metadata {
+        write_package(dir, name, dependencies, "[workspace]\n");
+        cargo_metadata::MetadataCommand::new()
+            .manifest_path(dir.join(name).join("Cargo.toml"))
+            .other_options(vec!["--offline".to_string()])
+            .exec()
+            .expect("cargo metadata failed")
+    }
+
+    #[test]
+    fn crates_are_only_combined_if_feature_unification_leaves_them_unchanged() {
+        // `a` enables a feature of `shared` that `b` doesn't, so documenting them together
+        // would build `b` against a different `shared` than on its own.
+        let dir = temp_dir("unification");
+        write_package(&dir, "shared", "", "[features]\nextra = []\n");
+        write_package(
+            &dir,
+            "a",
+            "shared = { path = \"../shared\", features = [\"extra\"] }\n",
+            "",
+        );
+        write_package(&dir, "b", "shared = { path = \"../shared\" }\n", "");
+        let standalone_a = resolve_placeholder(&dir, "placeholder-a", "a = { path = \"../a\" }\n");
+        let standalone_b = resolve_placeholder(&dir, "placeholder-b", "b = { path = \"../b\" }\n");
+        let combined = resolve_placeholder(
+            &dir,
+            "placeholder-combined",
+            "a = { path = \"../a\" }\nb = { path = \"../b\" }\n",
+        );
+
+        let graph_a = resolved_graph(&standalone_a, "a", "0.1.0").expect("a is resolved");
+        assert_eq!(graph_a.len(), 2);
+        assert_eq!(resolved_graph(&combined, "a", "0.1.0"), Some(graph_a));
+
+        let graph_b = resolved_graph(&standalone_b, "b", "0.1.0").expect("b is resolved");
+        let combined_graph_b = resolved_graph(&combined, "b", "0.1.0").expect("b is resolved");
+        assert_ne!(combined_graph_b, graph_b);
+        let shared_features = |graph: &super::ResolvedGraph| {
+            graph
+                .iter()
+                .find(|(id, _)| id.contains("shared"))
+                .map(|(_, features)| features.clone())
+                .expect("shared is a dependency")
+        };
+        assert!(shared_features(&graph_b).is_empty());
+        assert!(shared_features(&combined_graph_b).contains("extra"));
+
+        assert_eq!(resolved_graph(&standalone_a, "b", "0.1.0"), None);
+        assert_eq!(resolved_graph(&standalone_a, "a", "0.2.0"), None);
+
+        std::fs::remove_dir_all(&dir).expect("failed to remove temp dir");
+    }
+
+    #[test]
+    fn crates_sharing_a_rustdoc_json_file_are_not_combined() {
+        let mut combined = vec![
+            ("a v1.0.0", PathBuf::from("doc/a.json")),
+            ("b v1.0.0", PathBuf::from("doc/shared_name.json")),
+            ("c v1.0.0", PathBuf::from("doc/c.json")),
+            ("b-renamed v2.0.0", PathBuf::from("doc/shared_name.json")),
+        ];
+        retain_unique_json_paths(&mut combined);
+        assert_eq!(
+            combined
+                .iter()
+                .map(|(description, _)| *description)
+                .collect::<Vec<_>>(),
+            ["a v1.0.0", "c v1.0.0"]
+        );
+    }
+
+    #[test]
+    fn combined_slug_identifies_the_requests_and_build_environment() {
+        let entries = [
+            tame_index::IndexVersion::fake("a", "1.0.0"),
+            tame_index::IndexVersion::fake("b", "1.0.0"),
+            tame_index::IndexVersion::fake("b", "2.0.0"),
+        ];
+        let [a, b1, b2] = entries
+            .each_ref()
+            .map(|entry| CrateDataRequest::from_index(entry, true, Default::default(), None, true));
+        let linux = build_environment("x86_64-unknown-linux-gnu");
+        let slug = |requests: &[&CrateDataRequest<'_>], environment| {
+            combined_slug(requests, environment).expect("failed to compute slug")
+        };
+
+        let slug_a_b1 = slug(&[&a, &b1], &linux);
+        assert_eq!(slug_a_b1.len(), 16);
+        assert!(slug_a_b1.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(slug(&[&a, &b1], &linux), slug_a_b1);
+        assert_ne!(slug(&[&a, &b2], &linux), slug_a_b1);
+        assert_ne!(slug(&[&a], &linux), slug_a_b1);
+        assert_ne!(
+            slug(&[&a, &b1], &build_environment("aarch64-apple-darwin")),
+            slug_a_b1
+        );
+    }
+
+    #[test]
+    fn failed_combined_builds_leave_each_crate_to_be_built_separately() {
+        // `cargo doc` can't build for a target that doesn't exist,
+        // though resolving the dependencies doesn't need the target.
+        let target = "not-a-real-target";
+        let entries = [
+            tame_index::IndexVersion::fake("itoa", "1.0.15"),
+            tame_index::IndexVersion::fake("ryu", "1.0.20"),
+        ];
+        let requests = entries.each_ref().map(|entry| {
+            CrateDataRequest::from_index(entry, true, Default::default(), Some(target), true)
+        });
+        let target_root = temp_dir("fallback");
+        let cache_dir = target_root.join("cache");
+        let settings = GenerationSettings {
+            pass_through_stderr: false,
+            use_color: false,
+            offline: false,
+            timeout: None,
+            cancellation: None,
+            recorder: None,
+            fingerprint_api: false,
+        };
+
+        let mut announced = Vec::new();
+        let error = prebuild_registry_baselines(
+            &target_root,
+            &cache_dir,
+            &requests.each_ref(),
+            &settings,
+            &mut |crates| announced.extend_from_slice(crates),
+        )
+        .expect_err("the combined build should fail");
+        assert!(
+            error
+                .to_string()
+                .contains("'cargo doc' failed on the combined placeholder project"),
+            "{error:#}"
+        );
+        assert_eq!(announced, ["itoa v1.0.15", "ryu v1.0.20"]);
+
+        // Nothing was cached, so each crate's rustdoc is generated on its own.
+        let build_environment =
+            RustdocBuildEnvironment::from_env_and_config_for_target(Some(target), None)
+                .expect("failed to determine build environment");
+        for request in &requests {
+            let cache = CacheUse::new(
+                request,
+                &build_environment,
+                CacheSettings::ReadWrite(cache_dir.as_path()),
+            )
+            .expect("failed to set up cache");
+            assert!(cache.read().expect("failed to read cache").is_none());
+        }
+
+        std::fs::remove_dir_all(&target_root).expect("failed to remove temp dir");
+    }
+}
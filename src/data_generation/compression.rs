@@ -0,0 +1,130 @@
+use std::io::{Read as _, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+/// Every zstd frame starts with these bytes, which can't begin a JSON document.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Write the contents of `source` into `writer` as a zstd-compressed stream.
+pub(crate) fn compress_into(source: &Path, writer: &mut dyn Write) -> anyhow::Result<()> {
+    let mut source_file = fs_err::File::open(source)?;
+    let mut encoder = zstd::Encoder::new(writer, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+    std::io::copy(&mut source_file, &mut encoder)
+        .with_context(|| format!("failed to compress {}", source.display()))?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// A rustdoc JSON file that's ready to load from a path.
+///
+/// Rustdoc JSON is loaded from a path, so zstd-compressed files are
+/// decompressed into a temporary file, which is removed when this value is dropped.
+/// Uncompressed files are used in place.
+#[derive(Debug)]
+pub(crate) enum RustdocJsonFile {
+    InPlace(PathBuf),
+    Decompressed(tempfile::TempPath),
+}
+
+impl RustdocJsonFile {
+    /// Prepare the rustdoc JSON at `path` for loading, decompressing it into
+    /// `scratch_dir` if it's compressed.
+    pub(crate) fn open(path: &Path, scratch_dir: &Path) -> anyhow::Result<Self> {
+        let mut file = fs_err::File::open(path)?;
+        let mut magic = [0; ZSTD_MAGIC.len()];
+        let is_compressed = match file.read_exact(&mut magic) {
+            Ok(()) => magic == ZSTD_MAGIC,
+            // Too short to be compressed. Loading it will report any problems with it.
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => false,
+            Err(e) => return Err(e.into()),
+        };
+        if !is_compressed {
+            return Ok(Self::InPlace(path.to_owned()));
+        }
+
+        fs_err::create_dir_all(scratch_dir)?;
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("not a file path: {}", path.display()))?
+            .to_string_lossy();
+        // The temporary file is removed when dropped, even if decompression fails.
+        let temp_file = tempfile::Builder::new()
+            .prefix(&format!(".{file_name}.decompressed-"))
+            .tempfile_in(scratch_dir)
+            .with_context(|| {
+                format!(
+                    "failed to create a temporary file in {}",
+                    scratch_dir.display()
+                )
+            })?;
+
+        let source = std::io::BufReader::new(fs_err::File::open(path)?);
+        let mut destination = std::io::BufWriter::new(temp_file);
+        zstd::stream::copy_decode(source, &mut destination)
+            .with_context(|| format!("failed to decompress {}", path.display()))?;
+        let temp_file = destination.into_inner().map_err(|e| e.into_error())?;
+
+        Ok(Self::Decompressed(temp_file.into_temp_path()))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Self::InPlace(path) => path,
+            Self::Decompressed(temp_path) => temp_path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{RustdocJsonFile, compress_into};
+
+    #[test]
+    fn compressed_files_are_decompressed_and_cleaned_up() {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-compression-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        fs_err::create_dir_all(&dir).expect("failed to create test dir");
+        let plain = dir.join("plain.json");
+        let compressed = dir.join("compressed.json.zst");
+        fs_err::write(&plain, br#"{"format_version": 57}"#).expect("failed to write");
+        let mut compressed_bytes = Vec::new();
+        compress_into(&plain, &mut compressed_bytes).expect("failed to compress");
+        fs_err::write(&compressed, &compressed_bytes).expect("failed to write");
+
+        let plain_file = RustdocJsonFile::open(&plain, &dir).expect("failed to open plain");
+        assert_eq!(plain_file.path(), plain);
+
+        let decompressed_path: PathBuf = {
+            let file = RustdocJsonFile::open(&compressed, &dir).expect("failed to open compressed");
+            assert_ne!(file.path(), compressed);
+            assert_eq!(
+                fs_err::read(file.path()).expect("failed to read decompressed"),
+                fs_err::read(&plain).expect("failed to read plain"),
+            );
+            file.path().to_owned()
+        };
+        assert!(!decompressed_path.exists());
+
+        // Nothing is left behind when decompression fails either.
+        let corrupted = dir.join("corrupted.json.zst");
+        fs_err::write(&corrupted, &compressed_bytes[..compressed_bytes.len() / 2])
+            .expect("failed to write");
+        RustdocJsonFile::open(&corrupted, &dir).expect_err("decompressed a truncated file");
+        let leftovers: Vec<_> = fs_err::read_dir(&dir)
+            .expect("failed to list test dir")
+            .map(|entry| entry.expect("failed to list test dir").file_name())
+            .filter(|name| name.to_string_lossy().contains(".decompressed-"))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+
+        drop(plain_file);
+        assert!(plain.exists());
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+}
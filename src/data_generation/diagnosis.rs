@@ -0,0 +1,294 @@
+use cargo_metadata::{Message, PackageId, diagnostic::DiagnosticLevel};
+
+/// Why `cargo doc` failed to build rustdoc for a crate, as diagnosed from its output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BuildFailureKind {
+    /// Cargo couldn't resolve the versions of the crate's dependencies,
+    /// as found when `cargo update` or `cargo metadata` resolves them.
+    DependencyResolution,
+    /// The crate or one of its dependencies failed to compile.
+    CompileError,
+    /// A build script failed to compile or run.
+    BuildScript,
+    /// A build script or the linker couldn't find a system library.
+    MissingSystemLibrary,
+    /// The build target isn't installed, or the crate doesn't support it.
+    UnsupportedTarget,
+}
+
+impl BuildFailureKind {
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            Self::DependencyResolution => "dependency resolution failed",
+            Self::CompileError => "compilation failed",
+            Self::BuildScript => "a build script failed",
+            Self::MissingSystemLibrary => "a system library is missing",
+            Self::UnsupportedTarget => "the build target is unsupported",
+        }
+    }
+}
+
+/// What we could tell about a failed `cargo doc` run.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct BuildDiagnosis {
+    pub(crate) kind: Option<BuildFailureKind>,
+    /// The package that failed to build, like `openssl-sys v0.9.100`, if known.
+    pub(crate) culprit: Option<String>,
+    /// Compiler errors as rustc renders them. With `--message-format=json`,
+    /// cargo reports them on stdout instead of printing them to stderr.
+    pub(crate) rendered_errors: Vec<String>,
+    /// The output as cargo would print it without `--message-format=json`:
+    /// its own messages, with compiler messages rendered where they were reported.
+    pub(crate) transcript: String,
+}
+
+/// Messages showing the build target's standard library isn't available.
+const UNSUPPORTED_TARGET_PATTERNS: &[&str] = &[
+    "can't find crate for `std`",
+    "can't find crate for `core`",
+    "could not find specification for target",
+    "error loading target specification",
+];
+
+/// Messages from `pkg-config`, common `-sys` crate build scripts, and linkers
+/// when a system library is missing.
+const MISSING_SYSTEM_LIBRARY_PATTERNS: &[&str] = &[
+    "pkg-config exited with status",
+    "the system library `",
+    "could not find system library",
+    "unable to find library -l",
+    "cannot find -l",
+    "library not found for -l",
+];
+
+/// Messages cargo prints when it can't resolve the dependency graph.
+const DEPENDENCY_RESOLUTION_PATTERNS: &[&str] = &[
+    "failed to select a version for",
+    "no matching package named",
+    "failed to load source for dependency",
+    "cyclic package dependency",
+];
+
+const BUILD_SCRIPT_FAILURE_PREFIX: &str = "failed to run custom build command for `";
+
+/// Whether cargo's output shows it couldn't resolve the dependency graph.
+pub(crate) fn is_dependency_resolution_failure(stderr: &str) -> bool {
+    let stderr = stderr.to_ascii_lowercase();
+    DEPENDENCY_RESOLUTION_PATTERNS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// Classify a failed `cargo doc --message-format=json` run from its output:
+/// its stdout and stderr merged in the order they were written,
+/// or only its stdout if stderr wasn't captured.
+///
+/// `package_name` describes the package with the given ID, like `syn v2.0.0`.
+pub(crate) fn diagnose_cargo_doc_failure(
+    output: &[u8],
+    package_name: impl Fn(&PackageId) -> Option<String>,
+) -> BuildDiagnosis {
+    let mut diagnosis = BuildDiagnosis::default();
+    let mut stderr = String::new();
+    let mut build_script_failed = false;
+    for message in Message::parse_stream(output).flatten() {
+        let message = match message {
+            Message::CompilerMessage(message) => message,
+            Message::TextLine(line) => {
+                for text in [&mut stderr, &mut diagnosis.transcript] {
+                    text.push_str(&line);
+                    text.push('\n');
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        let level = message.message.level;
+        let rendered = message.message.rendered.unwrap_or_else(|| {
+            let level_name = match level {
+                DiagnosticLevel::Warning => "warning",
+                DiagnosticLevel::Note | DiagnosticLevel::FailureNote => "note",
+                DiagnosticLevel::Help => "help",
+                _ => "error",
+            };
+            format!("{level_name}: {}\n", message.message.message)
+        });
+        diagnosis.transcript.push_str(&rendered);
+        if level != DiagnosticLevel::Error {
+            continue;
+        }
+        if diagnosis.culprit.is_none() {
+            diagnosis.culprit = package_name(&message.package_id);
+        }
+        build_script_failed |= message.target.is_custom_build();
+        diagnosis.rendered_errors.push(rendered);
+    }
+    let stderr = stderr.as_str();
+
+    if let Some(start) = stderr.find(BUILD_SCRIPT_FAILURE_PREFIX) {
+        build_script_failed = true;
+        let package = &stderr[start + BUILD_SCRIPT_FAILURE_PREFIX.len()..];
+        if let Some(end) = package.find(['`', ' ']) {
+            let name = &package[..end];
+            // The package is described as `name vX.Y.Z (source)`.
+            let version = package[end..]
+                .strip_prefix(' ')
+                .and_then(|rest| rest.split([' ', '`']).next())
+                .unwrap_or_default();
+            diagnosis.culprit = Some(format!("{name} {version}").trim_end().to_string());
+        }
+    }
+
+    let output = std::iter::once(stderr)
+        .chain(diagnosis.rendered_errors.iter().map(String::as_str))
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>();
+    let mentions_any = |patterns: &[&str]| {
+        output
+            .iter()
+            .any(|text| patterns.iter().any(|pattern| text.contains(pattern)))
+    };
+    diagnosis.kind = if mentions_any(UNSUPPORTED_TARGET_PATTERNS) {
+        Some(BuildFailureKind::UnsupportedTarget)
+    } else if mentions_any(MISSING_SYSTEM_LIBRARY_PATTERNS) {
+        Some(BuildFailureKind::MissingSystemLibrary)
+    } else if build_script_failed {
+        Some(BuildFailureKind::BuildScript)
+    } else if !diagnosis.rendered_errors.is_empty() {
+        Some(BuildFailureKind::CompileError)
+    } else if is_dependency_resolution_failure(stderr) {
+        Some(BuildFailureKind::DependencyResolution)
+    } else {
+        None
+    };
+    diagnosis
+}
+
+#[cfg(test)]
+mod tests {
+    use cargo_metadata::PackageId;
+
+    use super::{BuildFailureKind, diagnose_cargo_doc_failure, is_dependency_resolution_failure};
+
+    fn compiler_error(package: &str, target_kind: &str, rendered: &str) -> String {
+        serde_json::json!({
+            "reason": "compiler-message",
+            "package_id": package,
+            "manifest_path": "/example/Cargo.toml",
+            "target": {
+                "name": "example",
+                "kind": [target_kind],
+                "crate_types": [target_kind],
+                "required-features": [],
+                "src_path": "/example/src/lib.rs",
+                "edition": "2021",
+                "doctest": true,
+                "test": true,
+                "doc": true,
+            },
+            "message": {
+                "message": "an error",
+                "code": null,
+                "level": "error",
+                "spans": [],
+                "children": [],
+                "rendered": rendered,
+            },
+        })
+        .to_string()
+    }
+
+    fn package_name(id: &PackageId) -> Option<String> {
+        Some(format!("{} v1.0.0", id.repr))
+    }
+
+    #[test]
+    fn compile_errors_are_reported_with_their_package() {
+        let stdout = compiler_error("example", "lib", "error[E0425]: cannot find value `x`\n");
+        let diagnosis = diagnose_cargo_doc_failure(stdout.as_bytes(), package_name);
+        assert_eq!(diagnosis.kind, Some(BuildFailureKind::CompileError));
+        assert_eq!(diagnosis.culprit.as_deref(), Some("example v1.0.0"));
+        assert_eq!(
+            diagnosis.rendered_errors,
+            ["error[E0425]: cannot find value `x`\n"]
+        );
+    }
+
+    #[test]
+    fn transcript_keeps_compiler_messages_in_order() {
+        let output = [
+            " Documenting example v1.0.0 (/example)".to_string(),
+            compiler_error("example", "lib", "error: first\n\n"),
+            "error: could not document `example`".to_string(),
+        ]
+        .join("\n");
+        let diagnosis = diagnose_cargo_doc_failure(output.as_bytes(), package_name);
+        assert_eq!(
+            diagnosis.transcript,
+            " Documenting example v1.0.0 (/example)\n\
+             error: first\n\n\
+             error: could not document `example`\n"
+        );
+        assert_eq!(diagnosis.rendered_errors, ["error: first\n\n"]);
+    }
+
+    #[test]
+    fn build_script_failures_name_their_package() {
+        let stderr = "\
+error: failed to run custom build command for `zstd-sys v2.0.9+zstd.1.5.5`
+
+Caused by:
+  process didn't exit successfully (exit status: 101)";
+        let diagnosis = diagnose_cargo_doc_failure(stderr.as_bytes(), package_name);
+        assert_eq!(diagnosis.kind, Some(BuildFailureKind::BuildScript));
+        assert_eq!(
+            diagnosis.culprit.as_deref(),
+            Some("zstd-sys v2.0.9+zstd.1.5.5")
+        );
+    }
+
+    #[test]
+    fn missing_system_libraries_are_told_apart_from_other_build_script_failures() {
+        let stderr = "\
+error: failed to run custom build command for `openssl-sys v0.9.100`
+  --- stderr
+  Could not find openssl via pkg-config:
+  pkg-config exited with status code 1";
+        let diagnosis = diagnose_cargo_doc_failure(stderr.as_bytes(), package_name);
+        assert_eq!(diagnosis.kind, Some(BuildFailureKind::MissingSystemLibrary));
+        assert_eq!(diagnosis.culprit.as_deref(), Some("openssl-sys v0.9.100"));
+    }
+
+    #[test]
+    fn missing_standard_library_means_the_target_is_unsupported() {
+        let stdout = compiler_error(
+            "example",
+            "lib",
+            "error[E0463]: can't find crate for `std`\n\
+             = note: the `thumbv7em-none-eabihf` target may not be installed\n",
+        );
+        let diagnosis = diagnose_cargo_doc_failure(stdout.as_bytes(), package_name);
+        assert_eq!(diagnosis.kind, Some(BuildFailureKind::UnsupportedTarget));
+    }
+
+    #[test]
+    fn dependency_resolution_failures_are_detected_from_stderr() {
+        let stderr = "\
+error: failed to select a version for the requirement `serde = \"^9\"`
+candidate versions found which didn't match: 1.0.200";
+        let diagnosis = diagnose_cargo_doc_failure(stderr.as_bytes(), package_name);
+        assert_eq!(diagnosis.kind, Some(BuildFailureKind::DependencyResolution));
+        assert_eq!(diagnosis.culprit, None);
+        assert!(is_dependency_resolution_failure(stderr));
+        assert!(!is_dependency_resolution_failure(
+            "error: could not compile `example`"
+        ));
+    }
+
+    #[test]
+    fn unrecognized_failures_are_not_classified() {
+        let diagnosis = diagnose_cargo_doc_failure(b"error: something else\n", package_name);
+        assert_eq!(diagnosis.kind, None);
+        assert!(diagnosis.rendered_errors.is_empty());
+    }
+}
@@ -1,10 +1,23 @@
+use super::diagnosis::BuildFailureKind;
+
 /// An error that blocks further progress. "Terminal" in the sense of "cannot continue."
 #[derive(Debug)]
 pub(crate) enum TerminalError {
     WithAdvice(anyhow::Error, String),
+    /// Building rustdoc failed for a diagnosed reason, with advice specific to it.
+    BuildFailure(BuildFailureKind, anyhow::Error, String),
     Other(anyhow::Error),
 }
 
+impl TerminalError {
+    /// The underlying error, without any advice.
+    pub(crate) fn into_error(self) -> anyhow::Error {
+        match self {
+            Self::WithAdvice(err, _) | Self::BuildFailure(_, err, _) | Self::Other(err) => err,
+        }
+    }
+}
+
 impl<E: std::error::Error + Send + Sync + 'static> From<E> for TerminalError {
     fn from(value: E) -> Self {
         Self::Other(value.into())
@@ -0,0 +1,530 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use serde::ser::{self, Serialize, Serializer};
+use sha2::Digest as _;
+
+/// A hash of a crate's public API, used to skip linting when nothing in it changed.
+///
+/// Covers the items reachable from the crate's root module, as lints see them,
+/// plus the package's feature table. It's computed from the parsed rustdoc,
+/// with item IDs replaced by the order in which items are reached from the root,
+/// so renumbered items don't change it. Source spans, documentation, and the crate's
+/// own version number are left out, since they change between releases without
+/// affecting the API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ApiFingerprint([u8; 32]);
+
+/// The parts of a parsed rustdoc crate, in any of the supported format versions,
+/// that its fingerprint covers.
+pub(super) struct RustdocParts<'c, I, K, T> {
+    /// The ID of the crate's root module.
+    pub(super) root: u32,
+    /// The items in the index by ID, with the ID of the crate each one is from.
+    pub(super) items: HashMap<u32, (u32, &'c I)>,
+    /// The crate ID, path, and kind of each item with a known path, by ID.
+    pub(super) paths: HashMap<u32, (u32, &'c [String], &'c K)>,
+    /// The name of each crate by ID, whether it's this crate or an external one.
+    pub(super) crate_names: HashMap<u32, &'c str>,
+    pub(super) target: &'c T,
+}
+
+impl ApiFingerprint {
+    pub(super) fn new<I: Serialize, K: Serialize, T: Serialize>(
+        parts: RustdocParts<'_, I, K, T>,
+        package: Option<&cargo_metadata::Package>,
+    ) -> anyhow::Result<Self> {
+        let RustdocParts {
+            root,
+            items,
+            paths,
+            crate_names,
+            target,
+        } = parts;
+        let mut ids = CanonicalIds {
+            items,
+            paths,
+            crate_names,
+            numbers: HashMap::new(),
+            unvisited: VecDeque::new(),
+        };
+        let mut hasher = sha2::Sha256::new();
+
+        let mut serializer = HashSerializer::new(&mut hasher, &mut ids);
+        target.serialize(&mut serializer)?;
+        serializer.hash_id(root)?;
+        while let Some(id) = ids.unvisited.pop_front() {
+            let (crate_id, item) = ids.items[&id];
+            hash_str(&mut hasher, ids.crate_name(crate_id));
+            item.serialize(&mut HashSerializer::new(&mut hasher, &mut ids))?;
+        }
+        match package {
+            Some(package) => {
+                hasher.update([1]);
+                for (feature, enables) in &package.features {
+                    hash_str(&mut hasher, feature);
+                    hasher.update(enables.len().to_le_bytes());
+                    for enabled in enables {
+                        hash_str(&mut hasher, enabled);
+                    }
+                }
+            }
+            None => hasher.update([0]),
+        }
+
+        let digest = hasher.finalize();
+        let fingerprint = digest
+            .as_slice()
+            .try_into()
+            .expect("SHA-256 digests are 32 bytes long");
+        Ok(Self(fingerprint))
+    }
+}
+
+/// The fields of rustdoc items that are left out of the fingerprint.
+/// The crate an item is from is hashed by name instead of by its `crate_id`,
+/// which depends on the order that dependencies were loaded in.
+const SKIPPED_ITEM_FIELDS: [&str; 4] = ["span", "docs", "links", "crate_id"];
+
+/// Numbers the items of a crate in the order they're reached from its root module.
+struct CanonicalIds<'c, I, K> {
+    items: HashMap<u32, (u32, &'c I)>,
+    paths: HashMap<u32, (u32, &'c [String], &'c K)>,
+    crate_names: HashMap<u32, &'c str>,
+    numbers: HashMap<u32, usize>,
+    /// Items that were reached, but haven't been hashed yet.
+    unvisited: VecDeque<u32>,
+}
+
+impl<I, K> CanonicalIds<'_, I, K> {
+    fn crate_name(&self, crate_id: u32) -> &str {
+        self.crate_names.get(&crate_id).copied().unwrap_or_default()
+    }
+}
+
+/// Hashes rustdoc data as it's serialized, replacing item IDs with their canonical numbers.
+struct HashSerializer<'h, 'c, I: Serialize, K: Serialize> {
+    hasher: &'h mut sha2::Sha256,
+    ids: &'h mut CanonicalIds<'c, I, K>,
+    /// Whether the next integer is the value of an item ID.
+    reading_id: bool,
+}
+
+impl<'h, 'c, I: Serialize, K: Serialize> HashSerializer<'h, 'c, I, K> {
+    fn new(hasher: &'h mut sha2::Sha256, ids: &'h mut CanonicalIds<'c, I, K>) -> Self {
+        Self {
+            hasher,
+            ids,
+            reading_id: false,
+        }
+    }
+
+    /// A serializer for a separate hash, which shares this one's item numbering.
+    fn with_hasher<'s>(&'s mut self, hasher: &'s mut sha2::Sha256) -> HashSerializer<'s, 'c, I, K> {
+        HashSerializer::new(hasher, self.ids)
+    }
+
+    fn hash_id(&mut self, id: u32) -> Result<(), HashError> {
+        if self.ids.items.contains_key(&id) {
+            let next_number = self.ids.numbers.len();
+            let number = *self.ids.numbers.entry(id).or_insert_with(|| {
+                self.ids.unvisited.push_back(id);
+                next_number
+            });
+            self.hasher.update([10]);
+            self.hasher.update(number.to_le_bytes());
+        } else if let Some(&(crate_id, path, kind)) = self.ids.paths.get(&id) {
+            // Items outside the index, like those of other crates, are identified by path.
+            self.hasher.update([11]);
+            hash_str(self.hasher, self.ids.crate_name(crate_id));
+            self.hasher.update(path.len().to_le_bytes());
+            for segment in path {
+                hash_str(self.hasher, segment);
+            }
+            kind.serialize(&mut *self)?;
+        } else {
+            self.hasher.update([12]);
+        }
+        Ok(())
+    }
+
+    fn hash_integer(&mut self, value: impl Display) -> Result<(), HashError> {
+        self.hasher.update([2]);
+        hash_str(self.hasher, &value.to_string());
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct HashError(String);
+
+impl Display for HashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HashError {}
+
+impl ser::Error for HashError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+impl<'h, 'c, I: Serialize, K: Serialize> Serializer for &mut HashSerializer<'h, 'c, I, K> {
+    type Ok = ();
+    type Error = HashError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = HashMapEntries<Self>;
+    type SerializeStruct = HashStructFields<Self>;
+    type SerializeStructVariant = HashStructFields<Self>;
+
+    fn serialize_bool(self, value: bool) -> Result<(), HashError> {
+        self.hasher.update([1, u8::from(value)]);
+        Ok(())
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), HashError> {
+        if std::mem::take(&mut self.reading_id) {
+            self.hash_id(value)
+        } else {
+            self.hash_integer(value)
+        }
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<(), HashError> {
+        self.hash_integer(value)
+    }
+
+    fn serialize_char(self, value: char) -> Result<(), HashError> {
+        self.serialize_str(value.encode_utf8(&mut [0; 4]))
+    }
+
+    fn serialize_str(self, value: &str) -> Result<(), HashError> {
+        self.hasher.update([3]);
+        hash_str(self.hasher, value);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<(), HashError> {
+        self.hasher.update([8]);
+        self.hasher.update(value.len().to_le_bytes());
+        self.hasher.update(value);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), HashError> {
+        self.hasher.update([0]);
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), HashError> {
+        self.hasher.update([9]);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), HashError> {
+        self.hasher.update([0]);
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), HashError> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), HashError> {
+        self.hasher.update([6]);
+        hash_str(self.hasher, variant);
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        // Rustdoc's `Id`s are newtypes around the number rustdoc assigned the item.
+        self.reading_id = name == "Id";
+        value.serialize(&mut *self)?;
+        self.reading_id = false;
+        Ok(())
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.serialize_unit_variant(name, index, variant)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self, HashError> {
+        // Mark the end of the sequence instead of prefixing its length,
+        // which isn't always known up front.
+        self.hasher.update([4]);
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self, HashError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self, HashError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self, HashError> {
+        self.serialize_unit_variant(name, index, variant)?;
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<HashMapEntries<Self>, HashError> {
+        Ok(HashMapEntries {
+            serializer: self,
+            entry: None,
+            entry_digests: Vec::new(),
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<HashStructFields<Self>, HashError> {
+        self.hasher.update([13]);
+        Ok(HashStructFields {
+            serializer: self,
+            is_item: name == "Item",
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<HashStructFields<Self>, HashError> {
+        self.serialize_unit_variant(name, index, variant)?;
+        self.serialize_struct(variant, len)
+    }
+}
+
+impl<I: Serialize, K: Serialize> HashSerializer<'_, '_, I, K> {
+    fn hash_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        value.serialize(self)
+    }
+
+    fn end_sequence(&mut self) -> Result<(), HashError> {
+        self.hasher.update([7]);
+        Ok(())
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeSeq for &mut HashSerializer<'_, '_, I, K> {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.hash_element(value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.end_sequence()
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeTuple for &mut HashSerializer<'_, '_, I, K> {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.hash_element(value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.end_sequence()
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeTupleStruct for &mut HashSerializer<'_, '_, I, K> {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.hash_element(value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.end_sequence()
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeTupleVariant for &mut HashSerializer<'_, '_, I, K> {
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        self.hash_element(value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.end_sequence()
+    }
+}
+
+/// Hashes each map entry on its own and combines them in sorted order,
+/// so that the order of the entries doesn't matter.
+struct HashMapEntries<S> {
+    serializer: S,
+    entry: Option<sha2::Sha256>,
+    entry_digests: Vec<[u8; 32]>,
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeMap
+    for HashMapEntries<&mut HashSerializer<'_, '_, I, K>>
+{
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), HashError> {
+        let entry = self.entry.insert(sha2::Sha256::new());
+        key.serialize(&mut self.serializer.with_hasher(entry))
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), HashError> {
+        let mut entry = self
+            .entry
+            .take()
+            .ok_or_else(|| <HashError as ser::Error>::custom("map value without a key"))?;
+        value.serialize(&mut self.serializer.with_hasher(&mut entry))?;
+        self.entry_digests.push(entry.finalize().into());
+        Ok(())
+    }
+
+    fn end(mut self) -> Result<(), HashError> {
+        self.entry_digests.sort_unstable();
+        let hasher = &mut self.serializer.hasher;
+        hasher.update([5]);
+        hasher.update(self.entry_digests.len().to_le_bytes());
+        for digest in &self.entry_digests {
+            hasher.update(digest);
+        }
+        Ok(())
+    }
+}
+
+/// Hashes the fields of a struct in order, leaving out those of items
+/// that don't affect the API.
+struct HashStructFields<S> {
+    serializer: S,
+    is_item: bool,
+}
+
+impl<I: Serialize, K: Serialize> HashStructFields<&mut HashSerializer<'_, '_, I, K>> {
+    fn hash_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        if self.is_item && SKIPPED_ITEM_FIELDS.contains(&key) {
+            return Ok(());
+        }
+        hash_str(self.serializer.hasher, key);
+        value.serialize(&mut *self.serializer)
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeStruct
+    for HashStructFields<&mut HashSerializer<'_, '_, I, K>>
+{
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.hash_field(key, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.serializer.end_sequence()
+    }
+}
+
+impl<I: Serialize, K: Serialize> ser::SerializeStructVariant
+    for HashStructFields<&mut HashSerializer<'_, '_, I, K>>
+{
+    type Ok = ();
+    type Error = HashError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), HashError> {
+        self.hash_field(key, value)
+    }
+
+    fn end(self) -> Result<(), HashError> {
+        self.serializer.end_sequence()
+    }
+}
+
+fn hash_str(hasher: &mut sha2::Sha256, value: &str) {
+    // Length-prefix strings so adjacent values cannot collide by concatenating ambiguously.
+    hasher.update(value.len().to_le_bytes());
+    hasher.update(value.as_bytes());
+}
@@ -1,15 +1,23 @@
 use std::borrow::Cow;
+use std::collections::BTreeSet;
 use std::fmt::Write as _;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use anyhow::Context as _;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-use crate::data_generation::request::RequestKind;
+use crate::CancellationToken;
+use crate::data_generation::request::{RegistryRequest, RequestKind};
 use crate::util::atomic_write;
 
+use super::diagnosis::{
+    BuildFailureKind, diagnose_cargo_doc_failure, is_dependency_resolution_failure,
+};
 use super::error::{IntoTerminalResult as _, TerminalError};
 use super::progress::CallbackHandler;
+use super::recording::{RecordedRequest, RequestRecorder};
 use super::request::CrateDataRequest;
 
 const EXTRA_RUSTDOCFLAGS: &str = "-Z unstable-options --document-private-items --document-hidden-items --output-format=json --cap-lints=allow";
@@ -25,6 +33,7 @@ pub(crate) struct RustdocBuildEnvironment {
 impl RustdocBuildEnvironment {
     pub(crate) fn from_env_and_config_for_target(
         build_target: Option<&str>,
+        toolchain: Option<&str>,
     ) -> anyhow::Result<Self> {
         let flags = Flags::from_env_and_config_for_target(build_target)?;
 
@@ -50,7 +59,9 @@ impl RustdocBuildEnvironment {
 
         // Include the rustdoc toolchain version in artifact identity, since rustdoc JSON may
         // change across toolchains.
-        let cmd_output = std::process::Command::new("rustdoc")
+        let mut cmd = std::process::Command::new("rustdoc");
+        set_toolchain(&mut cmd, toolchain);
+        let cmd_output = cmd
             .arg("--version")
             .output()
             .context("'rustdoc --version' failed, is Rust installed correctly?")?;
@@ -77,9 +88,97 @@ impl RustdocBuildEnvironment {
             toolchain_version,
         })
     }
+
+    /// The flags in this environment that change which items are in a crate's rustdoc.
+    pub(crate) fn build_flags(&self) -> BuildFlags {
+        let flags = [
+            self.cargo_rustflags.as_ref(),
+            self.cargo_rustdocflags.as_ref(),
+        ];
+        BuildFlags {
+            target_triple: self.target_triple.clone(),
+            cfgs: flags.iter().flat_map(|flags| extract_cfgs(flags)).collect(),
+            target_features: flags
+                .iter()
+                .flat_map(|flags| extract_target_features(flags))
+                .collect(),
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+/// The build settings that change which items are in a crate's rustdoc,
+/// recorded with generated rustdoc so that the baseline and current builds can be compared.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BuildFlags {
+    pub(crate) target_triple: String,
+    /// Values set with `--cfg` in either `RUSTFLAGS` or `RUSTDOCFLAGS`.
+    #[serde(default)]
+    pub(crate) cfgs: BTreeSet<String>,
+    /// Values set with `-C target-feature`, like `+avx2`.
+    #[serde(default)]
+    pub(crate) target_features: BTreeSet<String>,
+}
+
+impl BuildFlags {
+    /// Read the flags that the rustdoc JSON at `json_path` was built with
+    /// from the `<json_path>.build-flags.json` file next to it, if there is one.
+    pub(crate) fn read_sidecar(json_path: &Path) -> anyhow::Result<Option<Self>> {
+        let mut sidecar = json_path.as_os_str().to_owned();
+        sidecar.push(".build-flags.json");
+        let sidecar = PathBuf::from(sidecar);
+        let text = match std::fs::read_to_string(&sidecar) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(anyhow::Error::from(e).context(format!(
+                    "failed to read build flags file {}",
+                    sidecar.display()
+                )));
+            }
+        };
+        serde_json::from_str(&text)
+            .map(Some)
+            .with_context(|| format!("failed to parse build flags file {}", sidecar.display()))
+    }
+
+    /// Describe the `--cfg` values and target features set for only one of the two builds,
+    /// one per line, or return `None` if both builds set the same ones.
+    pub(crate) fn differences(&self, baseline: &Self) -> Option<String> {
+        let mut differences = String::new();
+        for (kind, current_values, baseline_values) in [
+            ("--cfg", &self.cfgs, &baseline.cfgs),
+            (
+                "-C target-feature=",
+                &self.target_features,
+                &baseline.target_features,
+            ),
+        ] {
+            let separator = if kind.ends_with('=') { "" } else { " " };
+            for (side, values, others) in [
+                ("current", current_values, baseline_values),
+                ("baseline", baseline_values, current_values),
+            ] {
+                for value in values.difference(others) {
+                    writeln!(
+                        differences,
+                        "  `{kind}{separator}{value}` is only set for the {side} crate"
+                    )
+                    .expect("formatting failed");
+                }
+            }
+        }
+        (!differences.is_empty()).then_some(differences)
+    }
+}
+
+/// Run `cmd` with the given rustup toolchain, the same way as `cargo +<toolchain>` would.
+pub(super) fn set_toolchain(cmd: &mut std::process::Command, toolchain: Option<&str>) {
+    if let Some(toolchain) = toolchain {
+        cmd.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+}
+
+#[derive(Debug, Clone)]
 pub(crate) struct GenerationSettings {
     /// Whether to allow `cargo` invocations to print directly to our stderr (`true`)
     /// or pipe the output to a buffer available after-the-fact (`false`).
@@ -87,10 +186,27 @@ pub(crate) struct GenerationSettings {
 
     /// On `true`, pass `--color=always` to `cargo` invocations. On `false`, pass `--color=never`.
     pub(crate) use_color: bool,
+
+    /// On `true`, pass `--offline` to `cargo` invocations, so that they only use
+    /// crates that were already downloaded.
+    pub(crate) offline: bool,
+
+    /// How long each `cargo` invocation may run before it's killed. Unset means no limit.
+    pub(crate) timeout: Option<Duration>,
+
+    /// Once cancelled, any running `cargo` invocation is killed.
+    pub(crate) cancellation: Option<CancellationToken>,
+
+    /// Records each request as it's resolved, for a bug report bundle.
+    pub(crate) recorder: Option<RequestRecorder>,
+
+    /// Whether to fingerprint the public API of loaded rustdoc, so that lints
+    /// can be skipped when it's unchanged. Only needed if that skipping is enabled.
+    pub(crate) fingerprint_api: bool,
 }
 
 impl GenerationSettings {
-    fn stderr(&self) -> std::process::Stdio {
+    pub(super) fn stderr(&self) -> std::process::Stdio {
         if self.pass_through_stderr {
             // Print cargo update progress
             std::process::Stdio::inherit()
@@ -99,13 +215,210 @@ impl GenerationSettings {
         }
     }
 
-    fn color_flag(&self) -> &'static str {
+    pub(super) fn color_flag(&self) -> &'static str {
         if self.use_color {
             "--color=always"
         } else {
             "--color=never"
         }
     }
+
+    pub(super) fn offline_flag(&self) -> Option<&'static str> {
+        self.offline.then_some("--offline")
+    }
+
+    /// Explain failures that may be caused by crates missing from the local cache.
+    fn write_offline_note(&self, message: &mut String) {
+        if self.offline {
+            writeln!(
+                message,
+                "note: running in offline mode, so all crates must already be downloaded;"
+            )
+            .expect("formatting failed");
+            writeln!(
+                message,
+                "      run `cargo fetch` in a project depending on this crate version with network access to download them"
+            )
+            .expect("formatting failed");
+        }
+    }
+
+    fn interruption(&self, deadline: Option<Instant>) -> Option<Interruption> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            Some(Interruption::Cancelled)
+        } else if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            self.timeout.map(Interruption::TimedOut)
+        } else {
+            None
+        }
+    }
+}
+
+/// Why a `cargo` invocation was killed before it finished.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Interruption {
+    TimedOut(Duration),
+    Cancelled,
+}
+
+impl Interruption {
+    /// The error for `phase`, like `'cargo doc'`, interrupted while run for the requested crate.
+    fn into_terminal_error(
+        self,
+        phase: &str,
+        request: &CrateDataRequest<'_>,
+        crate_name: &str,
+        version: &str,
+        repro_command: &str,
+    ) -> TerminalError {
+        let side = if request.is_baseline {
+            "baseline"
+        } else {
+            "current"
+        };
+        match self {
+            Self::Cancelled => TerminalError::Other(anyhow::anyhow!(
+                "{phase} on {side} crate {crate_name} v{version} was cancelled"
+            )),
+            Self::TimedOut(timeout) => {
+                let mut message = String::with_capacity(1024);
+                writeln!(
+                    message,
+                    "note: this is often due to a build script or procedural macro that hangs,"
+                )
+                .expect("formatting failed");
+                writeln!(
+                    message,
+                    "      such as one waiting on a lock, or a very slow build"
+                )
+                .expect("formatting failed");
+                writeln!(
+                    message,
+                    "note: pass a longer `--timeout` if the build only needs more time"
+                )
+                .expect("formatting failed");
+                writeln!(
+                    message,
+                    "note: the following command can be used to reproduce the hang:"
+                )
+                .expect("formatting failed");
+                let repro_base = produce_repro_workspace_shell_commands(request);
+                writeln!(message, "{repro_base}{repro_command}").expect("formatting failed");
+
+                TerminalError::WithAdvice(
+                    anyhow::anyhow!(
+                        "aborting because {phase} on {side} crate {crate_name} v{version} \
+                         timed out after {}s",
+                        timeout.as_secs()
+                    ),
+                    message,
+                )
+            }
+        }
+    }
+}
+
+/// How often to check whether a running `cargo` invocation should be interrupted.
+const INTERRUPTION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Run `cmd` to completion and collect its output, like [`std::process::Command::output`].
+/// If it runs past the configured timeout or the check is cancelled, it's killed instead.
+///
+/// Unlike `output()`, stdout and stderr are only captured if they're set to be piped.
+pub(super) fn run_interruptibly(
+    cmd: &mut std::process::Command,
+    settings: &GenerationSettings,
+) -> std::io::Result<Result<std::process::Output, Interruption>> {
+    if settings.timeout.is_none() && settings.cancellation.is_none() {
+        return cmd.output().map(Ok);
+    }
+
+    let deadline = settings.timeout.map(|timeout| Instant::now() + timeout);
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt as _;
+        // Lead a process group of its own, so that build scripts, compilers, and other
+        // processes it starts can be killed along with it.
+        cmd.process_group(0);
+    }
+    let mut child = cmd.spawn()?;
+
+    // Drain the pipes on other threads, so the child never blocks on a full pipe.
+    fn read_in_background(
+        pipe: Option<impl std::io::Read + Send + 'static>,
+    ) -> Option<std::thread::JoinHandle<std::io::Result<Vec<u8>>>> {
+        pipe.map(|mut pipe| {
+            std::thread::spawn(move || {
+                let mut buffer = Vec::new();
+                pipe.read_to_end(&mut buffer).map(|_| buffer)
+            })
+        })
+    }
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+    let collect = |reader: Option<std::thread::JoinHandle<std::io::Result<Vec<u8>>>>| {
+        reader.map_or(Ok(Vec::new()), |reader| {
+            reader.join().expect("output reader thread panicked")
+        })
+    };
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Ok(std::process::Output {
+                status,
+                stdout: collect(stdout)?,
+                stderr: collect(stderr)?,
+            }));
+        }
+        if let Some(interruption) = settings.interruption(deadline) {
+            kill_process_group(&mut child);
+            child.wait()?;
+            // Processes that left the process group may outlive it
+            // and keep its pipes open, so the reader threads are left detached.
+            return Ok(Err(interruption));
+        }
+        std::thread::sleep(INTERRUPTION_POLL_INTERVAL);
+    }
+}
+
+/// Kill `child` and, on Unix, every process in the process group it leads.
+fn kill_process_group(child: &mut std::process::Child) {
+    // The child isn't reaped yet, so its id still identifies its process group.
+    // Killing fails only if the processes already exited, which is fine too.
+    #[cfg(unix)]
+    let _ = std::process::Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", child.id())])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+    let _ = child.kill();
+}
+
+/// Run `cmd` like [`run_interruptibly`], but with its stdout and stderr merged into one stream,
+/// in the order they were written. The merged stream is returned as the output's stdout.
+fn run_interruptibly_merged(
+    mut cmd: std::process::Command,
+    settings: &GenerationSettings,
+) -> std::io::Result<Result<std::process::Output, Interruption>> {
+    let (mut reader, writer) = std::io::pipe()?;
+    cmd.stdout(writer.try_clone()?).stderr(writer);
+    let merged = std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut buffer).map(|_| buffer)
+    });
+    let result = run_interruptibly(&mut cmd, settings)?;
+    // The command holds the pipe's write ends, which must be closed for reading to finish.
+    drop(cmd);
+    let mut output = match result {
+        Ok(output) => output,
+        Err(interruption) => return Ok(Err(interruption)),
+    };
+    output.stdout = merged.join().expect("output reader thread panicked")?;
+    Ok(Ok(output))
 }
 
 pub(super) fn generate_rustdoc(
@@ -129,9 +442,11 @@ pub(super) fn generate_rustdoc(
         save_placeholder_rustdoc_manifest(build_dir, placeholder_manifest)
             .context("failed to save placeholder rustdoc manifest")
             .into_terminal_result()?;
+    seed_lockfile(request, build_dir).into_terminal_result()?;
     callbacks.generate_placeholder_project_success();
 
-    if matches!(request.kind, RequestKind::LocalProject(..)) {
+    // A seeded lockfile already determines the dependency versions to use on both sides.
+    if matches!(request.kind, RequestKind::LocalProject(..)) && request.lockfile.is_none() {
         // We have to run `cargo update` inside the newly-generated project, to ensure
         // all dependencies of the library we're scanning are up-to-date.
         //
@@ -160,18 +475,35 @@ pub(super) fn generate_rustdoc(
                     .context("IO error while running 'cargo update' on placeholder project");
                 return Err(TerminalError::Other(error));
             }
+            CargoUpdateResult::ResolutionFailed(stderr) => {
+                return Err(dependency_resolution_error(
+                    request,
+                    crate_name,
+                    version.as_ref(),
+                    "cargo update",
+                    &stderr,
+                    &settings,
+                ));
+            }
             CargoUpdateResult::ErrorReturned(_exit_status, message) => {
                 let error = anyhow::anyhow!(
                     "aborting due to failure to run 'cargo update' for crate {crate_name} v{version}"
                 );
                 return Err(TerminalError::WithAdvice(error, message));
             }
+            CargoUpdateResult::Interrupted(interruption) => {
+                return Err(interruption.into_terminal_error(
+                    "'cargo update'",
+                    request,
+                    crate_name,
+                    version.as_ref(),
+                    "cargo update",
+                ));
+            }
         }
     }
 
-    let metadata = cargo_metadata::MetadataCommand::new()
-        .manifest_path(&placeholder_manifest_path)
-        .exec()?;
+    let metadata = placeholder_metadata(&placeholder_manifest_path, request, &settings)?;
     let placeholder_target_directory = metadata.target_directory.as_path().as_std_path().to_owned();
     let target_dir = placeholder_target_directory.as_path();
 
@@ -190,16 +522,183 @@ pub(super) fn generate_rustdoc(
     Ok((rustdoc_data, metadata))
 }
 
+/// Copy the request's lockfile, if any, into the placeholder project in `build_dir`.
+pub(super) fn seed_lockfile(
+    request: &CrateDataRequest<'_>,
+    build_dir: &Path,
+) -> anyhow::Result<()> {
+    if let Some(lockfile) = request.lockfile {
+        // Cargo keeps the locked versions of any dependencies that still fit,
+        // and resolves only what's missing from the lockfile.
+        fs_err::copy(lockfile, build_dir.join("Cargo.lock"))
+            .context("failed to seed placeholder project with lockfile")?;
+    }
+    Ok(())
+}
+
+/// Cargo config that replaces the registry of `request` with its vendored crates, if any,
+/// as pairs of dotted keys and TOML values.
+fn vendored_registry_config(request: &CrateDataRequest<'_>) -> Vec<(String, String)> {
+    let RequestKind::Registry(RegistryRequest {
+        registry: Some(registry),
+        vendored_crates: Some(vendored_crates),
+        ..
+    }) = &request.kind
+    else {
+        return Vec::new();
+    };
+
+    // The registry's index is never read, since the vendored crates replace it,
+    // but cargo still requires one to identify the registry by.
+    let index = toml::Value::from(format!("file://{}", vendored_crates.display())).to_string();
+    let vendored_source = format!("{registry}-vendored");
+    vec![
+        (format!("registries.{registry}.index"), index.clone()),
+        (format!("source.{registry}.registry"), index),
+        (
+            format!("source.{registry}.replace-with"),
+            toml::Value::from(vendored_source.as_str()).to_string(),
+        ),
+        (
+            format!("source.{vendored_source}.directory"),
+            toml::Value::from(vendored_crates.to_string_lossy().as_ref()).to_string(),
+        ),
+    ]
+}
+
+/// The `--config` arguments that cargo commands on the placeholder project of `request` need.
+pub(super) fn config_args(request: &CrateDataRequest<'_>) -> Vec<String> {
+    vendored_registry_config(request)
+        .into_iter()
+        .flat_map(|(key, value)| ["--config".to_owned(), format!("{key}={value}")])
+        .collect()
+}
+
+/// Run `cargo metadata` on a placeholder project, resolving its dependencies.
+/// Like other `cargo` invocations, it's killed if it times out or the check is cancelled.
+pub(super) fn placeholder_metadata(
+    placeholder_manifest_path: &Path,
+    request: &CrateDataRequest<'_>,
+    settings: &GenerationSettings,
+) -> Result<cargo_metadata::Metadata, TerminalError> {
+    let mut metadata_command = cargo_metadata::MetadataCommand::new();
+    metadata_command.manifest_path(placeholder_manifest_path);
+    if let Some(toolchain) = request.toolchain() {
+        metadata_command.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+    let mut other_options = config_args(request);
+    other_options.extend(settings.offline_flag().map(ToString::to_string));
+    metadata_command.other_options(other_options);
+
+    // Run the command like `MetadataCommand::exec()` does.
+    let mut cmd = metadata_command.cargo_command();
+    cmd.stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let output = match run_interruptibly(&mut cmd, settings)? {
+        Ok(output) => output,
+        Err(interruption) => {
+            return Err(interruption.into_terminal_error(
+                "'cargo metadata'",
+                request,
+                request.kind.name().into_terminal_result()?,
+                request.kind.version().into_terminal_result()?.as_ref(),
+                "cargo metadata",
+            ));
+        }
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8(output.stderr)?;
+        if is_dependency_resolution_failure(&stderr) {
+            return Err(dependency_resolution_error(
+                request,
+                request.kind.name().into_terminal_result()?,
+                request.kind.version().into_terminal_result()?.as_ref(),
+                "cargo metadata",
+                &stderr,
+                settings,
+            ));
+        }
+        return Err(cargo_metadata::Error::CargoMetadata { stderr }.into());
+    }
+    let stdout = std::str::from_utf8(&output.stdout)?
+        .lines()
+        .find(|line| line.starts_with('{'))
+        .ok_or(cargo_metadata::Error::NoJson)?;
+    Ok(cargo_metadata::MetadataCommand::parse(stdout)?)
+}
+
+const LOCKFILE_NOTE: &str = "note: passing `--lockfile` keeps dependencies at the versions \
+                             in a Cargo.lock, which may avoid this";
+
+/// Explain that cargo couldn't resolve the dependencies of `this_crate`, like `a v1.0.0`.
+fn write_dependency_resolution_note(message: &mut String, this_crate: &str) {
+    writeln!(
+        message,
+        "note: cargo could not resolve the dependencies of crate {this_crate};"
+    )
+    .expect("formatting failed");
+    writeln!(
+        message,
+        "      a dependency may have been yanked, or need a newer toolchain than this one"
+    )
+    .expect("formatting failed");
+    writeln!(message, "{LOCKFILE_NOTE}").expect("formatting failed");
+}
+
+/// The error for `command`, like `cargo metadata`, failing to resolve the dependencies
+/// of the requested crate with the given stderr.
+fn dependency_resolution_error(
+    request: &CrateDataRequest<'_>,
+    crate_name: &str,
+    version: &str,
+    command: &str,
+    stderr: &str,
+    settings: &GenerationSettings,
+) -> TerminalError {
+    let this_crate = format!("{crate_name} v{version}");
+    let delimiter = "-----";
+    let mut message = String::with_capacity(1024);
+    writeln!(
+        message,
+        "error: running '{command}' on crate '{crate_name}' failed with output:\n\
+         {delimiter}\n{stderr}\n{delimiter}\n"
+    )
+    .expect("formatting failed");
+    write_dependency_resolution_note(&mut message, &this_crate);
+    settings.write_offline_note(&mut message);
+    writeln!(
+        message,
+        "note: the following command can be used to reproduce the error:"
+    )
+    .expect("formatting failed");
+    let repro_base = produce_repro_workspace_shell_commands(request);
+    writeln!(message, "{repro_base}{command}").expect("formatting failed");
+
+    let kind = BuildFailureKind::DependencyResolution;
+    TerminalError::BuildFailure(
+        kind,
+        anyhow::anyhow!(
+            "aborting due to failure to resolve the dependencies of crate {this_crate}: {}",
+            kind.describe()
+        ),
+        message,
+    )
+}
+
 fn produce_repro_workspace_shell_commands(request: &CrateDataRequest<'_>) -> String {
     let selector = match &request.kind {
-        RequestKind::Registry { .. } => format!(
-            "{}@={}",
+        RequestKind::Registry(registry_request) => format!(
+            "{}@={}{}",
             request.kind.name().expect("failed to get crate name"),
             request
                 .kind
                 .version()
                 .expect("failed to get crate version")
-                .as_ref()
+                .as_ref(),
+            registry_request
+                .registry
+                .map(|registry| format!(" --registry {registry}"))
+                .unwrap_or_default(),
         ),
         RequestKind::LocalProject(project) => format!(
             "--path {}",
@@ -224,19 +723,81 @@ fn produce_repro_workspace_shell_commands(request: &CrateDataRequest<'_>) -> Str
     } else {
         format!("--features {} ", request.extra_features.iter().join(","))
     };
+    let toolchain = request
+        .toolchain
+        .map(|toolchain| format!("export RUSTUP_TOOLCHAIN={toolchain} &&\n          "))
+        .unwrap_or_default();
+    let lockfile = request
+        .lockfile
+        .map(|lockfile| {
+            let lockfile = lockfile
+                .canonicalize()
+                .unwrap_or_else(|_| lockfile.to_owned());
+            format!("cp {} Cargo.lock &&\n          ", lockfile.display())
+        })
+        .unwrap_or_default();
+    let cargo_config = vendored_registry_config(request);
+    let cargo_config = if cargo_config.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "mkdir .cargo &&\n          printf '%s\\n' {} > .cargo/config.toml &&\n          ",
+            cargo_config
+                .iter()
+                .map(|(key, value)| format!("'{key} = {value}'"))
+                .join(" ")
+        )
+    };
     format!(
         "      \
-    cargo new --lib example &&
+    {toolchain}cargo new --lib example &&
           cd example &&
           echo '[workspace]' >> Cargo.toml &&
-          cargo add {selector} {no_default_features}{feature_list}&&
+          {cargo_config}{lockfile}cargo add {selector} {no_default_features}{feature_list}&&
           "
     )
 }
 
+/// Describe `request` for a bug report, with its rustdoc JSON loaded from `rustdoc_json`
+/// if it's been loaded yet.
+pub(super) fn record_request(
+    request: &CrateDataRequest<'_>,
+    rustdoc_json: Option<PathBuf>,
+) -> anyhow::Result<RecordedRequest> {
+    let placeholder_manifest = toml::to_string(&create_placeholder_rustdoc_manifest(request)?)?;
+    let build_target_flag = request
+        .build_target
+        .map(|build_target| format!(" --target {build_target}"))
+        .unwrap_or_default();
+    let repro_commands = format!(
+        "{}cargo doc{build_target_flag}\n",
+        produce_repro_workspace_shell_commands(request)
+    );
+    Ok(RecordedRequest {
+        crate_name: request.kind.name()?.to_string(),
+        version: request.kind.version()?.to_string(),
+        is_baseline: request.is_baseline,
+        default_features: request.default_features,
+        extra_features: request
+            .extra_features
+            .iter()
+            .map(|feature| feature.to_string())
+            .collect(),
+        build_target: request.build_target.map(str::to_string),
+        toolchain: request.toolchain.map(str::to_string),
+        lockfile: request.lockfile.map(Path::to_path_buf),
+        placeholder_manifest,
+        repro_commands,
+        rustdoc_json,
+    })
+}
+
 enum CargoUpdateResult {
     Success,
     IoError(std::io::Error),
+    Interrupted(Interruption),
+    /// Cargo couldn't resolve the dependencies, as shown by the captured stderr.
+    ResolutionFailed(String),
     ErrorReturned(std::process::ExitStatus, String),
 }
 
@@ -422,6 +983,49 @@ fn extract_cfg_related_flags(flags: &str) -> Vec<String> {
     extracted
 }
 
+/// The values set with `--cfg` in `flags`.
+fn extract_cfgs(flags: &str) -> Vec<String> {
+    let cfg_related = extract_cfg_related_flags(flags);
+    let mut tokens = cfg_related.iter();
+    let mut cfgs = Vec::new();
+    while let Some(token) = tokens.next() {
+        if let Some(value) = token.strip_prefix("--cfg=") {
+            cfgs.push(value.to_owned());
+        } else if token == "--cfg" {
+            cfgs.extend(tokens.next().cloned());
+        } else if token == "--check-cfg" {
+            // Skip the value, which only declares which cfgs are expected.
+            tokens.next();
+        }
+    }
+    cfgs
+}
+
+/// The target features enabled or disabled with `-C target-feature` in `flags`, like `+avx2`.
+fn extract_target_features(flags: &str) -> Vec<String> {
+    let mut tokens = flags.split_whitespace();
+    let mut features = Vec::new();
+    while let Some(token) = tokens.next() {
+        let codegen_option = match token {
+            "-C" | "--codegen" => tokens.next(),
+            _ => token
+                .strip_prefix("-C")
+                .or_else(|| token.strip_prefix("--codegen=")),
+        };
+        if let Some(value) =
+            codegen_option.and_then(|option| option.strip_prefix("target-feature="))
+        {
+            features.extend(
+                value
+                    .split(',')
+                    .filter(|feature| !feature.is_empty())
+                    .map(str::to_owned),
+            );
+        }
+    }
+    features
+}
+
 fn combine_witness_rustflags(mut rustflags: String, rustdocflags: &str) -> Option<String> {
     let cfg_related = extract_cfg_related_flags(rustdocflags);
     if cfg_related.is_empty() {
@@ -463,6 +1067,7 @@ fn run_cargo_update(
     settings: &GenerationSettings,
 ) -> CargoUpdateResult {
     let mut cmd = std::process::Command::new("cargo");
+    set_toolchain(&mut cmd, request.toolchain());
     cmd.stdout(std::process::Stdio::null()) // Don't pollute output
         .stderr(settings.stderr())
         .arg("update")
@@ -471,13 +1076,20 @@ fn run_cargo_update(
 
     // Respect our configured color choice.
     cmd.arg(settings.color_flag());
+    cmd.args(settings.offline_flag());
 
-    let output = match cmd.output() {
-        Ok(output) => output,
+    let output = match run_interruptibly(&mut cmd, settings) {
+        Ok(Ok(output)) => output,
+        Ok(Err(interruption)) => return CargoUpdateResult::Interrupted(interruption),
         Err(e) => return CargoUpdateResult::IoError(e),
     };
 
     if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if is_dependency_resolution_failure(&stderr) {
+            return CargoUpdateResult::ResolutionFailed(stderr.into_owned());
+        }
+
         let mut message = String::with_capacity(1024);
         if settings.pass_through_stderr {
             writeln!(message, "error: running 'cargo update' on crate '{crate_name}' v{version} failed, see stderr output above").expect("formatting failed");
@@ -487,9 +1099,8 @@ fn run_cargo_update(
                 message,
                 "\
                 error: running 'cargo update' on crate '{crate_name}' failed with output:\n\
-                {delimiter}\n{}\n{delimiter}\n\
+                {delimiter}\n{stderr}\n{delimiter}\n\
                 error: failed to update dependencies for crate {crate_name} v{version}",
-                String::from_utf8_lossy(&output.stderr)
             )
             .expect("formatting failed");
         }
@@ -503,6 +1114,7 @@ fn run_cargo_update(
             "      and is probably an issue with the crate's Cargo.toml"
         )
         .expect("formatting failed");
+        settings.write_offline_note(&mut message);
         writeln!(
             message,
             "note: the following command can be used to reproduce the compilation error:"
@@ -541,14 +1153,13 @@ fn run_cargo_doc(
     //       of the placeholder project.
     callbacks.generate_rustdoc_start();
     let mut cmd = std::process::Command::new("cargo");
+    set_toolchain(&mut cmd, request.toolchain());
     cmd.env("RUSTC_BOOTSTRAP", "1")
         .env(
             "RUSTDOCFLAGS",
             build_environment.cargo_rustdocflags.as_ref(),
         )
         .env("RUSTFLAGS", build_environment.cargo_rustflags.as_ref())
-        .stdout(std::process::Stdio::null()) // Don't pollute output
-        .stderr(settings.stderr())
         .arg("doc")
         .arg("--manifest-path")
         .arg(placeholder_manifest_path)
@@ -562,88 +1173,52 @@ fn run_cargo_doc(
     }
     cmd.arg("--no-deps");
 
+    // Compiler errors are reported as JSON, so we can tell why the build failed.
+    cmd.arg(if settings.use_color {
+        "--message-format=json-diagnostic-rendered-ansi"
+    } else {
+        "--message-format=json"
+    });
+
     // Respect our configured color choice
     cmd.arg(settings.color_flag());
-
-    let output = cmd.output()?;
-    if !output.status.success() {
-        let mut message = String::with_capacity(1024);
-        if settings.pass_through_stderr {
-            writeln!(message, "error: running cargo-doc on crate {crate_name} v{version} failed, see stderr output above").expect("formatting failed");
-        } else {
-            let delimiter = "-----";
-            writeln!(
-                message,
-                "error: running cargo-doc on crate '{crate_name}' failed with output:"
-            )
-            .expect("formatting failed");
-            writeln!(
-                message,
-                "{delimiter}\n{}\n{delimiter}\n",
-                String::from_utf8_lossy(&output.stderr)
-            )
-            .expect("formatting failed");
-            writeln!(
-                message,
-                "error: failed to build rustdoc for crate {crate_name} v{version}"
-            )
-            .expect("formatting failed");
-        }
-
-        writeln!(
-            message,
-            "note: this is usually due to a compilation error in the crate,"
-        )
-        .expect("formatting failed");
-        writeln!(
-            message,
-            "      and is unlikely to be a bug in cargo-semver-checks"
-        )
-        .expect("formatting failed");
-
-        if build_environment.cargo_rustflags.contains("--cfg") {
-            writeln!(
-                message,
-                "note: RUSTFLAGS appears to contain '--cfg' arguments."
-            )
-            .expect("formatting failed");
-            writeln!(
-                message,
-                "      Rustdoc only uses '--cfg' options in RUSTDOCFLAGS."
-            )
-            .expect("formatting failed");
-            writeln!(
-                message,
-                "      Setting the same flags in RUSTDOCFLAGS may resolve the problem."
-            )
-            .expect("formatting failed");
+    cmd.args(settings.offline_flag());
+    cmd.args(config_args(request));
+
+    // Unless stderr is passed through, it's merged into stdout, so that cargo's messages
+    // and the compiler messages it reports as JSON can be shown in their original order.
+    let output = if settings.pass_through_stderr {
+        cmd.stdout(std::process::Stdio::piped())
+            .stderr(settings.stderr());
+        run_interruptibly(&mut cmd, settings)?
+    } else {
+        run_interruptibly_merged(cmd, settings)?
+    };
+    let output = match output {
+        Ok(output) => output,
+        Err(interruption) => {
+            let build_target_flag = request
+                .build_target
+                .map(|build_target| format!(" --target {build_target}"))
+                .unwrap_or_default();
+            return Err(interruption.into_terminal_error(
+                "'cargo doc'",
+                request,
+                crate_name,
+                version,
+                &format!("cargo doc{build_target_flag}"),
+            ));
         }
-
-        writeln!(
-            message,
-            "note: the following command can be used to reproduce the error:"
-        )
-        .expect("formatting failed");
-
-        let repro_base = produce_repro_workspace_shell_commands(request);
-        let build_target_flag = if let Some(build_target) = request.build_target {
-            format!(" --target {build_target}")
-        } else {
-            String::new()
-        };
-        writeln!(
-            message,
-            "\
-{repro_base}cargo check{build_target_flag} &&
-          cargo doc{build_target_flag}"
-        )
-        .expect("formatting failed");
-
-        return Err(TerminalError::WithAdvice(
-            anyhow::anyhow!(
-                "aborting due to failure to build rustdoc for crate {crate_name} v{version}"
-            ),
-            message,
+    };
+    if !output.status.success() {
+        return Err(cargo_doc_failure_error(
+            request,
+            metadata,
+            crate_name,
+            version,
+            settings,
+            build_environment,
+            &output,
         ));
     }
 
@@ -660,7 +1235,7 @@ fn run_cargo_doc(
     // choosing a baseline version, and raise this error sooner. Alas, until the index
     // can give us that data more easily, we can't do that in a reasonable way.
     let observed_stderr_but_lib_msg_not_present = if !settings.pass_through_stderr {
-        let stderr_output = String::from_utf8_lossy(&output.stderr);
+        let stderr_output = String::from_utf8_lossy(&output.stdout);
         !stderr_output.contains("ignoring invalid dependency ")
             || !stderr_output.contains(" which is missing a lib target")
     } else {
@@ -682,20 +1257,7 @@ in the metadata and stderr didn't mention it was lacking a lib target. This is p
         })
         .into_terminal_result()?;
 
-    // Figure out the name of the JSON file where rustdoc will produce the output we want.
-    // The name is:
-    // - the name of the library-like target of the crate, not the crate's name
-    // - but with all `-` chars replaced with `_` instead.
-    // Related: https://github.com/obi1kenobi/cargo-semver-checks/issues/432
-    if let Some(lib_target) = subject_crate
-        .targets
-        .iter()
-        .find(|target| crate::is_lib_like_checkable_target(target))
-    {
-        let lib_name = lib_target.name.as_str();
-        let rustdoc_json_file_name = lib_name.replace('-', "_");
-
-        let json_path = rustdoc_dir.join(format!("{rustdoc_json_file_name}.json"));
+    if let Some(json_path) = rustdoc_json_path(&rustdoc_dir, subject_crate) {
         if json_path.exists() {
             callbacks.generate_rustdoc_success();
             return Ok(json_path);
@@ -713,7 +1275,222 @@ in the metadata and stderr didn't mention it was lacking a lib target. This is p
     )))
 }
 
-fn determine_rustdoc_dir(
+/// Where rustdoc puts the JSON for `package` in `rustdoc_dir`,
+/// or `None` if the package has no library-like target.
+pub(super) fn rustdoc_json_path(
+    rustdoc_dir: &Path,
+    package: &cargo_metadata::Package,
+) -> Option<PathBuf> {
+    // The name of the JSON file where rustdoc will produce the output we want is:
+    // - the name of the library-like target of the crate, not the crate's name
+    // - but with all `-` chars replaced with `_` instead.
+    // Related: https://github.com/obi1kenobi/cargo-semver-checks/issues/432
+    let lib_target = package
+        .targets
+        .iter()
+        .find(|target| crate::is_lib_like_checkable_target(target))?;
+    let rustdoc_json_file_name = lib_target.name.replace('-', "_");
+    Some(rustdoc_dir.join(format!("{rustdoc_json_file_name}.json")))
+}
+
+/// Explain why `cargo doc` failed, with advice specific to the cause if we can diagnose it.
+fn cargo_doc_failure_error(
+    request: &CrateDataRequest<'_>,
+    metadata: &cargo_metadata::Metadata,
+    crate_name: &str,
+    version: &str,
+    settings: &GenerationSettings,
+    build_environment: &RustdocBuildEnvironment,
+    output: &std::process::Output,
+) -> TerminalError {
+    let diagnosis = diagnose_cargo_doc_failure(&output.stdout, |package_id| {
+        metadata
+            .packages
+            .iter()
+            .find(|package| &package.id == package_id)
+            .map(|package| format!("{} v{}", package.name, package.version))
+    });
+    let mut message = String::with_capacity(1024);
+    if settings.pass_through_stderr {
+        writeln!(message, "error: running cargo-doc on crate {crate_name} v{version} failed, see stderr output above").expect("formatting failed");
+        // Cargo reports compiler errors as JSON messages instead of printing them to stderr.
+        let rendered_errors = diagnosis.rendered_errors.concat();
+        if !rendered_errors.is_empty() {
+            writeln!(message, "{rendered_errors}").expect("formatting failed");
+        }
+    } else {
+        let delimiter = "-----";
+        writeln!(
+            message,
+            "error: running cargo-doc on crate '{crate_name}' failed with output:"
+        )
+        .expect("formatting failed");
+        writeln!(
+            message,
+            "{delimiter}\n{}\n{delimiter}\n",
+            diagnosis.transcript
+        )
+        .expect("formatting failed");
+        writeln!(
+            message,
+            "error: failed to build rustdoc for crate {crate_name} v{version}"
+        )
+        .expect("formatting failed");
+    }
+
+    let this_crate = format!("{crate_name} v{version}");
+    let culprit = diagnosis.culprit.as_deref();
+    match diagnosis.kind {
+        None | Some(BuildFailureKind::CompileError) => {
+            match culprit.filter(|culprit| *culprit != this_crate) {
+                None => {
+                    writeln!(
+                        message,
+                        "note: this is usually due to a compilation error in the crate,"
+                    )
+                    .expect("formatting failed");
+                    writeln!(
+                        message,
+                        "      and is unlikely to be a bug in cargo-semver-checks"
+                    )
+                    .expect("formatting failed");
+                }
+                Some(dependency) => {
+                    writeln!(
+                        message,
+                        "note: dependency {dependency} of crate {this_crate} failed to compile;"
+                    )
+                    .expect("formatting failed");
+                    writeln!(
+                        message,
+                        "      a newer release of it may be incompatible with the crate \
+                         or the toolchain"
+                    )
+                    .expect("formatting failed");
+                    writeln!(message, "{LOCKFILE_NOTE}").expect("formatting failed");
+                }
+            }
+        }
+        Some(BuildFailureKind::DependencyResolution) => {
+            write_dependency_resolution_note(&mut message, &this_crate);
+        }
+        Some(BuildFailureKind::BuildScript) => {
+            writeln!(
+                message,
+                "note: the build script of {} failed;",
+                culprit.unwrap_or("a dependency")
+            )
+            .expect("formatting failed");
+            writeln!(
+                message,
+                "      build scripts may need tools, environment variables, or files \
+                 that aren't available here"
+            )
+            .expect("formatting failed");
+        }
+        Some(BuildFailureKind::MissingSystemLibrary) => {
+            writeln!(
+                message,
+                "note: a system library needed by {} was not found;",
+                culprit.unwrap_or("a dependency")
+            )
+            .expect("formatting failed");
+            writeln!(
+                message,
+                "      install its development package, or set `PKG_CONFIG_PATH` \
+                 to the directory with its `.pc` file"
+            )
+            .expect("formatting failed");
+        }
+        Some(BuildFailureKind::UnsupportedTarget) => match request.build_target {
+            Some(build_target) => {
+                let toolchain = request
+                    .toolchain()
+                    .map(|toolchain| format!(" --toolchain {toolchain}"))
+                    .unwrap_or_default();
+                writeln!(
+                    message,
+                    "note: the standard library for target `{build_target}` was not found;"
+                )
+                .expect("formatting failed");
+                writeln!(
+                    message,
+                    "      to install it, run `rustup target add {build_target}{toolchain}`"
+                )
+                .expect("formatting failed");
+            }
+            None => {
+                writeln!(
+                    message,
+                    "note: the standard library for the build target was not found;"
+                )
+                .expect("formatting failed");
+                writeln!(
+                    message,
+                    "      check that the target set by `build.target` in cargo config \
+                     is installed"
+                )
+                .expect("formatting failed");
+            }
+        },
+    }
+    settings.write_offline_note(&mut message);
+
+    if build_environment.cargo_rustflags.contains("--cfg") {
+        writeln!(
+            message,
+            "note: RUSTFLAGS appears to contain '--cfg' arguments."
+        )
+        .expect("formatting failed");
+        writeln!(
+            message,
+            "      Rustdoc only uses '--cfg' options in RUSTDOCFLAGS."
+        )
+        .expect("formatting failed");
+        writeln!(
+            message,
+            "      Setting the same flags in RUSTDOCFLAGS may resolve the problem."
+        )
+        .expect("formatting failed");
+    }
+
+    writeln!(
+        message,
+        "note: the following command can be used to reproduce the error:"
+    )
+    .expect("formatting failed");
+
+    let repro_base = produce_repro_workspace_shell_commands(request);
+    let build_target_flag = if let Some(build_target) = request.build_target {
+        format!(" --target {build_target}")
+    } else {
+        String::new()
+    };
+    writeln!(
+        message,
+        "\
+{repro_base}cargo check{build_target_flag} &&
+          cargo doc{build_target_flag}"
+    )
+    .expect("formatting failed");
+
+    match diagnosis.kind {
+        Some(kind) => TerminalError::BuildFailure(
+            kind,
+            anyhow::anyhow!(
+                "aborting due to failure to build rustdoc for crate {this_crate}: {}",
+                kind.describe()
+            ),
+            message,
+        ),
+        None => TerminalError::WithAdvice(
+            anyhow::anyhow!("aborting due to failure to build rustdoc for crate {this_crate}"),
+            message,
+        ),
+    }
+}
+
+pub(super) fn determine_rustdoc_dir(
     request: &CrateDataRequest<'_>,
     target_dir: &Path,
     crate_name: &str,
@@ -729,7 +1506,9 @@ fn determine_rustdoc_dir(
     // based on its config files and environment variables. The best way to check this
     // is to ask cargo itself.
     let build_target = {
-        let output = std::process::Command::new("cargo")
+        let mut cmd = std::process::Command::new("cargo");
+        set_toolchain(&mut cmd, request.toolchain());
+        let output = cmd
             .env("RUSTC_BOOTSTRAP", "1")
             .args([
                 "config",
@@ -809,8 +1588,16 @@ fn determine_rustdoc_dir(
 
 /// To get the rustdoc of the project, we first create a placeholder project somewhere
 /// with the project as a dependency, and run `cargo rustdoc` on it.
-fn create_placeholder_rustdoc_manifest(
+pub(super) fn create_placeholder_rustdoc_manifest(
     request: &CrateDataRequest<'_>,
+) -> anyhow::Result<cargo_toml::Manifest<()>> {
+    create_combined_placeholder_rustdoc_manifest(&[request])
+}
+
+/// Like [`create_placeholder_rustdoc_manifest`], but with all of `requests` as dependencies,
+/// so their rustdoc can be generated in a single `cargo doc` invocation.
+pub(super) fn create_combined_placeholder_rustdoc_manifest(
+    requests: &[&CrateDataRequest<'_>],
 ) -> anyhow::Result<cargo_toml::Manifest<()>> {
     use cargo_toml::*;
 
@@ -834,62 +1621,65 @@ fn create_placeholder_rustdoc_manifest(
             Some(product)
         },
         dependencies: {
-            let project_with_features: DependencyDetail = match &request.kind {
-                RequestKind::Registry { .. } => DependencyDetail {
-                    // We need the *exact* version as a dependency, or else cargo will
-                    // give us the latest semver-compatible version which is not we want.
-                    // Fixes: https://github.com/obi1kenobi/cargo-semver-checks/issues/261
-                    version: Some(
-                        format!("={}", request.kind.version()?.as_ref())
-                            .parse()
-                            .context("failed to parse exact dependency version requirement")?,
-                    ),
-                    default_features: request.default_features,
-                    features: request
-                        .extra_features
-                        .iter()
-                        .map(ToString::to_string)
-                        .collect(),
-                    ..DependencyDetail::default()
-                },
-                RequestKind::LocalProject(local_request) => {
-                    DependencyDetail {
-                        path: Some({
-                            let dir_path = crate::manifest::get_project_dir_from_manifest_path(
-                                &local_request.manifest.path,
-                            )?;
-                            // The manifest will be saved in some other directory,
-                            // so for convenience, we're using absolute paths.
-                            dir_path
-                                .canonicalize()
-                                .context("failed to canonicalize manifest path")?
-                                .to_str()
-                                .context("manifest path is not valid UTF-8")?
-                                .to_string()
-                        }),
+            let mut deps = DepsSet::new();
+            for request in requests {
+                let project_with_features: DependencyDetail = match &request.kind {
+                    RequestKind::Registry(registry_request) => DependencyDetail {
+                        // We need the *exact* version as a dependency, or else cargo will
+                        // give us the latest semver-compatible version which is not we want.
+                        // Fixes: https://github.com/obi1kenobi/cargo-semver-checks/issues/261
+                        version: Some(
+                            format!("={}", request.kind.version()?.as_ref())
+                                .parse()
+                                .context("failed to parse exact dependency version requirement")?,
+                        ),
+                        registry: registry_request.registry.map(ToString::to_string),
+                        default_features: request.default_features,
                         features: request
                             .extra_features
                             .iter()
                             .map(ToString::to_string)
                             .collect(),
-                        default_features: request.default_features,
                         ..DependencyDetail::default()
+                    },
+                    RequestKind::LocalProject(local_request) => {
+                        DependencyDetail {
+                            path: Some({
+                                let dir_path = crate::manifest::get_project_dir_from_manifest_path(
+                                    &local_request.manifest.path,
+                                )?;
+                                // The manifest will be saved in some other directory,
+                                // so for convenience, we're using absolute paths.
+                                dir_path
+                                    .canonicalize()
+                                    .context("failed to canonicalize manifest path")?
+                                    .to_str()
+                                    .context("manifest path is not valid UTF-8")?
+                                    .to_string()
+                            }),
+                            features: request
+                                .extra_features
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect(),
+                            default_features: request.default_features,
+                            ..DependencyDetail::default()
+                        }
                     }
-                }
-            };
+                };
 
-            let mut deps = DepsSet::new();
-            deps.insert(
-                request.kind.name()?.to_string(),
-                Dependency::Detailed(Box::new(project_with_features)),
-            );
+                deps.insert(
+                    request.kind.name()?.to_string(),
+                    Dependency::Detailed(Box::new(project_with_features)),
+                );
+            }
             deps
         },
         ..Default::default()
     })
 }
 
-fn save_placeholder_rustdoc_manifest(
+pub(super) fn save_placeholder_rustdoc_manifest(
     placeholder_build_dir: &Path,
     placeholder_manifest: cargo_toml::Manifest<()>,
 ) -> anyhow::Result<PathBuf> {
@@ -912,7 +1702,127 @@ fn save_placeholder_rustdoc_manifest(
 
 #[cfg(test)]
 mod tests {
-    use super::combine_witness_rustflags;
+    use std::borrow::Cow;
+    use std::time::{Duration, Instant};
+
+    use super::{
+        GenerationSettings, Interruption, RustdocBuildEnvironment, combine_witness_rustflags,
+        config_args, run_interruptibly, run_interruptibly_merged,
+    };
+    use crate::CancellationToken;
+    use crate::data_generation::CrateDataRequest;
+
+    fn settings_with(
+        timeout: Option<Duration>,
+        cancellation: Option<CancellationToken>,
+    ) -> GenerationSettings {
+        GenerationSettings {
+            pass_through_stderr: false,
+            use_color: false,
+            offline: false,
+            timeout,
+            cancellation,
+            recorder: None,
+            fingerprint_api: false,
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_interruptibly_collects_output_within_the_timeout() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let output = run_interruptibly(
+            &mut cmd,
+            &settings_with(Some(Duration::from_secs(60)), None),
+        )
+        .expect("failed to run command")
+        .expect("command was interrupted");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_interruptibly_merged_keeps_the_order_of_stdout_and_stderr() {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", "echo 1; sleep 0.1; echo 2 >&2; sleep 0.1; echo 3"]);
+        let output = run_interruptibly_merged(cmd, &settings_with(None, None))
+            .expect("failed to run command")
+            .expect("command was interrupted");
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"1\n2\n3\n");
+        assert!(output.stderr.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_interruptibly_kills_commands_that_time_out() {
+        let mut cmd = std::process::Command::new("sleep");
+        cmd.arg("60");
+        let result = run_interruptibly(
+            &mut cmd,
+            &settings_with(Some(Duration::from_millis(100)), None),
+        )
+        .expect("failed to run command");
+        assert!(matches!(
+            result,
+            Err(Interruption::TimedOut(timeout)) if timeout == Duration::from_millis(100)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_interruptibly_kills_cancelled_commands() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut cmd = std::process::Command::new("sleep");
+        cmd.arg("60");
+        let result = run_interruptibly(&mut cmd, &settings_with(None, Some(token)))
+            .expect("failed to run command");
+        assert!(matches!(result, Err(Interruption::Cancelled)));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn run_interruptibly_kills_processes_started_by_the_command() {
+        let pid_file = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-process-group-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        // Like a build script started by `cargo`, the `sleep` outlives its parent if not killed.
+        let mut cmd = std::process::Command::new("sh");
+        cmd.arg("-c").arg(format!(
+            "sleep 60 & echo $! > '{}'; wait",
+            pid_file.display()
+        ));
+        let result = run_interruptibly(
+            &mut cmd,
+            &settings_with(Some(Duration::from_millis(500)), None),
+        )
+        .expect("failed to run command");
+        assert!(matches!(result, Err(Interruption::TimedOut(_))));
+
+        let pid = fs_err::read_to_string(&pid_file).expect("failed to read pid file");
+        let stat_path = format!("/proc/{}/stat", pid.trim());
+        // Killed processes stay zombies until their new parent reaps them.
+        let is_running = || {
+            std::fs::read_to_string(&stat_path).is_ok_and(|stat| {
+                stat.rsplit_once(") ")
+                    .is_some_and(|(_, fields)| !fields.starts_with('Z'))
+            })
+        };
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while is_running() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!is_running(), "process {} is still running", pid.trim());
+        fs_err::remove_file(&pid_file).expect("failed to remove pid file");
+    }
 
     #[test]
     fn combine_witness_rustflags_preserves_existing_rustflags() {
@@ -937,4 +1847,77 @@ mod tests {
     fn combine_witness_rustflags_returns_none_when_no_flags_apply() {
         assert_eq!(combine_witness_rustflags(String::new(), ""), None);
     }
+
+    fn build_environment(
+        rustflags: &'static str,
+        rustdocflags: &'static str,
+    ) -> RustdocBuildEnvironment {
+        RustdocBuildEnvironment {
+            target_triple: "x86_64-unknown-linux-gnu".to_owned(),
+            cargo_rustflags: Cow::Borrowed(rustflags),
+            cargo_rustdocflags: Cow::Borrowed(rustdocflags),
+            toolchain_version: "1.90.0".to_owned(),
+        }
+    }
+
+    #[test]
+    fn build_flags_collect_cfgs_and_target_features_from_both_flag_sets() {
+        let flags = build_environment(
+            "--cfg tokio_unstable -C target-feature=+avx2,-sse4.1 --cap-lints=allow",
+            "--cfg=docsrs --check-cfg cfg(docsrs) -Ctarget-feature=+aes --output-format=json",
+        )
+        .build_flags();
+        assert_eq!(flags.target_triple, "x86_64-unknown-linux-gnu");
+        assert_eq!(
+            flags.cfgs.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["docsrs", "tokio_unstable"]
+        );
+        assert_eq!(
+            flags
+                .target_features
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<_>>(),
+            ["+aes", "+avx2", "-sse4.1"]
+        );
+    }
+
+    #[test]
+    fn build_flags_differences_name_each_side() {
+        let current = build_environment("--cfg tokio_unstable", "").build_flags();
+        let baseline = build_environment("--codegen target-feature=+avx2", "").build_flags();
+        assert_eq!(
+            current.differences(&baseline).as_deref(),
+            Some(concat!(
+                "  `--cfg tokio_unstable` is only set for the current crate\n",
+                "  `-C target-feature=+avx2` is only set for the baseline crate\n",
+            ))
+        );
+        assert_eq!(current.differences(&current), None);
+    }
+
+    #[test]
+    fn vendored_registries_replace_the_registry_in_cargo_config() {
+        let index_entry = tame_index::IndexVersion::fake("demo", "1.2.3");
+        let request =
+            CrateDataRequest::from_index(&index_entry, true, Default::default(), None, true)
+                .with_registry(Some("crate-file"));
+        assert!(config_args(&request).is_empty());
+
+        let request =
+            request.with_vendored_crates(Some(std::path::Path::new("/tmp/crate-0123/vendor")));
+        assert_eq!(
+            config_args(&request),
+            [
+                "--config",
+                r#"registries.crate-file.index="file:///tmp/crate-0123/vendor""#,
+                "--config",
+                r#"source.crate-file.registry="file:///tmp/crate-0123/vendor""#,
+                "--config",
+                r#"source.crate-file.replace-with="crate-file-vendored""#,
+                "--config",
+                r#"source.crate-file-vendored.directory="/tmp/crate-0123/vendor""#,
+            ]
+        );
+    }
 }
@@ -0,0 +1,255 @@
+//! Loading rustdoc JSON into the storage that lints query.
+//!
+//! This does what [`trustfall_rustdoc::load_rustdoc`] does, except that the parsed rustdoc
+//! can also be fingerprinted before it's indexed, without reading and parsing it again.
+
+use std::path::Path;
+
+use serde::Deserialize;
+use trustfall_rustdoc::{LoadingError, VersionedStorage};
+
+use super::fingerprint::{ApiFingerprint, RustdocParts};
+
+/// Load the rustdoc JSON at `json_path`, along with the package metadata of the crate
+/// it documents, if given. If `fingerprint_api` is set, also fingerprint its public API.
+///
+/// Failing to fingerprint the API isn't a loading error: without a fingerprint,
+/// the crate is just always fully checked.
+pub(crate) fn load_rustdoc(
+    json_path: &Path,
+    metadata: Option<cargo_metadata::Metadata>,
+    fingerprint_api: bool,
+) -> Result<(VersionedStorage, Option<anyhow::Result<ApiFingerprint>>), LoadingError> {
+    let package = metadata.map(package_metadata).transpose()?;
+
+    // Parsing JSON after fully reading a file into memory is much faster than
+    // parsing directly from a file, even if buffered:
+    // https://github.com/serde-rs/json/issues/160
+    let file_data = std::fs::read_to_string(json_path)
+        .map_err(|e| LoadingError::RustdocIoError(json_path.display().to_string(), e))?;
+    let format_version = detect_format_version(json_path, &file_data)?;
+
+    // The rustdoc types of each format version are only reachable through
+    // their parsed `Crate`, so its parts are gathered the same way for each version.
+    macro_rules! rustdoc_parts {
+        ($krate:ident) => {
+            RustdocParts {
+                root: $krate.root.0,
+                items: $krate
+                    .index
+                    .iter()
+                    .map(|(id, item)| (id.0, (item.crate_id, item)))
+                    .collect(),
+                paths: $krate
+                    .paths
+                    .iter()
+                    .map(|(id, summary)| {
+                        (
+                            id.0,
+                            (summary.crate_id, summary.path.as_slice(), &summary.kind),
+                        )
+                    })
+                    .collect(),
+                crate_names: $krate
+                    .external_crates
+                    .iter()
+                    .map(|(&crate_id, external)| (crate_id, external.name.as_str()))
+                    .chain(
+                        $krate
+                            .index
+                            .get(&$krate.root)
+                            .and_then(|root| root.name.as_deref())
+                            .map(|name| (0, name)),
+                    )
+                    .collect(),
+                target: &$krate.target,
+            }
+        };
+    }
+
+    macro_rules! load_format {
+        ($adapter:ident, $variant:ident) => {{
+            let krate: $adapter::Crate = serde_json::from_str(&file_data).map_err(|e| {
+                LoadingError::RustdocParsing(
+                    format_version,
+                    json_path.display().to_string(),
+                    e.into(),
+                )
+            })?;
+            drop(file_data);
+
+            let api_fingerprint = fingerprint_api
+                .then(|| ApiFingerprint::new(rustdoc_parts!(krate), package.as_ref()));
+            let storage = match package {
+                Some(package) => $adapter::PackageStorage::from_rustdoc_and_package(krate, package),
+                None => $adapter::PackageStorage::from_rustdoc(krate),
+            };
+            Ok((VersionedStorage::$variant(storage), api_fingerprint))
+        }};
+    }
+
+    match format_version {
+        57 => load_format!(trustfall_rustdoc_adapter_v57, V57),
+        60 => load_format!(trustfall_rustdoc_adapter_v60, V60),
+        61 => load_format!(trustfall_rustdoc_adapter_v61, V61),
+        _ => Err(LoadingError::UnsupportedFormat(
+            format_version,
+            json_path.display().to_string(),
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+struct RustdocFormatVersion {
+    format_version: u32,
+}
+
+/// Find the format version of rustdoc JSON, like `trustfall_rustdoc` does.
+fn detect_format_version(json_path: &Path, file_data: &str) -> Result<u32, LoadingError> {
+    // Rustdoc writes the format version last, so it can usually be read
+    // without parsing the whole file.
+    let fast_path = file_data
+        .trim_end()
+        .strip_suffix('}')
+        .and_then(|rest| rest.rsplit_once(','))
+        .and_then(|(_, last_field)| last_field.split_once(':'))
+        .filter(|(key, _)| key.trim() == "\"format_version\"")
+        .and_then(|(_, version)| version.trim().parse().ok());
+    match fast_path {
+        Some(version) => Ok(version),
+        None => serde_json::from_str::<RustdocFormatVersion>(file_data)
+            .map(|version| version.format_version)
+            .map_err(|e| {
+                LoadingError::RustdocFormatDetection(json_path.display().to_string(), e.into())
+            }),
+    }
+}
+
+/// Find the package whose rustdoc is being loaded in the metadata of its placeholder
+/// project, like `trustfall_rustdoc` does: it's the placeholder's only dependency.
+fn package_metadata(
+    metadata: cargo_metadata::Metadata,
+) -> Result<cargo_metadata::Package, LoadingError> {
+    let dependencies = &metadata
+        .root_package()
+        .ok_or_else(|| {
+            LoadingError::MetadataParsing("no root package found in 'cargo metadata' output".into())
+        })?
+        .dependencies;
+    let [dependency] = dependencies.as_slice() else {
+        return Err(LoadingError::MetadataParsing(
+            "the metadata unexpectedly contained more than one dependency; \
+             we expected our target package to be the only dependency"
+                .into(),
+        ));
+    };
+    let dependency = dependency.clone();
+
+    let mut candidates = metadata.packages.into_iter().filter(|package| {
+        package.name.as_str() == dependency.name
+            && match &dependency.path {
+                Some(path) => package.manifest_path.starts_with(path),
+                None => dependency.req.matches(&package.version),
+            }
+    });
+    let Some(package) = candidates.next() else {
+        return Err(LoadingError::MetadataParsing(format!(
+            "failed to find package metadata for package {}",
+            dependency.name
+        )));
+    };
+    if candidates.next().is_some() {
+        return Err(LoadingError::MetadataParsing(format!(
+            "ambiguous package metadata found for {}",
+            dependency.name
+        )));
+    }
+    Ok(package)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use serde_json::{Value, json};
+
+    use super::load_rustdoc;
+    use crate::data_generation::ApiFingerprint;
+
+    /// Rustdoc JSON of a crate whose root module, numbered `root`, contains
+    /// a single function numbered `function`.
+    fn rustdoc(root: u32, function: u32) -> Value {
+        let span = json!({ "filename": "src/lib.rs", "begin": [1, 0], "end": [1, 20] });
+        json!({
+            "root": root,
+            "crate_version": "1.0.0",
+            "includes_private": false,
+            "index": {
+                root.to_string(): {
+                    "id": root, "crate_id": 0, "name": "example", "span": span,
+                    "visibility": "public", "docs": null, "links": {}, "attrs": [],
+                    "deprecation": null,
+                    "inner": { "module": { "is_crate": true, "items": [function], "is_stripped": false } },
+                },
+                function.to_string(): {
+                    "id": function, "crate_id": 0, "name": "run", "span": span,
+                    "visibility": "public", "docs": "Runs.", "links": {}, "attrs": [],
+                    "deprecation": null,
+                    "inner": { "function": {
+                        "sig": { "inputs": [], "output": null, "is_c_variadic": false },
+                        "generics": { "params": [], "where_predicates": [] },
+                        "header": { "is_const": false, "is_unsafe": false, "is_async": false, "abi": "Rust" },
+                        "has_body": true,
+                    } },
+                },
+            },
+            "paths": {},
+            "external_crates": {},
+            "target": { "triple": "x86_64-unknown-linux-gnu", "target_features": [] },
+            "format_version": 57,
+        })
+    }
+
+    fn fingerprint(rustdoc: &Value) -> Option<ApiFingerprint> {
+        let mut file = tempfile::NamedTempFile::new().expect("failed to create temp file");
+        file.write_all(rustdoc.to_string().as_bytes())
+            .expect("failed to write rustdoc");
+        let (_, fingerprint) =
+            load_rustdoc(file.path(), None, true).expect("failed to load rustdoc");
+        Some(fingerprint?.expect("failed to fingerprint rustdoc"))
+    }
+
+    #[test]
+    fn fingerprint_ignores_ids_docs_spans_and_version() {
+        let original = rustdoc(0, 1);
+        let mut edited = rustdoc(7, 3);
+        edited["crate_version"] = json!("1.0.1");
+        edited["index"]["3"]["docs"] = json!("Runs, now documented differently.");
+        edited["index"]["3"]["span"]["begin"] = json!([5, 0]);
+
+        assert!(fingerprint(&original).is_some());
+        assert_eq!(fingerprint(&original), fingerprint(&edited));
+    }
+
+    #[test]
+    fn fingerprint_covers_public_api() {
+        let original = rustdoc(0, 1);
+        let mut hidden = original.clone();
+        hidden["index"]["1"]["visibility"] = json!("crate");
+        let mut made_const = original.clone();
+        made_const["index"]["1"]["inner"]["function"]["header"]["is_const"] = json!(true);
+
+        assert_ne!(fingerprint(&original), fingerprint(&hidden));
+        assert_ne!(fingerprint(&original), fingerprint(&made_const));
+    }
+
+    #[test]
+    fn fingerprint_only_when_asked() {
+        let mut file = tempfile::NamedTempFile::new().expect("failed to create temp file");
+        file.write_all(rustdoc(0, 1).to_string().as_bytes())
+            .expect("failed to write rustdoc");
+        let (_, fingerprint) =
+            load_rustdoc(file.path(), None, false).expect("failed to load rustdoc");
+        assert!(fingerprint.is_none());
+    }
+}
@@ -1,27 +1,61 @@
+mod batch;
+mod compression;
+mod diagnosis;
 mod error;
+mod fingerprint;
 mod generate;
+mod load;
 mod progress;
+mod recording;
 mod request;
 
 use trustfall_rustdoc::{VersionedIndex, VersionedRustdocAdapter, VersionedStorage};
 
 use crate::RustdocIndexingMode;
 
+pub(crate) use batch::prebuild_registry_baselines;
+pub(crate) use compression::RustdocJsonFile;
 pub(crate) use error::{IntoTerminalResult, TerminalError};
-pub(crate) use generate::GenerationSettings;
+pub(crate) use fingerprint::ApiFingerprint;
 pub(crate) use generate::effective_witness_rustflags;
+pub(crate) use generate::{BuildFlags, GenerationSettings};
+pub(crate) use load::load_rustdoc;
 pub(crate) use progress::ProgressCallbacks;
+pub(crate) use recording::{RecordedRequest, RequestRecorder};
 pub(crate) use request::{CacheSettings, CrateDataRequest};
 
+/// Loaded rustdoc data for one crate.
+#[derive(Debug)]
+pub(crate) struct LoadedRustdoc {
+    pub(crate) storage: VersionedStorage,
+    /// Fingerprint of the crate's public API, if one could be computed.
+    pub(crate) api_fingerprint: Option<ApiFingerprint>,
+    /// The flags the rustdoc was built with, unless it was loaded from a file.
+    pub(crate) build_flags: Option<BuildFlags>,
+}
+
 #[derive(Debug)]
 pub(crate) struct DataStorage {
     current: VersionedStorage,
     baseline: VersionedStorage,
+    api_unchanged: bool,
 }
 
 impl DataStorage {
-    pub(crate) fn new(current: VersionedStorage, baseline: VersionedStorage) -> Self {
-        Self { current, baseline }
+    pub(crate) fn new(current: LoadedRustdoc, baseline: LoadedRustdoc) -> Self {
+        let api_unchanged = current.api_fingerprint.is_some()
+            && current.api_fingerprint == baseline.api_fingerprint;
+        Self {
+            current: current.storage,
+            baseline: baseline.storage,
+            api_unchanged,
+        }
+    }
+
+    /// Whether the baseline and current versions have identical public APIs,
+    /// in which case no lint can find anything and they don't need to run.
+    pub(crate) fn api_unchanged(&self) -> bool {
+        self.api_unchanged
     }
 
     pub(crate) fn current_crate(&self) -> &VersionedStorage {
@@ -35,20 +69,22 @@ impl DataStorage {
 
 impl DataStorage {
     pub(crate) fn create_indexes(&self, mode: RustdocIndexingMode) -> IndexStorage<'_> {
-        let (current_crate, baseline_crate) = match mode {
-            RustdocIndexingMode::Ordinary => (
-                VersionedIndex::from_storage(&self.current),
-                VersionedIndex::from_storage(&self.baseline),
-            ),
-            RustdocIndexingMode::StabilityAware => (
-                VersionedIndex::from_rust_std_component_storage(&self.current),
-                VersionedIndex::from_rust_std_component_storage(&self.baseline),
-            ),
-        };
-
         IndexStorage {
-            current_crate,
-            baseline_crate,
+            current_crate: create_index(&self.current, mode),
+            baseline_crate: create_index(&self.baseline, mode),
+        }
+    }
+}
+
+/// Index the rustdoc of a single crate, for querying it on its own.
+pub(crate) fn create_index(
+    storage: &VersionedStorage,
+    mode: RustdocIndexingMode,
+) -> VersionedIndex<'_> {
+    match mode {
+        RustdocIndexingMode::Ordinary => VersionedIndex::from_storage(storage),
+        RustdocIndexingMode::StabilityAware => {
+            VersionedIndex::from_rust_std_component_storage(storage)
         }
     }
 }
@@ -0,0 +1,102 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+/// Collects what went into each rustdoc data request, to bundle it into a bug report.
+///
+/// Clones share the same collection.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RequestRecorder(Arc<Mutex<Vec<RecordedRequest>>>);
+
+impl RequestRecorder {
+    /// Record `request`, or where its rustdoc JSON was loaded from if it's already recorded.
+    ///
+    /// Requests are recorded before their rustdoc is generated, so that failed ones
+    /// are still in the bug report, and recorded again once it's loaded.
+    pub(crate) fn record(&self, request: RecordedRequest) {
+        let mut requests = self.0.lock().expect("request recorder lock poisoned");
+        // The same data may be requested more than once, like when regenerating stale rustdoc.
+        let same_request = |recorded: &&mut RecordedRequest| {
+            RecordedRequest {
+                rustdoc_json: None,
+                ..(*recorded).clone()
+            } == RecordedRequest {
+                rustdoc_json: None,
+                ..request.clone()
+            }
+        };
+        match requests.iter_mut().find(same_request) {
+            Some(recorded) => {
+                if request.rustdoc_json.is_some() {
+                    recorded.rustdoc_json = request.rustdoc_json;
+                }
+            }
+            None => requests.push(request),
+        }
+    }
+
+    pub(crate) fn requests(&self) -> Vec<RecordedRequest> {
+        self.0
+            .lock()
+            .expect("request recorder lock poisoned")
+            .clone()
+    }
+}
+
+/// A rustdoc data request, with what's needed to reproduce it outside `cargo-semver-checks`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub(crate) struct RecordedRequest {
+    pub(crate) crate_name: String,
+    pub(crate) version: String,
+    pub(crate) is_baseline: bool,
+    pub(crate) default_features: bool,
+    pub(crate) extra_features: Vec<String>,
+    pub(crate) build_target: Option<String>,
+    pub(crate) toolchain: Option<String>,
+    pub(crate) lockfile: Option<PathBuf>,
+    /// The `Cargo.toml` of the placeholder project that rustdoc is generated in.
+    #[serde(skip)]
+    pub(crate) placeholder_manifest: String,
+    /// Shell commands that set up the same placeholder project.
+    #[serde(skip)]
+    pub(crate) repro_commands: String,
+    /// Where the rustdoc JSON was loaded from, zstd-compressed if it came from the cache,
+    /// or `None` if it wasn't loaded.
+    #[serde(skip)]
+    pub(crate) rustdoc_json: Option<PathBuf>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{RecordedRequest, RequestRecorder};
+
+    fn request(rustdoc_json: Option<&str>) -> RecordedRequest {
+        RecordedRequest {
+            crate_name: "example".to_string(),
+            version: "1.0.0".to_string(),
+            is_baseline: true,
+            default_features: true,
+            extra_features: vec![],
+            build_target: None,
+            toolchain: None,
+            lockfile: None,
+            placeholder_manifest: String::new(),
+            repro_commands: String::new(),
+            rustdoc_json: rustdoc_json.map(PathBuf::from),
+        }
+    }
+
+    #[test]
+    fn recording_a_loaded_request_fills_in_its_rustdoc() {
+        let recorder = RequestRecorder::default();
+        recorder.record(request(None));
+        assert_eq!(recorder.requests(), [request(None)]);
+
+        recorder.record(request(Some("rustdoc.json")));
+        recorder.record(request(None));
+        assert_eq!(recorder.requests(), [request(Some("rustdoc.json"))]);
+    }
+}
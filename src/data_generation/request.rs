@@ -6,24 +6,40 @@ use std::{
 };
 
 use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use sha2::Digest as _;
 use trustfall_rustdoc::{LoadingError, VersionedStorage};
 
 use crate::manifest::Manifest;
 use crate::util::{atomic_write, slugify};
 
+use super::LoadedRustdoc;
+use super::compression::{RustdocJsonFile, compress_into};
 use super::error::{IntoTerminalResult, TerminalError};
-use super::generate::{GenerationSettings, RustdocBuildEnvironment};
+use super::fingerprint::ApiFingerprint;
+use super::generate::{BuildFlags, GenerationSettings, RustdocBuildEnvironment};
+use super::load;
 use super::progress::{CallbackHandler, ProgressCallbacks};
 
 #[derive(Debug, Clone)]
 pub(crate) struct RegistryRequest<'a> {
     index_entry: &'a tame_index::IndexVersion,
+
+    /// The `[registries]` name of the registry, or `None` for crates.io.
+    pub(super) registry: Option<&'a str>,
+
+    /// A directory of vendored crates that cargo is configured to use in place of
+    /// the registry, like the unpacked `.crate` file of a crate that may be unpublished.
+    pub(super) vendored_crates: Option<&'a Path>,
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct ProjectRequest<'a> {
     pub(super) manifest: &'a Manifest,
+
+    /// An identifier of the exact contents of the project's sources, such as a git tree id,
+    /// if known. Only then can we cache the project's rustdoc.
+    pub(super) source_tree: Option<&'a str>,
 }
 
 #[derive(Debug, Clone)]
@@ -35,8 +51,8 @@ pub(crate) enum RequestKind<'a> {
 impl RequestKind<'_> {
     pub(super) fn name(&self) -> anyhow::Result<&str> {
         Ok(match self {
-            Self::Registry(RegistryRequest { index_entry }) => &index_entry.name,
-            Self::LocalProject(ProjectRequest { manifest }) => {
+            Self::Registry(RegistryRequest { index_entry, .. }) => &index_entry.name,
+            Self::LocalProject(ProjectRequest { manifest, .. }) => {
                 crate::manifest::get_package_name(manifest)?
             }
         })
@@ -44,10 +60,10 @@ impl RequestKind<'_> {
 
     pub(super) fn version(&self) -> anyhow::Result<Cow<'_, str>> {
         Ok(match self {
-            Self::Registry(RegistryRequest { index_entry }) => {
+            Self::Registry(RegistryRequest { index_entry, .. }) => {
                 Cow::Borrowed(index_entry.version.as_str())
             }
-            Self::LocalProject(ProjectRequest { manifest }) => {
+            Self::LocalProject(ProjectRequest { manifest, .. }) => {
                 Cow::Owned(crate::manifest::get_package_version(manifest)?)
             }
         })
@@ -88,6 +104,11 @@ pub(crate) struct CacheUse<'a> {
     /// and always `Some` otherwise.
     json_cache_location: Option<PathBuf>,
 
+    /// Where older versions cached the same rustdoc JSON uncompressed.
+    /// Invariant: always `None` if the cache settings are [`CacheSettings::None`],
+    /// and always `Some` otherwise.
+    legacy_json_cache_location: Option<PathBuf>,
+
     /// Invariant: always `None` if the cache settings are [`CacheSettings::None`],
     /// and always `Some` otherwise.
     metadata_cache_location: Option<PathBuf>,
@@ -95,36 +116,51 @@ pub(crate) struct CacheUse<'a> {
     settings: CacheSettings<&'a Path>,
 }
 
+/// The contents of a cache entry's metadata file: the crate's `cargo metadata`,
+/// plus the flags its rustdoc was built with.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedMetadata<M> {
+    #[serde(flatten)]
+    metadata: M,
+
+    /// Missing in entries written before the flags were recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    build_flags: Option<BuildFlags>,
+}
+
 #[derive(Debug, Clone)]
-struct CacheEntry<'a> {
+pub(super) struct CacheEntry<'a> {
     json: &'a Path,
     metadata: &'a Path,
 }
 
 impl<'a> CacheUse<'a> {
-    fn new(
+    pub(super) fn new(
         request: &CrateDataRequest<'a>,
         build_environment: &RustdocBuildEnvironment,
         settings: CacheSettings<&'a Path>,
     ) -> anyhow::Result<Self> {
-        // We can only cache registry crates. For local crates, we have no idea of the state
-        // of the local filesystem: it can point to an arbitrary git commit, have dirty repo state,
-        // or might not be part of a git repository at all. We cannot guarantee consistency,
-        // so we don't offer caching.
-        let settings = if matches!(request.kind, RequestKind::LocalProject(..)) {
-            CacheSettings::None
-        } else {
-            settings
+        // Registry crates are immutable, so we can always cache them. For local crates,
+        // we generally have no idea of the state of the local filesystem: it can point to
+        // an arbitrary git commit, have dirty repo state, or might not be part of a git repository
+        // at all. We can only cache them when their sources are known to exactly match
+        // a git tree or packaged crate, whose id then becomes part of the cache key.
+        let settings = match &request.kind {
+            RequestKind::LocalProject(ProjectRequest {
+                source_tree: None, ..
+            }) => CacheSettings::None,
+            RequestKind::LocalProject(..) | RequestKind::Registry(..) => settings,
         };
 
         let key = request.artifact_slug(build_environment)?;
 
-        let (json_cache_location, metadata_cache_location) = {
+        let (json_cache_location, legacy_json_cache_location, metadata_cache_location) = {
             match settings {
-                CacheSettings::None => (None, None),
+                CacheSettings::None => (None, None, None),
                 CacheSettings::ReadOnly(path)
                 | CacheSettings::ReadWrite(path)
                 | CacheSettings::WriteOnly(path) => (
+                    Some(path.join(format!("{key}.json.zst"))),
                     Some(path.join(format!("{key}.json"))),
                     Some(path.join(format!("{key}.metadata.json"))),
                 ),
@@ -133,12 +169,13 @@ impl<'a> CacheUse<'a> {
 
         Ok(Self {
             json_cache_location,
+            legacy_json_cache_location,
             metadata_cache_location,
             settings,
         })
     }
 
-    fn read(&self) -> anyhow::Result<Option<CacheEntry<'_>>> {
+    pub(super) fn read(&self) -> anyhow::Result<Option<CacheEntry<'_>>> {
         match self.settings {
             CacheSettings::ReadWrite(..) | CacheSettings::ReadOnly(..) => {
                 let json_path = self
@@ -150,12 +187,35 @@ impl<'a> CacheUse<'a> {
                     .as_ref()
                     .expect("invariant violation: no metadata path for readable cache");
 
-                if json_path.exists() && metadata_path.exists() {
+                let legacy_json_path = self
+                    .legacy_json_cache_location
+                    .as_ref()
+                    .expect("invariant violation: no legacy cache path for readable cache");
+
+                if !metadata_path.exists() {
+                    return Ok(None);
+                }
+                if json_path.exists() {
                     return Ok(Some(CacheEntry {
                         json: json_path,
                         metadata: metadata_path,
                     }));
                 }
+                if legacy_json_path.exists() {
+                    // Compress entries cached by older versions in place,
+                    // if we may write to the cache, so they don't linger uncompressed.
+                    let json = if matches!(self.settings, CacheSettings::ReadWrite(..)) {
+                        atomic_write(json_path, |writer| compress_into(legacy_json_path, writer))?;
+                        fs_err::remove_file(legacy_json_path)?;
+                        json_path
+                    } else {
+                        legacy_json_path
+                    };
+                    return Ok(Some(CacheEntry {
+                        json,
+                        metadata: metadata_path,
+                    }));
+                }
             }
             CacheSettings::WriteOnly(..) | CacheSettings::None => {}
         }
@@ -167,10 +227,11 @@ impl<'a> CacheUse<'a> {
     ///
     /// If the cache policy does not allow writing, this returns `Ok(..)`.
     /// Errors are genuine failures to write to the cache, such as I/O errors.
-    fn populate(
+    pub(super) fn populate(
         &self,
         rustdoc_json: &Path,
         metadata: &cargo_metadata::Metadata,
+        build_flags: &BuildFlags,
     ) -> anyhow::Result<bool> {
         match self.settings {
             CacheSettings::ReadWrite(path) | CacheSettings::WriteOnly(path) => {
@@ -184,24 +245,25 @@ impl<'a> CacheUse<'a> {
                     .expect("invariant violation: no metadata path for readable cache");
 
                 fs_err::create_dir_all(path)?;
-                // Do not use `fs_err::copy` or `std::fs::copy` here. Those APIs open the
-                // destination path themselves and stream bytes into it directly, so another process
-                // can observe a partially copied cache file if it reads while the copy is in
-                // progress or if the copy fails partway through. Routing the copy through
+                // Do not stream into the destination path directly: another process
+                // can observe a partially written cache file if it reads while compression is in
+                // progress or if it fails partway through. Routing the compressed copy through
                 // `atomic_write` publishes only a completed temp-file copy.
                 atomic_write(json_path, |writer| {
-                    let mut rustdoc_json_file = fs_err::File::open(rustdoc_json)?;
-                    std::io::copy(&mut rustdoc_json_file, writer).with_context(|| {
+                    compress_into(rustdoc_json, writer).with_context(|| {
                         format!(
-                            "failed to copy rustdoc JSON cache file from {} to {}",
+                            "failed to write rustdoc JSON cache file from {} to {}",
                             rustdoc_json.display(),
                             json_path.display()
                         )
-                    })?;
-                    Ok(())
+                    })
                 })?;
                 atomic_write(metadata_path, |writer| {
-                    serde_json::to_writer(writer, metadata)?;
+                    let cached = CachedMetadata {
+                        metadata,
+                        build_flags: Some(build_flags.clone()),
+                    };
+                    serde_json::to_writer(writer, &cached)?;
                     Ok(())
                 })?;
                 Ok(true)
@@ -222,6 +284,13 @@ pub(crate) struct CrateDataRequest<'a> {
     pub(super) extra_features: BTreeSet<Cow<'a, str>>,
     pub(super) build_target: Option<&'a str>,
 
+    /// The rustup toolchain to run `cargo` and `rustdoc` with. Unset means the default one.
+    pub(super) toolchain: Option<&'a str>,
+
+    /// The `Cargo.lock` to seed the placeholder project's dependency resolution with.
+    /// Unset means dependencies are resolved fresh.
+    pub(super) lockfile: Option<&'a Path>,
+
     /// Purely for progress reporting purposes. Does not change behavior.
     pub(super) is_baseline: bool,
 }
@@ -235,10 +304,16 @@ impl<'a> CrateDataRequest<'a> {
         is_baseline: bool,
     ) -> Self {
         Self {
-            kind: RequestKind::Registry(RegistryRequest { index_entry }),
+            kind: RequestKind::Registry(RegistryRequest {
+                index_entry,
+                registry: None,
+                vendored_crates: None,
+            }),
             default_features,
             extra_features,
             build_target,
+            toolchain: None,
+            lockfile: None,
             is_baseline,
         }
     }
@@ -251,14 +326,58 @@ impl<'a> CrateDataRequest<'a> {
         is_baseline: bool,
     ) -> Self {
         Self {
-            kind: RequestKind::LocalProject(ProjectRequest { manifest }),
+            kind: RequestKind::LocalProject(ProjectRequest {
+                manifest,
+                source_tree: None,
+            }),
             default_features,
             extra_features,
             build_target,
+            toolchain: None,
+            lockfile: None,
             is_baseline,
         }
     }
 
+    /// Record the `[registries]` name of the registry that a registry request's crate
+    /// comes from. Has no effect on local project requests.
+    pub(crate) fn with_registry(mut self, registry: Option<&'a str>) -> Self {
+        if let RequestKind::Registry(request) = &mut self.kind {
+            request.registry = registry;
+        }
+        self
+    }
+
+    /// Use the given directory of vendored crates in place of the registry
+    /// of a registry request. Has no effect on local project requests.
+    pub(crate) fn with_vendored_crates(mut self, vendored_crates: Option<&'a Path>) -> Self {
+        if let RequestKind::Registry(request) = &mut self.kind {
+            request.vendored_crates = vendored_crates;
+        }
+        self
+    }
+
+    /// Record an identifier of the exact contents of a local project's sources,
+    /// which allows caching its rustdoc. Has no effect on registry requests.
+    pub(crate) fn with_source_tree(mut self, source_tree: Option<&'a str>) -> Self {
+        if let RequestKind::LocalProject(project) = &mut self.kind {
+            project.source_tree = source_tree;
+        }
+        self
+    }
+
+    /// Generate the rustdoc with the given rustup toolchain instead of the default one.
+    pub(crate) fn with_toolchain(mut self, toolchain: Option<&'a str>) -> Self {
+        self.toolchain = toolchain;
+        self
+    }
+
+    /// Seed dependency resolution with the given lockfile instead of resolving fresh.
+    pub(crate) fn with_lockfile(mut self, lockfile: Option<&'a Path>) -> Self {
+        self.lockfile = lockfile;
+        self
+    }
+
     pub(crate) fn package_name(&self) -> anyhow::Result<&str> {
         self.kind.name()
     }
@@ -270,7 +389,7 @@ impl<'a> CrateDataRequest<'a> {
     pub(crate) fn local_project_dir(&self) -> anyhow::Result<Option<PathBuf>> {
         match &self.kind {
             RequestKind::Registry(..) => Ok(None),
-            RequestKind::LocalProject(ProjectRequest { manifest }) => Ok(Some(
+            RequestKind::LocalProject(ProjectRequest { manifest, .. }) => Ok(Some(
                 crate::manifest::get_project_dir_from_manifest_path(&manifest.path)?,
             )),
         }
@@ -288,6 +407,10 @@ impl<'a> CrateDataRequest<'a> {
         self.build_target
     }
 
+    pub(crate) fn toolchain(&self) -> Option<&str> {
+        self.toolchain
+    }
+
     /// Best-effort Rust import name for this crate as it would appear in
     /// downstream code. This is intentionally not always the same as the Cargo
     /// package name: local projects may customize their library target name,
@@ -295,7 +418,7 @@ impl<'a> CrateDataRequest<'a> {
     pub(crate) fn fallback_import_name(&self) -> anyhow::Result<String> {
         match &self.kind {
             RequestKind::Registry(..) => Ok(self.package_name()?.replace('-', "_")),
-            RequestKind::LocalProject(ProjectRequest { manifest }) => {
+            RequestKind::LocalProject(ProjectRequest { manifest, .. }) => {
                 crate::manifest::get_library_target_name(manifest)
             }
         }
@@ -314,29 +437,41 @@ impl<'a> CrateDataRequest<'a> {
         cache_settings: CacheSettings<&'a Path>,
         generation_settings: GenerationSettings,
         callbacks: &'slf mut dyn ProgressCallbacks<'slf>,
-    ) -> Result<VersionedStorage, TerminalError> {
+    ) -> Result<LoadedRustdoc, TerminalError> {
+        let crate_name = self
+            .kind
+            .name()
+            .context("failed to get crate name")
+            .into_terminal_result()?;
         let version = self
             .kind
             .version()
             .context("failed to get crate version")
             .into_terminal_result()?;
-        let mut callbacks = CallbackHandler::new(
-            self.kind
-                .name()
-                .context("failed to get crate name")
-                .into_terminal_result()?,
-            version,
-            self.is_baseline,
-            callbacks,
-        );
+        let mut callbacks = CallbackHandler::new(crate_name, version, self.is_baseline, callbacks);
+        let recorder = generation_settings.recorder.clone();
+        let fingerprint_api = generation_settings.fingerprint_api;
+        let record = |rustdoc_json: Option<&Path>, callbacks: &mut CallbackHandler<'_>| {
+            if let Some(recorder) = &recorder {
+                match super::generate::record_request(self, rustdoc_json.map(Path::to_path_buf)) {
+                    Ok(request) => recorder.record(request),
+                    Err(e) => callbacks
+                        .non_fatal_error(e.context("failed to record request for bug report")),
+                }
+            }
+        };
+        // Record the request up front, so it's in the bug report even if generating fails.
+        record(None, &mut callbacks);
 
         // We treat failures to even set up a cache to use as fatal,
         // since they almost always indicate a serious bug in our mental model.
         // An example of a failure here would be "crates don't always have a name, actually"
         // which is something we want to know about ASAP.
-        let build_environment =
-            RustdocBuildEnvironment::from_env_and_config_for_target(self.build_target())
-                .into_terminal_result()?;
+        let build_environment = RustdocBuildEnvironment::from_env_and_config_for_target(
+            self.build_target(),
+            self.toolchain(),
+        )
+        .into_terminal_result()?;
         let cache =
             CacheUse::new(self, &build_environment, cache_settings).into_terminal_result()?;
 
@@ -349,15 +484,35 @@ impl<'a> CrateDataRequest<'a> {
 
                 match std::fs::read_to_string(entry.metadata) {
                     Ok(text) => match serde_json::from_str(&text) {
-                        Ok(metadata) => {
-                            match load_rustdoc_with_optional_metadata(
-                                entry.json,
-                                metadata,
-                                &mut callbacks,
-                            ) {
-                                Ok(data) => {
+                        Ok(CachedMetadata {
+                            metadata,
+                            build_flags,
+                        }) => {
+                            // Cached rustdoc is compressed, so it's loaded from a
+                            // decompressed copy that's removed once loading is done.
+                            let loaded =
+                                RustdocJsonFile::open(entry.json, target_root).and_then(|json| {
+                                    load_rustdoc_with_optional_metadata(
+                                        json.path(),
+                                        metadata,
+                                        fingerprint_api,
+                                        &mut callbacks,
+                                    )
+                                });
+                            match loaded {
+                                Ok((storage, api_fingerprint)) => {
                                     callbacks.parse_rustdoc_success(true);
-                                    return Ok(data);
+                                    record(Some(entry.json), &mut callbacks);
+                                    return Ok(LoadedRustdoc {
+                                        storage,
+                                        api_fingerprint,
+                                        // The flags are part of the cache key, so older entries
+                                        // that didn't record them were built with the same ones.
+                                        build_flags: Some(
+                                            build_flags
+                                                .unwrap_or_else(|| build_environment.build_flags()),
+                                        ),
+                                    });
                                 }
                                 Err(e) => {
                                     callbacks.non_fatal_error(
@@ -406,7 +561,8 @@ impl<'a> CrateDataRequest<'a> {
         // If the cache doesn't need to be populated, this returns `Ok(false)`.
         // Errors are genuine failures to populate the cache, such as I/O problems.
         let mut clean_up_build_dir = false;
-        match cache.populate(data_path.as_path(), &metadata) {
+        let build_flags = build_environment.build_flags();
+        match cache.populate(data_path.as_path(), &metadata, &build_flags) {
             // Populated the cache.
             Ok(true) => {
                 callbacks.rustdoc_cache_populated();
@@ -426,9 +582,19 @@ impl<'a> CrateDataRequest<'a> {
 
         // This time, failure to read the rustdoc is fatal.
         callbacks.parse_rustdoc_start(false);
-        let data = load_rustdoc_with_optional_metadata(&data_path, metadata, &mut callbacks)
-            .into_terminal_result()?;
+        let (storage, api_fingerprint) = load_rustdoc_with_optional_metadata(
+            &data_path,
+            metadata,
+            fingerprint_api,
+            &mut callbacks,
+        )
+        .into_terminal_result()?;
         callbacks.parse_rustdoc_success(false);
+        // Once cached, the build dir with the uncompressed rustdoc JSON is removed.
+        match &cache.json_cache_location {
+            Some(cached_json) if clean_up_build_dir => record(Some(cached_json), &mut callbacks),
+            _ => record(Some(&data_path), &mut callbacks),
+        }
 
         if clean_up_build_dir {
             let outcome = std::fs::remove_dir_all(&build_dir);
@@ -440,11 +606,15 @@ impl<'a> CrateDataRequest<'a> {
             }
         }
 
-        Ok(data)
+        Ok(LoadedRustdoc {
+            storage,
+            api_fingerprint,
+            build_flags: Some(build_flags),
+        })
     }
 
     /// A path-safe unique identifier for the placeholder build directory.
-    fn build_path_slug(
+    pub(super) fn build_path_slug(
         &self,
         build_environment: &RustdocBuildEnvironment,
     ) -> anyhow::Result<String> {
@@ -459,7 +629,10 @@ impl<'a> CrateDataRequest<'a> {
     }
 
     /// A path-safe unique identifier for the generated rustdoc artifact.
-    fn artifact_slug(&self, build_environment: &RustdocBuildEnvironment) -> anyhow::Result<String> {
+    pub(super) fn artifact_slug(
+        &self,
+        build_environment: &RustdocBuildEnvironment,
+    ) -> anyhow::Result<String> {
         Ok(format!(
             "{}-{}-{}-{}",
             slugify(self.kind.name()?),
@@ -488,6 +661,30 @@ impl<'a> CrateDataRequest<'a> {
                 RequestKind::LocalProject { .. } => "local",
             },
         );
+        match &self.kind {
+            RequestKind::Registry(RegistryRequest {
+                registry,
+                vendored_crates,
+                ..
+            }) => {
+                if let Some(registry) = registry {
+                    update_artifact_hash(&mut hasher, "registry", registry);
+                }
+                if let Some(vendored_crates) = vendored_crates {
+                    // Named after the hash of the crate file they were unpacked from.
+                    update_artifact_hash(
+                        &mut hasher,
+                        "vendored_crates",
+                        &vendored_crates.to_string_lossy(),
+                    );
+                }
+            }
+            RequestKind::LocalProject(ProjectRequest {
+                source_tree: Some(source_tree),
+                ..
+            }) => update_artifact_hash(&mut hasher, "source_tree", source_tree),
+            RequestKind::LocalProject(..) => {}
+        }
         update_artifact_hash(&mut hasher, "name", self.kind.name()?);
         update_artifact_hash(&mut hasher, "version", self.kind.version()?.as_ref());
         update_artifact_hash(
@@ -518,6 +715,11 @@ impl<'a> CrateDataRequest<'a> {
             "toolchain_version",
             &build_environment.toolchain_version,
         );
+        if let Some(lockfile) = self.lockfile {
+            // Locked dependency versions can change the API, such as through re-exports.
+            let contents = fs_err::read_to_string(lockfile).context("failed to read lockfile")?;
+            update_artifact_hash(&mut hasher, "lockfile", &contents);
+        }
 
         // First 16 hex characters are good enough for our use case.
         // For birthday paradox to occur, a single crate version must be run
@@ -531,23 +733,39 @@ impl<'a> CrateDataRequest<'a> {
     }
 }
 
+/// Load the rustdoc, and fingerprint its public API if `fingerprint_api` is set.
+/// Without a fingerprint, the crate is always fully checked.
 fn load_rustdoc_with_optional_metadata(
     json_path: &Path,
     metadata: cargo_metadata::Metadata,
+    fingerprint_api: bool,
     callbacks: &mut CallbackHandler<'_>,
-) -> anyhow::Result<VersionedStorage> {
-    match trustfall_rustdoc::load_rustdoc(json_path, Some(metadata)) {
-        Ok(data) => Ok(data),
+) -> anyhow::Result<(VersionedStorage, Option<ApiFingerprint>)> {
+    let (storage, api_fingerprint) = match load::load_rustdoc(
+        json_path,
+        Some(metadata),
+        fingerprint_api,
+    ) {
+        Ok(data) => data,
         Err(e @ LoadingError::MetadataParsing(..)) => {
             // Metadata parsing is brand new and might have unforeseen issues.
             // Be resilient: report the problem but don't crash --
             // instead, drop back to not checking manifest data.
             callbacks.non_fatal_error(anyhow::Error::from(e).context("skipping package metadata due to failure to load it; package manifest checks will not discover any breakage"));
 
-            trustfall_rustdoc::load_rustdoc(json_path, None).map_err(anyhow::Error::from)
+            load::load_rustdoc(json_path, None, fingerprint_api)?
         }
-        Err(e) => Err(anyhow::Error::from(e)),
-    }
+        Err(e) => return Err(anyhow::Error::from(e)),
+    };
+    let api_fingerprint = match api_fingerprint {
+        Some(Ok(fingerprint)) => Some(fingerprint),
+        Some(Err(e)) => {
+            callbacks.non_fatal_error(e.context("failed to fingerprint the crate's public API"));
+            None
+        }
+        None => None,
+    };
+    Ok((storage, api_fingerprint))
 }
 
 fn update_artifact_hash(hasher: &mut sha2::Sha256, label: &str, value: &str) {
@@ -588,11 +806,11 @@ mod tests {
     #[test]
     fn artifact_slug_uses_canonical_target_triple() {
         let implicit_build_environment =
-            RustdocBuildEnvironment::from_env_and_config_for_target(None)
+            RustdocBuildEnvironment::from_env_and_config_for_target(None, None)
                 .expect("implicit build environment failed");
         let target_triple = implicit_build_environment.target_triple.clone();
         let explicit_build_environment =
-            RustdocBuildEnvironment::from_env_and_config_for_target(Some(&target_triple))
+            RustdocBuildEnvironment::from_env_and_config_for_target(Some(&target_triple), None)
                 .expect("explicit build environment failed");
 
         let implicit_manifest = test_manifest();
@@ -627,7 +845,7 @@ mod tests {
         let manifest = test_manifest();
         let request =
             CrateDataRequest::from_local_project(&manifest, true, BTreeSet::new(), None, false);
-        let host_environment = RustdocBuildEnvironment::from_env_and_config_for_target(None)
+        let host_environment = RustdocBuildEnvironment::from_env_and_config_for_target(None, None)
             .expect("build environment failed");
         let without_cfg = build_environment(
             &host_environment.target_triple,
@@ -647,4 +865,102 @@ mod tests {
                 .expect("artifact slug with cfg failed"),
         );
     }
+
+    #[test]
+    fn local_projects_are_cached_only_with_known_source_tree() {
+        let manifest = test_manifest();
+        let build_environment = RustdocBuildEnvironment::from_env_and_config_for_target(None, None)
+            .expect("build environment failed");
+        let cache_dir = Path::new("cache");
+        let untracked =
+            CrateDataRequest::from_local_project(&manifest, true, BTreeSet::new(), None, false);
+        let tracked = untracked
+            .clone()
+            .with_source_tree(Some("4b825dc642cb6eb9a060e54bf8d69288fbee4904"));
+
+        let untracked_cache = CacheUse::new(
+            &untracked,
+            &build_environment,
+            CacheSettings::ReadWrite(cache_dir),
+        )
+        .expect("failed to set up cache");
+        assert!(matches!(untracked_cache.settings, CacheSettings::None));
+        assert!(untracked_cache.json_cache_location.is_none());
+
+        let tracked_cache = CacheUse::new(
+            &tracked,
+            &build_environment,
+            CacheSettings::ReadWrite(cache_dir),
+        )
+        .expect("failed to set up cache");
+        assert!(matches!(
+            tracked_cache.settings,
+            CacheSettings::ReadWrite(_)
+        ));
+        assert!(tracked_cache.json_cache_location.is_some());
+
+        assert_ne!(
+            untracked
+                .artifact_slug(&build_environment)
+                .expect("untracked artifact slug failed"),
+            tracked
+                .artifact_slug(&build_environment)
+                .expect("tracked artifact slug failed"),
+        );
+    }
+
+    #[test]
+    fn uncompressed_cache_entries_are_migrated() {
+        let manifest = test_manifest();
+        let build_environment = RustdocBuildEnvironment::from_env_and_config_for_target(None, None)
+            .expect("build environment failed");
+        let request =
+            CrateDataRequest::from_local_project(&manifest, true, BTreeSet::new(), None, false)
+                .with_source_tree(Some("4b825dc642cb6eb9a060e54bf8d69288fbee4904"));
+        let cache_dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-legacy-cache-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        fs_err::create_dir_all(&cache_dir).expect("failed to create cache dir");
+        let key = request
+            .artifact_slug(&build_environment)
+            .expect("artifact slug failed");
+        let legacy_json = cache_dir.join(format!("{key}.json"));
+        fs_err::write(&legacy_json, "{}").expect("failed to write");
+        fs_err::write(cache_dir.join(format!("{key}.metadata.json")), "{}")
+            .expect("failed to write");
+
+        // Read-only caches are left as they are.
+        let read_only = CacheUse::new(
+            &request,
+            &build_environment,
+            CacheSettings::ReadOnly(&cache_dir),
+        )
+        .expect("failed to set up cache");
+        let entry = read_only.read().expect("failed to read cache");
+        assert_eq!(entry.map(|entry| entry.json), Some(legacy_json.as_path()));
+
+        let read_write = CacheUse::new(
+            &request,
+            &build_environment,
+            CacheSettings::ReadWrite(&cache_dir),
+        )
+        .expect("failed to set up cache");
+        let entry = read_write
+            .read()
+            .expect("failed to read cache")
+            .expect("legacy entry wasn't found");
+        assert_eq!(entry.json, cache_dir.join(format!("{key}.json.zst")));
+        assert!(!legacy_json.exists());
+        let json = RustdocJsonFile::open(entry.json, &cache_dir).expect("failed to open");
+        assert!(matches!(json, RustdocJsonFile::Decompressed(_)));
+        assert_eq!(
+            fs_err::read_to_string(json.path()).expect("failed to read"),
+            "{}"
+        );
+        drop(json);
+
+        fs_err::remove_dir_all(&cache_dir).expect("failed to clean up");
+    }
 }
@@ -0,0 +1,348 @@
+//! Detection of public items that the current crate only provides with non-default features.
+//!
+//! Lints compare the baseline and current crates under the same feature configuration,
+//! so an item that moved behind a non-default feature goes unnoticed whenever that feature
+//! is enabled for the check, as it is by default. To catch such items, both crates are also
+//! documented with only their default features and compared by [`MISSING_ITEMS_QUERY`].
+//! Items it finds missing from the current crate there, while still present under
+//! the checked features, have become feature-gated. Each of them is then attributed
+//! to the first non-default feature that makes it available again.
+//!
+//! This isn't a lint in `src/lints`: it only makes sense on crates built with
+//! their default features, so it can't run along with the lints. Its findings are
+//! reported like a lint's, under [`CHECK_ID`], which lint levels can be configured for.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use trustfall::FieldValue;
+use trustfall_rustdoc::{VersionedIndex, VersionedRustdocAdapter, VersionedStorage};
+
+use crate::check_release::LintResult;
+use crate::data_generation::{self, GenerationSettings};
+use crate::rustdoc_gen::{
+    CrateDataForRustdoc, FeatureConfig, FeaturesGroup, RustdocGenerator, StatefulRustdocGenerator,
+};
+use crate::timings::{CrateTimings, TimingPhase};
+use crate::{
+    ActualSemverUpdate, GlobalConfig, LintLevel, OverrideStack, RequiredSemverUpdate,
+    RustdocIndexingMode, SemverQuery,
+};
+
+/// The id this check's findings are reported and configured under.
+pub(crate) const CHECK_ID: &str = "public_item_now_feature_gated";
+
+/// The most non-default features to generate the current crate's rustdoc with,
+/// one at a time, to find the feature that gates each item. Items that none of them
+/// provide are reported without naming a feature.
+const MAX_ATTRIBUTION_BUILDS: usize = 8;
+
+/// The baseline's public items that the current crate doesn't provide at the same path.
+const MISSING_ITEMS_QUERY: &str = r#"
+{
+    CrateDiff {
+        baseline {
+            item {
+                ... on Importable {
+                    visibility_limit @filter(op: "=", value: ["$public"])
+                    name @output
+                    kind: __typename @output
+
+                    importable_path {
+                        path @output @tag
+                        public_api @filter(op: "=", value: ["$true"])
+                    }
+
+                    span_: span @optional {
+                        filename @output
+                        begin_line @output
+                        end_line @output
+                    }
+                }
+            }
+        }
+        current @fold @transform(op: "count") @filter(op: "=", value: ["$zero"]) {
+            item {
+                ... on Importable {
+                    visibility_limit @filter(op: "=", value: ["$public"])
+
+                    importable_path {
+                        path @filter(op: "=", value: ["%path"])
+                        public_api @filter(op: "=", value: ["$true"])
+                    }
+                }
+            }
+        }
+    }
+}"#;
+
+type QueryResult = BTreeMap<Arc<str>, FieldValue>;
+
+/// How this check's findings are described in reports.
+fn feature_gated_item_check() -> &'static SemverQuery {
+    static CHECK: OnceLock<SemverQuery> = OnceLock::new();
+    CHECK.get_or_init(|| SemverQuery {
+        id: CHECK_ID.to_string(),
+        human_readable_name: "pub item now requires a non-default feature".to_string(),
+        description: "A public item is no longer available with the crate's default features."
+            .to_string(),
+        required_update: RequiredSemverUpdate::Major,
+        lint_level: LintLevel::Deny,
+        reference: None,
+        reference_link: Some(
+            "https://doc.rust-lang.org/cargo/reference/semver.html#cargo-feature-remove-another"
+                .to_string(),
+        ),
+        query: MISSING_ITEMS_QUERY.to_string(),
+        arguments: [
+            ("public", FieldValue::from("public")),
+            ("true", true.into()),
+            ("zero", 0.into()),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.into()))
+        .collect(),
+        error_message: "A public item is now only available when a non-default feature \
+                        is enabled, or under a narrower cfg. Downstream crates using the item \
+                        with default features will no longer compile."
+            .to_string(),
+        per_result_error_template: Some(
+            "{{lowercase kind}} {{join \"::\" path}} now requires \
+             {{#if feature}}feature `{{feature}}`{{else}}non-default features or cfgs{{/if}}, \
+             previously in file {{span_filename}}:{{span_begin_line}}"
+                .to_string(),
+        ),
+        witness: None,
+    })
+}
+
+/// Returns the baseline's public items that `current` is missing,
+/// by import path, each with the query result describing it.
+fn missing_items(
+    baseline: &VersionedIndex<'_>,
+    current: &VersionedStorage,
+    mode: RustdocIndexingMode,
+) -> anyhow::Result<BTreeMap<Vec<String>, QueryResult>> {
+    let check = feature_gated_item_check();
+    let current = data_generation::create_index(current, mode);
+    let adapter = VersionedRustdocAdapter::new(&current, Some(baseline))?;
+
+    let mut items = BTreeMap::new();
+    for result in adapter.run_query(&check.query, check.arguments.clone())? {
+        let path = result["path"]
+            .as_vec_with(|segment| segment.as_str().map(str::to_owned))
+            .expect("path was not a list of strings");
+        items.insert(path, result);
+    }
+    Ok(items)
+}
+
+/// Whether a feature configuration already enables only the default features,
+/// in which case gated items are reported as missing by the lints themselves.
+pub(crate) fn is_default_features_only(feature_config: &FeatureConfig) -> bool {
+    feature_config.features_group == FeaturesGroup::Default
+        && feature_config.extra_features.is_empty()
+}
+
+/// Checks one crate for public items that became feature-gated.
+pub(crate) struct FeatureGatingCheck<'a> {
+    pub(crate) current_generator: &'a RustdocGenerator,
+    pub(crate) baseline_generator: &'a RustdocGenerator,
+    pub(crate) current_crate_data: &'a CrateDataForRustdoc<'a>,
+    pub(crate) baseline_crate_data: &'a CrateDataForRustdoc<'a>,
+    /// Non-default features of the current crate, tried in order to name the feature
+    /// that gates each item, up to [`MAX_ATTRIBUTION_BUILDS`] of them.
+    /// If empty, no feature is named.
+    pub(crate) candidate_features: &'a [String],
+    pub(crate) overrides: &'a OverrideStack,
+    /// Bump between the baseline and current versions.
+    pub(crate) detected_bump: ActualSemverUpdate,
+    pub(crate) rustdoc_indexing_mode: RustdocIndexingMode,
+}
+
+impl FeatureGatingCheck<'_> {
+    /// Find the baseline's public items that the current crate is missing with default features,
+    /// but provides in `checked_current`: the current crate built with the checked features.
+    ///
+    /// Each such item is a query result, with the gating feature as `feature`
+    /// if one of the tried candidate features provides it. Returns `None` without generating
+    /// any rustdoc if the check is allowed, or the release bump already permits its findings.
+    pub(crate) fn run(
+        &self,
+        config: &mut GlobalConfig,
+        generation_settings: GenerationSettings,
+        checked_current: &VersionedStorage,
+        timings: &mut CrateTimings,
+    ) -> anyhow::Result<Option<LintResult>> {
+        // These builds are only queried for the gated items, never skipped as unchanged.
+        let generation_settings = GenerationSettings {
+            fingerprint_api: false,
+            ..generation_settings
+        };
+        let semver_query = feature_gated_item_check();
+        let effective_required_update = self.overrides.effective_required_update(semver_query);
+        let effective_lint_level = self.overrides.effective_lint_level(semver_query);
+        if self
+            .detected_bump
+            .supports_requirement(effective_required_update)
+            || effective_lint_level == LintLevel::Allow
+        {
+            return Ok(None);
+        }
+
+        let crate_name = &self.current_crate_data.name;
+        config.shell_status(
+            "Checking",
+            format_args!("{crate_name} with default features, for newly feature-gated items"),
+        )?;
+
+        let default_current_config = only_default_features(self.current_crate_data.feature_config);
+        let default_baseline_config =
+            only_default_features(self.baseline_crate_data.feature_config);
+        let default_current_data = CrateDataForRustdoc {
+            feature_config: &default_current_config,
+            ..self.current_crate_data.clone()
+        };
+        let default_baseline_data = CrateDataForRustdoc {
+            feature_config: &default_baseline_config,
+            ..self.baseline_crate_data.clone()
+        };
+
+        let current_loader = StatefulRustdocGenerator::couple_data(
+            self.current_generator,
+            config,
+            &default_current_data,
+        )
+        .map_err(|err| crate::log_terminal_error(config, err))?;
+        let baseline_loader = StatefulRustdocGenerator::couple_data(
+            self.baseline_generator,
+            config,
+            &default_baseline_data,
+        )
+        .map_err(|err| crate::log_terminal_error(config, err))?;
+        let current_loader = current_loader
+            .prepare_generator(config)
+            .map_err(|err| crate::log_terminal_error(config, err))?;
+        let baseline_loader = baseline_loader
+            .prepare_generator(config)
+            .map_err(|err| crate::log_terminal_error(config, err))?;
+
+        let data_storage = crate::generate_crate_data(
+            config,
+            generation_settings.clone(),
+            &current_loader,
+            &baseline_loader,
+            timings,
+        )
+        .map_err(|err| crate::log_terminal_error(config, err))?;
+
+        let baseline_index = data_generation::create_index(
+            data_storage.baseline_crate(),
+            self.rustdoc_indexing_mode,
+        );
+        let mut query_duration = Duration::ZERO;
+        let mut missing_from = |current: &VersionedStorage| {
+            let start = Instant::now();
+            let items = missing_items(&baseline_index, current, self.rustdoc_indexing_mode);
+            query_duration += start.elapsed();
+            items
+        };
+
+        // Items missing even under the checked features were removed outright,
+        // which the other lints already report.
+        let removed: BTreeSet<Vec<String>> = missing_from(checked_current)
+            .context("failed to compare the baseline with the checked current crate")?
+            .into_keys()
+            .collect();
+        let mut gated_items: BTreeMap<Vec<String>, QueryResult> =
+            missing_from(data_storage.current_crate())?
+                .into_iter()
+                .filter(|(path, _)| !removed.contains(path))
+                .collect();
+        let mut results = Vec::with_capacity(gated_items.len());
+        for feature in self.candidate_features.iter().take(MAX_ATTRIBUTION_BUILDS) {
+            if gated_items.is_empty() {
+                break;
+            }
+
+            let mut feature_config = default_current_config.clone();
+            feature_config.extra_features.push(feature.clone());
+            let feature_data = CrateDataForRustdoc {
+                feature_config: &feature_config,
+                ..self.current_crate_data.clone()
+            };
+            let loader = StatefulRustdocGenerator::couple_data(
+                self.current_generator,
+                config,
+                &feature_data,
+            )
+            .map_err(|err| crate::log_terminal_error(config, err))?;
+            let loader = loader
+                .prepare_generator(config)
+                .map_err(|err| crate::log_terminal_error(config, err))?;
+            let loaded = loader
+                .load_rustdoc(
+                    config,
+                    generation_settings.clone(),
+                    data_generation::CacheSettings::ReadWrite(()),
+                    timings,
+                )
+                .map_err(|err| crate::log_terminal_error(config, err))?;
+            let still_missing = missing_from(&loaded.storage)?;
+
+            let (remaining, provided): (BTreeMap<_, _>, BTreeMap<_, _>) =
+                std::mem::take(&mut gated_items)
+                    .into_iter()
+                    .partition(|(path, _)| still_missing.contains_key(path));
+            gated_items = remaining;
+            results.extend(provided.into_values().map(|mut result| {
+                result.insert("feature".into(), feature.as_str().into());
+                result
+            }));
+        }
+        results.extend(gated_items.into_values().map(|mut result| {
+            result.insert("feature".into(), FieldValue::Null);
+            result
+        }));
+
+        timings.record(
+            TimingPhase::RunQuery,
+            semver_query.id.as_str(),
+            query_duration,
+        );
+
+        Ok(Some(LintResult {
+            semver_query: semver_query.clone(),
+            query_results: results,
+            query_duration,
+            effective_required_update,
+            effective_lint_level,
+            result_configurations: Vec::new(),
+        }))
+    }
+}
+
+fn only_default_features(feature_config: &FeatureConfig) -> FeatureConfig {
+    FeatureConfig {
+        features_group: FeaturesGroup::Default,
+        extra_features: Vec::new(),
+        ..feature_config.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_gated_item_check_is_not_a_lint() {
+        assert!(
+            !crate::query::LintRegistry::get()
+                .queries()
+                .contains_key(CHECK_ID)
+        );
+        assert_eq!(feature_gated_item_check().id, CHECK_ID);
+    }
+}
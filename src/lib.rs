@@ -1,46 +1,66 @@
 #![forbid(unsafe_code)]
 
+mod bisect;
+mod bug_report;
+mod cache;
 mod callbacks;
 mod check_release;
 mod config;
 mod data_generation;
+mod feature_gating;
 mod manifest;
+mod phase_limits;
 mod query;
+mod query_limits;
+mod registry;
 mod rustdoc_gen;
 mod templating;
+mod timings;
 mod util;
 mod witness_gen;
 
 use anyhow::Context;
 use cargo_metadata::PackageId;
 use clap::ValueEnum;
-use data_generation::{DataStorage, IntoTerminalResult as _, TerminalError};
+use data_generation::{DataStorage, IntoTerminalResult as _, LoadedRustdoc, TerminalError};
 use directories::ProjectDirs;
 use itertools::Itertools;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt::Write as _;
 use std::io::Write as _;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use check_release::{CheckReleaseSettings, LintResult, PendingCrateReport, run_check_release};
 use rustdoc_gen::CrateDataForRustdoc;
 
+pub use bisect::BisectedCommit;
+pub use cache::{CacheEntry, PrunePolicy, RustdocCache};
 pub use config::{FeatureFlag, GlobalConfig};
 pub use query::{
     ActualSemverUpdate, LintLevel, OverrideMap, OverrideStack, QueryOverride, RequiredSemverUpdate,
     SemverQuery, Witness, WitnessPurpose,
 };
+pub use timings::TimingsFormat;
 
 /// Test a release for semver violations.
 #[non_exhaustive]
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Check {
     /// Which packages to analyze.
     scope: Scope,
     current: Rustdoc,
     baseline: Rustdoc,
+    /// Further baselines to check against, each separately, after `baseline`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    additional_baselines: Vec<Rustdoc>,
+    /// Whether to check each local crate against every non-yanked registry release
+    /// in its version's semver-compatible series, instead of only the latest one.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    baseline_all_in_major: bool,
 
     /// Whether we should consider stability attributes when determining public API status.
     /// Stability attributes are not currently stable, and are only used internally
@@ -49,28 +69,275 @@ pub struct Check {
     rustdoc_indexing_mode: RustdocIndexingMode,
 
     release_type: Option<ReleaseType>,
+    /// Which changes pre-releases of the same version may make relative to each other.
+    #[serde(skip_serializing_if = "PrereleasePolicy::is_breaking_allowed")]
+    prerelease_policy: PrereleasePolicy,
+    /// Which changes each kind of release may make. Unset means the policy configured
+    /// in each crate's manifest, or else Cargo's rules.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versioning_policy: Option<VersioningPolicy>,
     current_feature_config: rustdoc_gen::FeatureConfig,
     baseline_feature_config: rustdoc_gen::FeatureConfig,
-    /// Which `--target` to use, if unset pass no flag
-    build_target: Option<String>,
+    /// The `--target`s to check the crate on, one at a time. If empty, pass no flag.
+    #[serde(rename = "build_target", serialize_with = "serialize_build_targets")]
+    build_targets: Vec<String>,
+    /// Whether to check each local crate on the targets listed in its
+    /// `[package.metadata.docs.rs]` table, unless build targets are given.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    docs_rs_targets: bool,
     /// Options for generating [witnesses](Witness).
     witness_generation: WitnessGeneration,
+    /// Whether and how to report the time spent in each phase of the check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timings: Option<TimingsFormat>,
+    /// Whether to run lints even when the baseline and current public APIs are identical.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    check_unchanged_api: bool,
+    /// How many crates to check concurrently. Unset means one at a time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jobs: Option<NonZeroUsize>,
+    /// The `[registries]` name of the registry to look up registry baselines in.
+    /// Unset means crates.io.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry: Option<String>,
+    /// Whether to use only already-downloaded crates and registry index entries.
+    /// Cargo's own offline configuration applies even when this is unset.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    offline: bool,
+    /// The lockfile to seed dependency resolution with when generating rustdoc.
+    /// Unset means dependencies are resolved without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dependency_lockfile: Option<DependencyLockfile>,
+    /// How long each `cargo` invocation generating rustdoc may run. Unset means no limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_timeout: Option<Duration>,
+    /// How long each lint may run on each crate. Unset means no limit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query_timeout: Option<Duration>,
+    /// Stops the check early once cancelled, such as from another thread.
+    #[serde(skip)]
+    cancellation: Option<CancellationToken>,
+    /// The rustup toolchain to generate the current crate's rustdoc with,
+    /// as in `cargo +<toolchain>`. Unset means the toolchain selected by rustup as usual.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_toolchain: Option<String>,
+    /// The rustup toolchain to generate the baseline crate's rustdoc with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    baseline_toolchain: Option<String>,
+    /// Additional feature sets to check each local crate with, one at a time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    feature_matrix: Option<FeatureMatrix>,
+    /// Whether to also look for public items that are no longer available
+    /// with the crate's default features.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    check_feature_gating: bool,
 }
 
 /// The kind of release we're making.
 ///
 /// Affects which lints are executed.
-/// Non-exhaustive in case we want to add "pre-release" as an option in the future.
 #[non_exhaustive]
 #[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum ReleaseType {
     Major,
     Minor,
     Patch,
+    /// A pre-release, whose allowed changes follow the [`PrereleasePolicy`].
+    Prerelease,
+}
+
+/// Which changes a pre-release may make relative to the previous pre-release
+/// of the same version, and the final release relative to its last pre-release.
+///
+/// Changes between different versions, such as from `1.4.0` to `2.0.0-alpha.1`,
+/// are classified by their version numbers as usual.
+#[non_exhaustive]
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum PrereleasePolicy {
+    /// Pre-releases may make breaking changes, as the semver specification allows.
+    /// Crates are checked against their last stable release.
+    #[default]
+    BreakingAllowed,
+    /// Alpha pre-releases, like `2.0.0-alpha.3`, may make breaking changes.
+    /// Later pre-releases and the final release must stay compatible with
+    /// the pre-release before them.
+    BreakingInAlpha,
+    /// Each pre-release and the final release must stay compatible with
+    /// the pre-release before them.
+    Compatible,
+}
+
+impl PrereleasePolicy {
+    fn is_breaking_allowed(&self) -> bool {
+        matches!(self, Self::BreakingAllowed)
+    }
+
+    /// The change allowed in a release following the pre-release with identifier `pre`
+    /// of the same version, or `None` if the policy doesn't restrict it
+    /// or `pre` is empty, as for a stable release.
+    pub(crate) fn update_after(self, pre: &semver::Prerelease) -> Option<ActualSemverUpdate> {
+        let is_alpha = pre
+            .as_str()
+            .split('.')
+            .next()
+            .is_some_and(|phase| phase.to_ascii_lowercase().starts_with("alpha"));
+        match self {
+            _ if pre.is_empty() => None,
+            Self::BreakingAllowed => None,
+            Self::BreakingInAlpha if is_alpha => None,
+            // Compatible pre-releases may still add to the API.
+            Self::BreakingInAlpha | Self::Compatible => Some(ActualSemverUpdate::Minor),
+        }
+    }
+}
+
+/// A `Cargo.lock` to seed dependency resolution with, so that the baseline and current
+/// crates are documented against the same versions of their dependencies.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum DependencyLockfile {
+    /// The `Cargo.lock` of the current project's workspace.
+    CurrentProject,
+    /// The given lockfile, such as one recorded at the baseline's git revision.
+    File(PathBuf),
+}
+
+/// A handle for stopping a running [`Check`], such as from another thread.
+///
+/// Clones share the same state, so cancelling any of them cancels the check.
+/// Running `cargo` invocations are killed, and the check returns an error
+/// naming the crate and the phase it was in.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stop the check at the next opportunity.
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+/// Tokens are equal when they're clones of each other.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+/// Which changes each kind of release may make, by how its version number changed.
+///
+/// Cargo assumes that releases are compatible according to its own rules when resolving
+/// dependencies, so no policy allows breaking changes where Cargo's rules don't.
+#[non_exhaustive]
+#[derive(ValueEnum, Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum VersioningPolicy {
+    /// Cargo's rules: a change in the left-most non-zero version component may break
+    /// the API, and a change in the next component may add to it.
+    #[default]
+    Cargo,
+    /// Like Cargo's rules, except that in `0.y.z` versions, a change to `z`
+    /// is a patch release that may not add to the API.
+    StrictZeroVer,
+    /// Only releases that may break the API may add to it. Other releases,
+    /// such as minor releases of `x.y.z` versions, are held to the rules for patch releases.
+    Conservative,
+    /// Calendar or epoch versioning, like `2025.3.1`, where only the left-most component
+    /// signals compatibility: releases that change it may break the API,
+    /// and any other release may add to it. `0.y.z` versions follow Cargo's rules.
+    Calendar,
+}
+
+impl VersioningPolicy {
+    /// The change this policy allows in a release of `version_current`,
+    /// given the change that Cargo's rules allow in it.
+    pub(crate) fn allowed_update(
+        self,
+        update: ActualSemverUpdate,
+        version_current: &semver::Version,
+    ) -> ActualSemverUpdate {
+        match (self, update) {
+            (Self::StrictZeroVer, ActualSemverUpdate::Minor) if version_current.major == 0 => {
+                ActualSemverUpdate::Patch
+            }
+            (Self::Conservative, ActualSemverUpdate::Minor) => ActualSemverUpdate::Patch,
+            (Self::Calendar, ActualSemverUpdate::Patch) if version_current.major != 0 => {
+                ActualSemverUpdate::Minor
+            }
+            (_, update) => update,
+        }
+    }
+}
+
+/// Which sets of features to check a crate with, one at a time, in addition to the
+/// features chosen by the [`Check`]'s feature configuration. Each set is enabled
+/// on top of that configuration, for both the baseline and the current crate.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FeatureMatrix {
+    /// Each of the crate's features on its own.
+    EachFeature,
+    /// Every combination of the crate's features, optionally only
+    /// the ones with at most `depth` features.
+    Powerset { depth: Option<NonZeroUsize> },
+}
+
+/// The most feature sets a [`FeatureMatrix::Powerset`] without a `depth` checks a crate with.
+/// Each one takes a separate rustdoc build for both crates, so all combinations of
+/// many features would take practically forever.
+const MAX_POWERSET_FEATURE_SETS: usize = 256;
+
+impl FeatureMatrix {
+    /// Bound a powerset of `feature_count` features without a `depth` to the combinations
+    /// of as many features as keep it within [`MAX_POWERSET_FEATURE_SETS`], if all of them don't.
+    fn capped(self, feature_count: usize) -> Self {
+        let Self::Powerset { depth: None } = self else {
+            return self;
+        };
+        let mut sets = 1usize;
+        let mut combinations = 1usize;
+        for size in 1..=feature_count {
+            // The number of combinations of `size` features, from the one of `size - 1`.
+            combinations = combinations.saturating_mul(feature_count - size + 1) / size;
+            sets = sets.saturating_add(combinations);
+            if sets > MAX_POWERSET_FEATURE_SETS {
+                return Self::Powerset {
+                    depth: NonZeroUsize::new(size - 1).or(NonZeroUsize::new(1)),
+                };
+            }
+        }
+        self
+    }
+
+    /// The feature sets to check, starting with the empty set:
+    /// only the features chosen by the feature configuration.
+    fn feature_sets(self, features: &[String]) -> Vec<Vec<String>> {
+        match self {
+            Self::EachFeature => std::iter::once(Vec::new())
+                .chain(features.iter().map(|feature| vec![feature.clone()]))
+                .collect(),
+            Self::Powerset { depth } => {
+                let depth = depth.map_or(features.len(), NonZeroUsize::get);
+                (0..=depth.min(features.len()))
+                    .flat_map(|size| features.iter().cloned().combinations(size))
+                    .collect()
+            }
+        }
+    }
 }
 
 #[non_exhaustive]
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Rustdoc {
     source: RustdocSource,
 }
@@ -121,6 +388,14 @@ impl Rustdoc {
         }
     }
 
+    /// Generate the rustdoc file from a packaged `.crate` file, such as one made by
+    /// `cargo package`, the same way as for a crate downloaded from a registry.
+    pub fn from_crate_file(crate_file: impl Into<PathBuf>) -> Self {
+        Self {
+            source: RustdocSource::CrateFile(crate_file.into()),
+        }
+    }
+
     /// Generate the rustdoc file from the largest-numbered non-yanked non-prerelease version
     /// published to the cargo registry. If no such version, uses
     /// the largest-numbered version including yanked and prerelease versions.
@@ -138,7 +413,7 @@ impl Rustdoc {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 enum RustdocSource {
     /// Path to the Rustdoc json file.
     /// Use this option when you have already generated the rustdoc file.
@@ -148,6 +423,8 @@ enum RustdocSource {
     Root(PathBuf),
     /// Project root directory and Git Revision.
     Revision(PathBuf, String),
+    /// Path to a packaged `.crate` file.
+    CrateFile(PathBuf),
     /// Version from cargo registry to lookup. E.g. "1.0.0".
     /// If `None`, uses the largest-numbered non-yanked non-prerelease version
     /// published to the cargo registry. If no such version, uses
@@ -156,12 +433,12 @@ enum RustdocSource {
 }
 
 /// Which packages to analyze.
-#[derive(Default, Debug, PartialEq, Eq, Serialize)]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize)]
 struct Scope {
     mode: ScopeMode,
 }
 
-#[derive(Debug, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 enum ScopeMode {
     /// All packages except the excluded ones.
     DenyList(PackageSelection),
@@ -272,10 +549,57 @@ impl Scope {
     }
 }
 
+/// Serializes the build targets of a [`Check`] as its single `build_target` used to be,
+/// unless there are several of them, so that checks on one target serialize as before.
+fn serialize_build_targets<S: serde::Serializer>(
+    build_targets: &[String],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match build_targets {
+        [] => serializer.serialize_none(),
+        [build_target] => serializer.serialize_some(build_target),
+        build_targets => build_targets.serialize(serializer),
+    }
+}
+
 struct CrateToCheck<'a> {
     overrides: OverrideStack,
     current_crate_data: CrateDataForRustdoc<'a>,
     baseline_crate_data: CrateDataForRustdoc<'a>,
+    /// The `--target`s to check the crate on. If empty, pass no flag.
+    build_targets: Vec<String>,
+    /// Sets of features to enable on top of the configured ones, checking each separately.
+    /// If empty, only the configured features are checked.
+    feature_sets: Vec<Vec<String>>,
+    /// Features that may gate public items, when checking for newly feature-gated ones.
+    /// If empty, gated items aren't attributed to a feature.
+    gating_features: Vec<String>,
+    versioning_policy: VersioningPolicy,
+}
+
+/// What checking each crate shares, set up once before checking any of them.
+struct SharedCheckData {
+    generation_settings: data_generation::GenerationSettings,
+    current_generator: rustdoc_gen::RustdocGenerator,
+    /// The baselines to check against, and how to describe each one.
+    baseline_generators: Vec<(String, rustdoc_gen::RustdocGenerator)>,
+    witness_target_dir: PathBuf,
+    /// Whether to check each local crate against every compatible release in the registry,
+    /// instead of the configured baselines.
+    all_in_major: bool,
+    phase_limits: phase_limits::PhaseLimits,
+}
+
+/// One configuration to check a crate in: against one baseline,
+/// on one build target, with one set of extra features.
+struct CrateConfiguration<'a> {
+    /// Tells the configuration apart from the crate's others.
+    description: String,
+    baseline_generator: &'a rustdoc_gen::RustdocGenerator,
+    baseline_crate_data: &'a CrateDataForRustdoc<'a>,
+    build_target: Option<&'a str>,
+    /// Features to enable on top of the configured ones, if checking a feature matrix.
+    extra_features: Option<&'a [String]>,
 }
 
 /// Is the specified target able to be semver-checked as a library, of any sort.
@@ -305,12 +629,30 @@ impl Check {
             scope: Scope::default(),
             current,
             baseline: Rustdoc::from_registry_latest_crate_version(),
+            additional_baselines: Vec::new(),
+            baseline_all_in_major: false,
             rustdoc_indexing_mode: RustdocIndexingMode::default(),
             release_type: None,
+            prerelease_policy: PrereleasePolicy::default(),
+            versioning_policy: None,
             current_feature_config: rustdoc_gen::FeatureConfig::default_for_current(),
             baseline_feature_config: rustdoc_gen::FeatureConfig::default_for_baseline(),
-            build_target: None,
+            build_targets: Vec::new(),
+            docs_rs_targets: false,
             witness_generation: WitnessGeneration::default(),
+            timings: None,
+            check_unchanged_api: false,
+            jobs: None,
+            registry: None,
+            offline: false,
+            dependency_lockfile: None,
+            generation_timeout: None,
+            query_timeout: None,
+            cancellation: None,
+            current_toolchain: None,
+            baseline_toolchain: None,
+            feature_matrix: None,
+            check_feature_gating: false,
         }
     }
 
@@ -324,8 +666,36 @@ impl Check {
         self
     }
 
+    /// Set the baseline to check against, replacing any baselines set before.
     pub fn set_baseline(&mut self, baseline: Rustdoc) -> &mut Self {
         self.baseline = baseline;
+        self.additional_baselines.clear();
+        self
+    }
+
+    /// Check against each of the given baselines, generating rustdoc and running lints
+    /// separately for each one. Findings are merged into one report per crate,
+    /// noting which baselines each one was found against.
+    ///
+    /// The first baseline is used wherever only one can be, such as for
+    /// [`Check::set_check_feature_gating`]. Leaves the baseline unchanged if given none.
+    pub fn set_baselines(&mut self, baselines: impl IntoIterator<Item = Rustdoc>) -> &mut Self {
+        let mut baselines = baselines.into_iter();
+        if let Some(baseline) = baselines.next() {
+            self.baseline = baseline;
+            self.additional_baselines = baselines.collect();
+        }
+        self
+    }
+
+    /// Check each crate in the local project against every non-yanked, non-pre-release
+    /// registry release in its version's semver-compatible series, rather than only
+    /// the latest one. Rustdoc for each release is cached as usual, so later runs
+    /// only generate it for newly published releases.
+    ///
+    /// Only applies when the baseline is the default one from the registry.
+    pub fn set_baseline_all_in_major(&mut self, baseline_all_in_major: bool) -> &mut Self {
+        self.baseline_all_in_major = baseline_all_in_major;
         self
     }
 
@@ -334,6 +704,26 @@ impl Check {
         self
     }
 
+    /// Set which changes pre-releases of the same version may make relative to each other.
+    ///
+    /// Unless the policy allows breaking changes in all pre-releases, a pre-release
+    /// is checked against the previous pre-release of the same version published
+    /// to the registry, if any, instead of against the last stable release.
+    pub fn set_prerelease_policy(&mut self, prerelease_policy: PrereleasePolicy) -> &mut Self {
+        self.prerelease_policy = prerelease_policy;
+        self
+    }
+
+    /// Set which changes each kind of release may make, for every checked crate.
+    ///
+    /// Unless set, crates in the local project follow the `versioning-policy` key of their
+    /// `[package.metadata.cargo-semver-checks]` or `[workspace.metadata.cargo-semver-checks]`
+    /// table, and other crates follow Cargo's rules.
+    pub fn set_versioning_policy(&mut self, versioning_policy: VersioningPolicy) -> &mut Self {
+        self.versioning_policy = Some(versioning_policy);
+        self
+    }
+
     #[doc(hidden)]
     pub fn set_rustdoc_indexing_mode(&mut self, mode: RustdocIndexingMode) -> &mut Self {
         self.rustdoc_indexing_mode = mode;
@@ -377,7 +767,53 @@ impl Check {
     /// Set what `--target` to build the documentation with, by default will not pass any flag
     /// relying on the users cargo configuration.
     pub fn set_build_target(&mut self, build_target: String) -> &mut Self {
-        self.build_target = Some(build_target);
+        self.build_targets = vec![build_target];
+        self
+    }
+
+    /// Check the crate on each of the given `--target`s, generating rustdoc and running lints
+    /// separately for each one. Findings are merged into one report per crate,
+    /// noting which targets each one was found on.
+    pub fn set_build_targets(
+        &mut self,
+        build_targets: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.build_targets = build_targets
+            .into_iter()
+            .map(Into::<String>::into)
+            .unique()
+            .collect();
+        self
+    }
+
+    /// Also check each crate in the local project with each of the feature sets
+    /// in the given [`FeatureMatrix`], on top of the otherwise chosen features.
+    /// Findings are merged into one report per crate, noting which feature sets
+    /// each one was found with.
+    pub fn set_feature_matrix(&mut self, feature_matrix: FeatureMatrix) -> &mut Self {
+        self.feature_matrix = Some(feature_matrix);
+        self
+    }
+
+    /// Also look for public items that the current crate no longer provides with
+    /// its default features, such as ones moved behind a non-default feature.
+    ///
+    /// Lints compare the crates under the same features, so they can't see such items
+    /// when the gating feature is enabled for the check. This check generates rustdoc
+    /// for both crates with only their default features, and then for the current crate
+    /// with each non-default feature until every such item is attributed to one.
+    /// At most 8 features are tried; items that none of them bring back are still
+    /// reported, without a feature.
+    pub fn set_check_feature_gating(&mut self, check_feature_gating: bool) -> &mut Self {
+        self.check_feature_gating = check_feature_gating;
+        self
+    }
+
+    /// Check each crate in the local project on the targets listed in its
+    /// `[package.metadata.docs.rs]` table, the same ones docs.rs builds documentation for.
+    /// Targets set with [`Check::set_build_targets`] take precedence.
+    pub fn set_docs_rs_targets(&mut self, docs_rs_targets: bool) -> &mut Self {
+        self.docs_rs_targets = docs_rs_targets;
         self
     }
 
@@ -387,6 +823,188 @@ impl Check {
         self
     }
 
+    /// Report how long rustdoc generation, parsing, indexing, each query,
+    /// and witness execution took for each crate, in the given format.
+    pub fn set_timings(&mut self, format: TimingsFormat) -> &mut Self {
+        self.timings = Some(format);
+        self
+    }
+
+    /// Run lints even when the baseline and current public APIs are identical,
+    /// instead of skipping them.
+    pub fn set_check_unchanged_api(&mut self, check_unchanged_api: bool) -> &mut Self {
+        self.check_unchanged_api = check_unchanged_api;
+        self
+    }
+
+    /// Check several crates at the same time: up to `jobs` crates generate rustdoc
+    /// while up to `jobs` others run lints. Each crate's output is still printed
+    /// as one uninterrupted block, in the same order as when checking crates one at a time.
+    pub fn set_jobs(&mut self, jobs: NonZeroUsize) -> &mut Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Look up registry baselines in the named registry from the `[registries]` table
+    /// of cargo config, instead of crates.io. `[source]` replacement in cargo config
+    /// is honored, as is the `CARGO_REGISTRIES_<NAME>_INDEX` environment variable.
+    pub fn set_registry(&mut self, registry: impl Into<String>) -> &mut Self {
+        self.registry = Some(registry.into());
+        self
+    }
+
+    /// Never access the network: look up registry baselines only in the parts of the registry
+    /// index that cargo already downloaded, and build rustdoc only from already-downloaded crates.
+    ///
+    /// Offline mode is also enabled by `CARGO_NET_OFFLINE` or `net.offline` in cargo config.
+    pub fn set_offline(&mut self, offline: bool) -> &mut Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Seed dependency resolution with the given lockfile whenever rustdoc is generated,
+    /// for both the baseline and current crates. Dependencies are then kept at their locked
+    /// versions where possible, instead of being updated to the latest compatible ones.
+    pub fn set_dependency_lockfile(&mut self, lockfile: DependencyLockfile) -> &mut Self {
+        self.dependency_lockfile = Some(lockfile);
+        self
+    }
+
+    /// Abort the check if any `cargo` invocation generating rustdoc runs for longer
+    /// than `timeout`, such as when a build script deadlocks.
+    pub fn set_generation_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.generation_timeout = Some(timeout);
+        self
+    }
+
+    /// Abort the check if any lint runs for longer than `timeout` on a single crate.
+    ///
+    /// Lints produce their results one at a time, and the time is checked in between,
+    /// so a lint may overrun its budget until its next result.
+    pub fn set_query_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.query_timeout = Some(timeout);
+        self
+    }
+
+    /// Stop the check early once `token` is cancelled.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) -> &mut Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Generate rustdoc for both the current and baseline crates with the given
+    /// rustup toolchain, the same way as `cargo +<toolchain>` would.
+    pub fn set_toolchain(&mut self, toolchain: impl Into<String>) -> &mut Self {
+        let toolchain = toolchain.into();
+        self.current_toolchain = Some(toolchain.clone());
+        self.baseline_toolchain = Some(toolchain);
+        self
+    }
+
+    /// Generate rustdoc for the current crate with the given rustup toolchain.
+    pub fn set_current_toolchain(&mut self, toolchain: impl Into<String>) -> &mut Self {
+        self.current_toolchain = Some(toolchain.into());
+        self
+    }
+
+    /// Generate rustdoc for the baseline crate with the given rustup toolchain.
+    ///
+    /// If the two toolchains emit different rustdoc JSON format versions,
+    /// the crates cannot be compared and the check fails with an explanation.
+    pub fn set_baseline_toolchain(&mut self, toolchain: impl Into<String>) -> &mut Self {
+        self.baseline_toolchain = Some(toolchain.into());
+        self
+    }
+
+    /// The `--target`s to check a local package on.
+    fn build_targets_for_package(&self, package: &cargo_metadata::Package) -> Vec<String> {
+        if self.docs_rs_targets && self.build_targets.is_empty() {
+            docs_rs_build_targets(&package.metadata)
+        } else {
+            self.build_targets.clone()
+        }
+    }
+
+    /// The extra feature sets to check a local package with. Empty if there's no matrix.
+    fn feature_sets_for_package(
+        &self,
+        config: &mut GlobalConfig,
+        package: &cargo_metadata::Package,
+    ) -> anyhow::Result<Vec<Vec<String>>> {
+        let Some(feature_matrix) = self.feature_matrix else {
+            return Ok(Vec::new());
+        };
+        let features = non_default_features(package);
+        let capped = feature_matrix.capped(features.len());
+        if let FeatureMatrix::Powerset { depth: Some(depth) } = capped
+            && capped != feature_matrix
+        {
+            config.shell_warn(format_args!(
+                "{} has {} features, too many to check every combination of; \
+                 checking combinations of at most {depth} of them instead, \
+                 pass `--depth` to choose the limit",
+                package.name,
+                features.len(),
+            ))?;
+        }
+        Ok(capped.feature_sets(&features))
+    }
+
+    /// Whether offline mode is enabled, either explicitly or through cargo's configuration.
+    fn is_offline(&self) -> bool {
+        self.offline || cargo_net_offline_configured(self.cargo_config_dir().as_deref())
+    }
+
+    /// The directory whose cargo configuration applies, like cargo run in the current project.
+    /// `None` if the current crate isn't a project, in which case the working directory's applies.
+    fn cargo_config_dir(&self) -> Option<PathBuf> {
+        match &self.current.source {
+            RustdocSource::Root(root) | RustdocSource::Revision(root, _) => {
+                let manifest_path = manifest_path(root).ok()?;
+                let dir = manifest_path.parent()?;
+                Some(if dir.as_os_str().is_empty() {
+                    PathBuf::from(".")
+                } else {
+                    dir.to_owned()
+                })
+            }
+            RustdocSource::Rustdoc(_)
+            | RustdocSource::CrateFile(_)
+            | RustdocSource::VersionFromRegistry(_) => None,
+        }
+    }
+
+    /// The path of the lockfile to seed dependency resolution with, if any.
+    fn seed_lockfile(&self, config: &mut GlobalConfig) -> anyhow::Result<Option<PathBuf>> {
+        let lockfile = match &self.dependency_lockfile {
+            None => return Ok(None),
+            Some(DependencyLockfile::File(path)) => path.clone(),
+            Some(DependencyLockfile::CurrentProject) => match &self.current.source {
+                RustdocSource::Root(project_root) => {
+                    manifest_metadata_no_deps(project_root, self.current_toolchain.as_deref())?
+                        .workspace_root
+                        .as_std_path()
+                        .join("Cargo.lock")
+                }
+                _ => {
+                    config.shell_warn(
+                        "only a local project has a lockfile, \
+                         resolving dependencies without one instead",
+                    )?;
+                    return Ok(None);
+                }
+            },
+        };
+        if !lockfile.is_file() {
+            anyhow::bail!(
+                "lockfile {} not found\n\n\
+                 HELP: to create one for a project, run `cargo generate-lockfile`",
+                lockfile.display()
+            );
+        }
+        Ok(Some(lockfile))
+    }
+
     /// Some `RustdocSource`s don't contain a path to the project root,
     /// so they don't have a target directory. We try to deduce the target directory
     /// on a "best effort" basis -- when the source contains a target dir,
@@ -394,13 +1012,23 @@ impl Check {
     /// otherwise we just use a standard cache folder as specified by XDG.
     /// We cannot use a temporary directory, because the rustdocs from registry
     /// are being cached in the target directory.
-    fn get_target_dir(&self, source: &RustdocSource) -> anyhow::Result<PathBuf> {
+    fn get_target_dir(
+        &self,
+        source: &RustdocSource,
+        toolchain: Option<&str>,
+    ) -> anyhow::Result<PathBuf> {
         Ok(
-            if let Some(path) = get_target_dir_from_project_root(source)? {
+            if let Some(path) = get_target_dir_from_project_root(source, toolchain)? {
                 path
-            } else if let Some(path) = get_target_dir_from_project_root(&self.current.source)? {
+            } else if let Some(path) = get_target_dir_from_project_root(
+                &self.current.source,
+                self.current_toolchain.as_deref(),
+            )? {
                 path
-            } else if let Some(path) = get_target_dir_from_project_root(&self.baseline.source)? {
+            } else if let Some(path) = get_target_dir_from_project_root(
+                &self.baseline.source,
+                self.baseline_toolchain.as_deref(),
+            )? {
                 path
             } else {
                 get_cache_dir()?
@@ -408,52 +1036,128 @@ impl Check {
         )
     }
 
+    /// The baselines to check against, the main one first.
+    fn baselines(&self) -> impl Iterator<Item = &Rustdoc> {
+        std::iter::once(&self.baseline).chain(&self.additional_baselines)
+    }
+
     fn get_rustdoc_generator(
         &self,
         config: &mut GlobalConfig,
         source: &RustdocSource,
+        toolchain: Option<&str>,
+        offline: bool,
     ) -> anyhow::Result<rustdoc_gen::RustdocGenerator> {
-        let target_dir = self.get_target_dir(source)?;
+        let target_dir = self.get_target_dir(source, toolchain)?;
         Ok(match source {
             RustdocSource::Rustdoc(path) => {
-                rustdoc_gen::RustdocFromFile::new(path.to_owned()).into()
+                rustdoc_gen::RustdocFromFile::new(path.to_owned(), target_dir).into()
             }
             RustdocSource::Root(root) => {
-                rustdoc_gen::RustdocFromProjectRoot::new(root, &target_dir)?.into()
+                rustdoc_gen::RustdocFromProjectRoot::new(root, &target_dir)?
+                    .with_clean_worktree_source_tree(config)
+                    .into()
             }
             RustdocSource::Revision(root, rev) => {
-                let metadata = manifest_metadata_no_deps(root)?;
+                let metadata = manifest_metadata_no_deps(root, toolchain)?;
                 let source = metadata.workspace_root.as_std_path();
                 rustdoc_gen::RustdocFromGitRevision::with_rev(source, &target_dir, rev, config)?
                     .into()
             }
+            RustdocSource::CrateFile(crate_file) => {
+                rustdoc_gen::RustdocFromCrateFile::new(crate_file, &target_dir, config)?.into()
+            }
             RustdocSource::VersionFromRegistry(version) => {
-                let mut registry = rustdoc_gen::RustdocFromRegistry::new(&target_dir, config)?;
+                let mut registry = rustdoc_gen::RustdocFromRegistry::new(
+                    &target_dir,
+                    self.registry.as_deref(),
+                    offline,
+                    config,
+                )?;
                 if let Some(ver) = version {
                     let semver = semver::Version::parse(ver)?;
                     registry.set_version(semver);
                 }
+                registry
+                    .set_prefer_prerelease_baselines(!self.prerelease_policy.is_breaking_allowed());
                 registry.into()
             }
         })
     }
 
+    /// Find the first commit in the git history from the baseline revision to `current_rev`
+    /// at which checking against the baseline fails, or at which `lint` first fires if given.
+    ///
+    /// The baseline must be a git revision. Commits are checked one at a time with
+    /// otherwise the same settings, binary-searching along the first-parent history.
+    pub fn bisect(
+        &self,
+        config: &mut GlobalConfig,
+        current_rev: &str,
+        lint: Option<&str>,
+    ) -> anyhow::Result<BisectedCommit> {
+        bisect::bisect(self, config, current_rev, lint)
+    }
+
+    /// Run the check, then write a tar archive to `bundle` with everything needed to reproduce
+    /// the run: this configuration, tool and toolchain versions, cargo config, and the
+    /// placeholder manifests and commands that each crate's rustdoc was generated with.
+    ///
+    /// If `include_rustdoc` is set, the rustdoc JSON that was compared is included too.
+    /// The archive is written even if the check fails, and records how it ended.
+    pub fn write_bug_report_bundle(
+        &self,
+        config: &mut GlobalConfig,
+        bundle: &Path,
+        include_rustdoc: bool,
+    ) -> anyhow::Result<()> {
+        bug_report::write_bundle(self, config, bundle, include_rustdoc)
+    }
+
     pub fn check_release(&self, config: &mut GlobalConfig) -> anyhow::Result<Report> {
+        self.check_release_with_recorder(config, None)
+    }
+
+    /// Run the check, recording each rustdoc data request in `recorder` if given.
+    fn check_release_with_recorder(
+        &self,
+        config: &mut GlobalConfig,
+        recorder: Option<data_generation::RequestRecorder>,
+    ) -> anyhow::Result<Report> {
+        let jobs = self.jobs.map_or(1, NonZeroUsize::get);
+        let offline = self.is_offline();
         let generation_settings = data_generation::GenerationSettings {
             use_color: config.err_color_choice(),
-            pass_through_stderr: config.is_verbose(),
+            // Concurrent `cargo` invocations writing directly to our stderr would interleave.
+            pass_through_stderr: config.is_verbose() && jobs == 1,
+            offline,
+            timeout: self.generation_timeout,
+            cancellation: self.cancellation.clone(),
+            recorder,
+            // The fingerprint is only used to skip linting an unchanged API.
+            fingerprint_api: !self.check_unchanged_api,
         };
 
-        // If both the current and baseline rustdoc are given explicitly as a file path,
-        // we don't need to use the installed rustc, and this check can be skipped.
-        if !(matches!(self.current.source, RustdocSource::Rustdoc(_))
-            && matches!(self.baseline.source, RustdocSource::Rustdoc(_)))
-        {
+        // Rustdoc given explicitly as a file path doesn't need the installed rustc,
+        // so the check is only done for the toolchains we'll generate rustdoc with.
+        let generated_toolchains: BTreeSet<Option<&str>> = self
+            .baselines()
+            .map(|baseline| (&baseline.source, self.baseline_toolchain.as_deref()))
+            .chain([(&self.current.source, self.current_toolchain.as_deref())])
+            .filter(|(source, _)| !matches!(source, RustdocSource::Rustdoc(_)))
+            .map(|(_, toolchain)| toolchain)
+            .collect();
+        for toolchain in generated_toolchains {
             let rustc_version_needed = config.minimum_rustc_version();
-            match rustc_version::version() {
+            match rustc_version_for_toolchain(toolchain) {
                 Ok(rustc_version) => {
                     if rustc_version < *rustc_version_needed {
-                        let help = "HELP: to use the latest rustc, run `rustup update stable && cargo +stable semver-checks <args>`";
+                        let help = match toolchain {
+                            Some(toolchain) => format!(
+                                "HELP: to use the latest rustc for toolchain `{toolchain}`, run `rustup update {toolchain}`"
+                            ),
+                            None => "HELP: to use the latest rustc, run `rustup update stable && cargo +stable semver-checks <args>`".to_string(),
+                        };
                         anyhow::bail!(
                             "rustc version is not high enough: >={rustc_version_needed} needed, got {rustc_version}\n\n{help}"
                         );
@@ -470,10 +1174,24 @@ impl Check {
             };
         }
 
+        let seed_lockfile = self.seed_lockfile(config)?;
         let crates_to_check: Vec<CrateToCheck<'_>> = match &self.current.source {
             RustdocSource::Rustdoc(_)
             | RustdocSource::Revision(_, _)
+            | RustdocSource::CrateFile(_)
             | RustdocSource::VersionFromRegistry(_) => {
+                if self.docs_rs_targets && self.build_targets.is_empty() {
+                    config.shell_warn(
+                        "docs.rs targets can only be read from a local project's manifest, \
+                         checking the default target instead",
+                    )?;
+                }
+                if self.feature_matrix.is_some() {
+                    config.shell_warn(
+                        "features can only be listed from a local project's manifest, \
+                         checking only the configured features instead",
+                    )?;
+                }
                 let names = match &self.scope.mode {
                     ScopeMode::DenyList(_) => match &self.current.source {
                         RustdocSource::Rustdoc(_) => {
@@ -498,7 +1216,9 @@ impl Check {
                                 crate_type: rustdoc_gen::CrateType::Current,
                                 name: name.clone(),
                                 feature_config: &self.current_feature_config,
-                                build_target: self.build_target.as_deref(),
+                                build_target: None,
+                                toolchain: self.current_toolchain.as_deref(),
+                                lockfile: seed_lockfile.as_deref(),
                             },
                             baseline_crate_data: CrateDataForRustdoc {
                                 crate_type: rustdoc_gen::CrateType::Baseline {
@@ -506,14 +1226,21 @@ impl Check {
                                 },
                                 name,
                                 feature_config: &self.baseline_feature_config,
-                                build_target: self.build_target.as_deref(),
+                                build_target: None,
+                                toolchain: self.baseline_toolchain.as_deref(),
+                                lockfile: seed_lockfile.as_deref(),
                             },
+                            build_targets: self.build_targets.clone(),
+                            feature_sets: Vec::new(),
+                            gating_features: Vec::new(),
+                            versioning_policy: self.versioning_policy.unwrap_or_default(),
                         }
                     })
                     .collect()
             }
             RustdocSource::Root(project_root) => {
-                let metadata = manifest_metadata(project_root)?;
+                let metadata =
+                    manifest_metadata(project_root, self.current_toolchain.as_deref(), offline)?;
                 let (selected, skipped) = self.scope.selected_packages(&metadata);
                 if selected.is_empty() {
                     let help = if skipped.is_empty() {
@@ -535,6 +1262,9 @@ note: skipped the following crates since they have no library target: {skipped}"
                     manifest::deserialize_lint_table(&metadata.workspace_metadata)
                         .context("[workspace.metadata.cargo-semver-checks] table is invalid")?
                         .map(|table| table.into_stack());
+                let workspace_versioning_policy =
+                    manifest::deserialize_versioning_policy(&metadata.workspace_metadata)
+                        .context("[workspace.metadata.cargo-semver-checks] table is invalid")?;
 
                 selected
                     .iter()
@@ -569,6 +1299,19 @@ note: skipped the following crates since they have no library target: {skipped}"
                                 selected,
                                 workspace_overrides.as_deref(),
                             )?;
+                            let versioning_policy = match self.versioning_policy {
+                                Some(policy) => policy,
+                                None => manifest::deserialize_versioning_policy(&selected.metadata)
+                                    .with_context(|| {
+                                        format!(
+                                            "package `{}`'s [package.metadata.cargo-semver-checks] \
+                                             table is invalid (at {})",
+                                            selected.name, selected.manifest_path,
+                                        )
+                                    })?
+                                    .or(workspace_versioning_policy)
+                                    .unwrap_or_default(),
+                            };
 
                             Ok(Some(CrateToCheck {
                                 overrides,
@@ -576,7 +1319,9 @@ note: skipped the following crates since they have no library target: {skipped}"
                                     crate_type: rustdoc_gen::CrateType::Current,
                                     name: crate_name.to_string(),
                                     feature_config: &self.current_feature_config,
-                                    build_target: self.build_target.as_deref(),
+                                    build_target: None,
+                                    toolchain: self.current_toolchain.as_deref(),
+                                    lockfile: seed_lockfile.as_deref(),
                                 },
                                 baseline_crate_data: CrateDataForRustdoc {
                                     crate_type: rustdoc_gen::CrateType::Baseline {
@@ -584,8 +1329,18 @@ note: skipped the following crates since they have no library target: {skipped}"
                                     },
                                     name: crate_name.to_string(),
                                     feature_config: &self.baseline_feature_config,
-                                    build_target: self.build_target.as_deref(),
+                                    build_target: None,
+                                    toolchain: self.baseline_toolchain.as_deref(),
+                                    lockfile: seed_lockfile.as_deref(),
+                                },
+                                build_targets: self.build_targets_for_package(selected),
+                                feature_sets: self.feature_sets_for_package(config, selected)?,
+                                gating_features: if self.check_feature_gating {
+                                    non_default_features(selected)
+                                } else {
+                                    Vec::new()
                                 },
+                                versioning_policy,
                             }))
                         }
                     })
@@ -594,78 +1349,104 @@ note: skipped the following crates since they have no library target: {skipped}"
             }
         };
 
-        let current_loader = self.get_rustdoc_generator(config, &self.current.source)?;
-        let baseline_loader = self.get_rustdoc_generator(config, &self.baseline.source)?;
-        let witness_target_dir = self.get_target_dir(&self.current.source)?;
-
-        // Create a report for each crate.
-        // We want to run all the checks, even if one returns `Err`.
-        let all_outcomes: Vec<anyhow::Result<(String, PendingCrateReport)>> = crates_to_check
-            .into_iter()
-            .map(|selected| {
-                let start = std::time::Instant::now();
-                let name = selected.current_crate_data.name.clone();
-
-                let current_loader = rustdoc_gen::StatefulRustdocGenerator::couple_data(
-                    &current_loader,
-                    config,
-                    &selected.current_crate_data,
-                )
-                .map_err(|err| log_terminal_error(config, err))?;
-                let baseline_loader = rustdoc_gen::StatefulRustdocGenerator::couple_data(
-                    &baseline_loader,
-                    config,
-                    &selected.baseline_crate_data,
-                )
-                .map_err(|err| log_terminal_error(config, err))?;
-
-                let current_loader = current_loader
-                    .prepare_generator(config)
-                    .map_err(|err| log_terminal_error(config, err))?;
-                let baseline_loader = baseline_loader
-                    .prepare_generator(config)
-                    .map_err(|err| log_terminal_error(config, err))?;
-
-                let witness_data = witness_gen::WitnessGenerationData::new(
-                    baseline_loader.get_data_request(),
-                    current_loader.get_data_request(),
-                    witness_target_dir.clone(),
-                );
-
-                let data_storage = generate_crate_data(
-                    config,
-                    generation_settings,
-                    &current_loader,
-                    &baseline_loader,
-                )
-                .map_err(|err| log_terminal_error(config, err))?;
+        let all_in_major = self.baseline_all_in_major
+            && self.additional_baselines.is_empty()
+            && self.baseline.source == RustdocSource::VersionFromRegistry(None)
+            && matches!(self.current.source, RustdocSource::Root(_));
+        if self.baseline_all_in_major && !all_in_major {
+            config.shell_warn(
+                "every compatible release can only be checked against for a local project \
+                 with the default registry baseline, checking the configured baselines instead",
+            )?;
+        }
 
-                let report = run_check_release(
+        let current_toolchain = self.current_toolchain.as_deref();
+        let baseline_toolchain = self.baseline_toolchain.as_deref();
+        let current_loader =
+            self.get_rustdoc_generator(config, &self.current.source, current_toolchain, offline)?;
+        let baseline_loaders = self
+            .baselines()
+            .map(|baseline| {
+                let loader = self.get_rustdoc_generator(
                     config,
-                    &data_storage,
-                    &name,
-                    CheckReleaseSettings {
-                        release_type: self.release_type,
-                        rustdoc_indexing_mode: self.rustdoc_indexing_mode,
-                    },
-                    &selected.overrides,
-                    &self.witness_generation,
-                    witness_data,
+                    &baseline.source,
+                    baseline_toolchain,
+                    offline,
                 )?;
-                config.shell_status(
-                    "Finished",
-                    format_args!("[{:>8.3}s] {name}", start.elapsed().as_secs_f32()),
-                )?;
-                Ok((name, report))
+                anyhow::Ok((describe_baseline(baseline), loader))
             })
-            .collect();
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let witness_target_dir = self.get_target_dir(&self.current.source, current_toolchain)?;
+
+        // The registry baselines of several crates are built together up front,
+        // sharing the build of their dependencies. Each crate's check then finds
+        // its baseline's rustdoc in the cache.
+        if crates_to_check.len() > 1
+            && matches!(self.baseline.source, RustdocSource::VersionFromRegistry(_))
+            && let [(_, baseline_loader)] = baseline_loaders.as_slice()
+        {
+            let baseline_crate_data = crates_to_check
+                .iter()
+                // A feature matrix changes the features of even the first configuration.
+                .filter(|selected| selected.feature_sets.is_empty())
+                .map(|selected| CrateDataForRustdoc {
+                    build_target: selected.build_targets.first().map(String::as_str),
+                    ..selected.baseline_crate_data.clone()
+                })
+                .collect_vec();
+            if let Err(err) = rustdoc_gen::prebuild_registry_baselines(
+                config,
+                &generation_settings,
+                baseline_loader,
+                &baseline_crate_data,
+            ) {
+                config.shell_warn(format_args!(
+                    "failed to build baselines together, building them one at a time: {err:#}"
+                ))?;
+            }
+        }
+
+        let shared = SharedCheckData {
+            generation_settings,
+            current_generator: current_loader,
+            baseline_generators: baseline_loaders,
+            witness_target_dir,
+            all_in_major,
+            phase_limits: phase_limits::PhaseLimits::new(jobs),
+        };
+
+        // Create a report for each crate.
+        // We want to run all the checks, even if one returns `Err`.
+        let check_crate = |config: &mut GlobalConfig, selected: CrateToCheck<'_>| {
+            self.check_crate(config, &shared, selected)
+        };
+        let all_outcomes: Vec<anyhow::Result<(String, PendingCrateReport)>> =
+            if jobs > 1 && crates_to_check.len() > 1 {
+                // Enough workers for `jobs` crates to generate rustdoc
+                // while `jobs` others run lints.
+                check_crates_concurrently(config, 2 * jobs, crates_to_check, check_crate)?
+            } else {
+                crates_to_check
+                    .into_iter()
+                    .map(|selected| check_crate(config, selected))
+                    .collect()
+            };
+        // Crates that were checked are still reported on if another crate failed,
+        // since their timings can explain where a slow run spent its time.
+        let mut first_error = None;
         let crate_reports: BTreeMap<String, CrateReport> = {
             let mut reports = BTreeMap::new();
             let mut witness_run_reports = Vec::new();
             for outcome in all_outcomes {
-                let (name, outcome) = outcome?;
-                witness_run_reports.push(outcome.witness_run_report);
-                reports.insert(name, outcome.report);
+                match outcome {
+                    Ok((name, outcome)) => {
+                        witness_run_reports.extend(outcome.witness_run_reports);
+                        reports.insert(name, outcome.report);
+                    }
+                    Err(err) => {
+                        first_error.get_or_insert(err);
+                    }
+                }
             }
 
             match witness_gen::finalize_retained_artifacts(config.run_id(), &witness_run_reports) {
@@ -693,7 +1474,313 @@ note: skipped the following crates since they have no library target: {skipped}"
             reports
         };
 
-        Ok(Report { crate_reports })
+        if let Some(format) = self.timings {
+            timings::report_timings(
+                config,
+                format,
+                crate_reports
+                    .iter()
+                    .map(|(name, report)| (name.as_str(), &report.timings)),
+                &shared.witness_target_dir,
+            )?;
+        }
+
+        match first_error {
+            Some(err) => Err(err),
+            None => Ok(Report { crate_reports }),
+        }
+    }
+
+    /// Check one crate in each of its configurations, merging the results into one report.
+    fn check_crate(
+        &self,
+        config: &mut GlobalConfig,
+        shared: &SharedCheckData,
+        selected: CrateToCheck<'_>,
+    ) -> anyhow::Result<(String, PendingCrateReport)> {
+        let start = std::time::Instant::now();
+        let name = selected.current_crate_data.name.clone();
+
+        let build_targets: Vec<Option<&str>> = if selected.build_targets.is_empty() {
+            vec![None]
+        } else {
+            selected
+                .build_targets
+                .iter()
+                .map(|t| Some(t.as_str()))
+                .collect()
+        };
+        let feature_sets: Vec<Option<&[String]>> = if selected.feature_sets.is_empty() {
+            vec![None]
+        } else {
+            selected
+                .feature_sets
+                .iter()
+                .map(|features| Some(features.as_slice()))
+                .collect()
+        };
+
+        // Each baseline to check against: how to describe it, how to generate its rustdoc,
+        // and the crate data to generate it with.
+        let mut baselines = Vec::new();
+        if shared.all_in_major
+            && let rustdoc_gen::CrateType::Baseline {
+                highest_allowed_version: Some(version_current),
+            } = &selected.baseline_crate_data.crate_type
+        {
+            let (_, registry_generator) = &shared.baseline_generators[0];
+            let versions = registry_generator
+                .compatible_registry_versions(
+                    config,
+                    &selected.baseline_crate_data,
+                    version_current,
+                )
+                .map_err(|err| log_terminal_error(config, err))?;
+            if versions.is_empty() {
+                config.shell_warn(format_args!(
+                    "{name} has no releases compatible with v{version_current}, \
+                     checking the default baseline instead"
+                ))?;
+            }
+            // Newest first, so that the release otherwise used as the baseline comes first.
+            baselines.extend(versions.into_iter().rev().map(|version| {
+                let description = format!("baseline v{version}");
+                let crate_data = CrateDataForRustdoc {
+                    crate_type: rustdoc_gen::CrateType::Baseline {
+                        highest_allowed_version: Some(version),
+                    },
+                    ..selected.baseline_crate_data.clone()
+                };
+                (description, registry_generator, crate_data)
+            }));
+        }
+        if baselines.is_empty() {
+            baselines.extend(
+                shared
+                    .baseline_generators
+                    .iter()
+                    .map(|(description, generator)| {
+                        (
+                            description.clone(),
+                            generator,
+                            selected.baseline_crate_data.clone(),
+                        )
+                    }),
+            );
+        }
+
+        let configurations = baselines
+            .iter()
+            .cartesian_product(build_targets.iter().cartesian_product(&feature_sets))
+            .map(
+                |(
+                    (baseline_description, baseline_generator, baseline_crate_data),
+                    (&build_target, &extra_features),
+                )| {
+                    CrateConfiguration {
+                        description: describe_configuration(
+                            Some(baseline_description.as_str()).filter(|_| baselines.len() > 1),
+                            build_target.filter(|_| build_targets.len() > 1),
+                            extra_features,
+                        ),
+                        baseline_generator,
+                        baseline_crate_data,
+                        build_target,
+                        extra_features,
+                    }
+                },
+            )
+            .collect_vec();
+        let several_configurations = configurations.len() > 1;
+
+        let check_feature_gating = self.check_feature_gating
+            && !feature_gating::is_default_features_only(
+                selected.current_crate_data.feature_config,
+            );
+        // Run along with the first configuration, and reported once all are merged.
+        let mut feature_gating_result = None;
+
+        let mut configuration_reports = Vec::with_capacity(configurations.len());
+        for configuration in configurations {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                anyhow::bail!("check was cancelled before checking crate {name}");
+            }
+
+            let (report, gating_result) = self.check_configuration(
+                config,
+                shared,
+                &selected,
+                &configuration,
+                several_configurations,
+                check_feature_gating && configuration_reports.is_empty(),
+            )?;
+            if gating_result.is_some() {
+                feature_gating_result = gating_result;
+            }
+            configuration_reports.push((configuration.description, report));
+        }
+
+        let mut report = if several_configurations {
+            PendingCrateReport::merge_configurations(configuration_reports)
+        } else {
+            let (_, report) = configuration_reports
+                .pop()
+                .expect("checked the crate in at least one configuration");
+            report
+        };
+
+        if let Some((lint_result, timings)) = feature_gating_result {
+            report.add_lint_result(lint_result, timings);
+        }
+        check_release::print_report(config, &self.witness_generation, &report.report)?;
+        config.shell_status(
+            "Finished",
+            format_args!("[{:>8.3}s] {name}", start.elapsed().as_secs_f32()),
+        )?;
+        Ok((name, report))
+    }
+
+    /// Check one crate in one configuration: generate the rustdoc of both sides
+    /// and run the lints on it. If `check_feature_gating` is set, also look for
+    /// public items that are now gated behind a non-default feature.
+    fn check_configuration(
+        &self,
+        config: &mut GlobalConfig,
+        shared: &SharedCheckData,
+        selected: &CrateToCheck<'_>,
+        configuration: &CrateConfiguration<'_>,
+        several_configurations: bool,
+        check_feature_gating: bool,
+    ) -> anyhow::Result<(
+        PendingCrateReport,
+        Option<(LintResult, timings::CrateTimings)>,
+    )> {
+        let CrateConfiguration {
+            description,
+            baseline_generator,
+            baseline_crate_data: selected_baseline_data,
+            build_target,
+            extra_features,
+        } = configuration;
+        let name = &selected.current_crate_data.name;
+
+        let with_extra_features = |feature_config: &rustdoc_gen::FeatureConfig| {
+            let mut feature_config = feature_config.clone();
+            feature_config
+                .extra_features
+                .extend(extra_features.iter().copied().flatten().cloned());
+            feature_config
+        };
+        let current_feature_config =
+            with_extra_features(selected.current_crate_data.feature_config);
+        let baseline_feature_config = with_extra_features(selected_baseline_data.feature_config);
+        let current_crate_data = CrateDataForRustdoc {
+            build_target: *build_target,
+            feature_config: &current_feature_config,
+            ..selected.current_crate_data.clone()
+        };
+        let baseline_crate_data = CrateDataForRustdoc {
+            build_target: *build_target,
+            feature_config: &baseline_feature_config,
+            ..(*selected_baseline_data).clone()
+        };
+
+        let generating = shared.phase_limits.generating();
+        let current_loader = rustdoc_gen::StatefulRustdocGenerator::couple_data(
+            &shared.current_generator,
+            config,
+            &current_crate_data,
+        )
+        .map_err(|err| log_terminal_error(config, err))?;
+        let baseline_loader = rustdoc_gen::StatefulRustdocGenerator::couple_data(
+            baseline_generator,
+            config,
+            &baseline_crate_data,
+        )
+        .map_err(|err| log_terminal_error(config, err))?;
+
+        let current_loader = current_loader
+            .prepare_generator(config)
+            .map_err(|err| log_terminal_error(config, err))?;
+        let baseline_loader = baseline_loader
+            .prepare_generator(config)
+            .map_err(|err| log_terminal_error(config, err))?;
+
+        let witness_data = witness_gen::WitnessGenerationData::new(
+            baseline_loader.get_data_request(),
+            current_loader.get_data_request(),
+            shared.witness_target_dir.clone(),
+        );
+
+        let mut timings = timings::CrateTimings::new();
+        let data_storage = generate_crate_data(
+            config,
+            shared.generation_settings.clone(),
+            &current_loader,
+            &baseline_loader,
+            &mut timings,
+        )
+        .map_err(|err| log_terminal_error(config, err))?;
+        drop(generating);
+
+        let linting = shared.phase_limits.linting();
+        let report = run_check_release(
+            config,
+            &data_storage,
+            name,
+            CheckReleaseSettings {
+                release_type: self.release_type,
+                prerelease_policy: self.prerelease_policy,
+                versioning_policy: selected.versioning_policy,
+                rustdoc_indexing_mode: self.rustdoc_indexing_mode,
+                skip_unchanged_api: !self.check_unchanged_api,
+                reported_configuration: several_configurations.then_some(description.as_str()),
+                query_timeout: self.query_timeout,
+                cancellation: self.cancellation.as_ref(),
+            },
+            &selected.overrides,
+            &self.witness_generation,
+            witness_data,
+            timings,
+        )?;
+        drop(linting);
+
+        let mut feature_gating_result = None;
+        if check_feature_gating {
+            let _generating = shared.phase_limits.generating();
+            let current_crate_data = CrateDataForRustdoc {
+                build_target: *build_target,
+                ..selected.current_crate_data.clone()
+            };
+            let baseline_crate_data = CrateDataForRustdoc {
+                build_target: *build_target,
+                ..(*selected_baseline_data).clone()
+            };
+            let feature_gating_check = feature_gating::FeatureGatingCheck {
+                current_generator: &shared.current_generator,
+                baseline_generator,
+                current_crate_data: &current_crate_data,
+                baseline_crate_data: &baseline_crate_data,
+                candidate_features: &selected.gating_features,
+                overrides: &selected.overrides,
+                detected_bump: report.report.detected_bump,
+                rustdoc_indexing_mode: self.rustdoc_indexing_mode,
+            };
+            let mut timings = timings::CrateTimings::new();
+            feature_gating_result = feature_gating_check
+                .run(
+                    config,
+                    shared.generation_settings.clone(),
+                    data_storage.current_crate(),
+                    &mut timings,
+                )?
+                .map(|lint_result| (lint_result, timings));
+        }
+        Ok((report, feature_gating_result))
     }
 }
 
@@ -733,10 +1820,73 @@ fn overrides_for_workspace_package(
     Ok(overrides)
 }
 
+/// Run `check_crate` on each crate, using up to `workers` threads.
+///
+/// Each crate's output is captured while it's being checked, then printed
+/// once that crate and all crates before it are done. This keeps the output
+/// grouped by crate and in the same order as checking crates one at a time.
+fn check_crates_concurrently<T: Send, R: Send>(
+    config: &mut GlobalConfig,
+    workers: usize,
+    crates: Vec<T>,
+    check_crate: impl Fn(&mut GlobalConfig, T) -> anyhow::Result<R> + Sync,
+) -> anyhow::Result<Vec<anyhow::Result<R>>> {
+    let total = crates.len();
+    let queue = std::sync::Mutex::new(crates.into_iter().enumerate());
+    let worker_config = config.worker_config();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers.min(total) {
+            let sender = sender.clone();
+            let queue = &queue;
+            let worker_config = &worker_config;
+            let check_crate = &check_crate;
+            scope.spawn(move || {
+                loop {
+                    let next = queue.lock().expect("crate queue lock poisoned").next();
+                    let Some((index, krate)) = next else {
+                        break;
+                    };
+
+                    let stdout = crate::config::CapturedOutput::new();
+                    let stderr = crate::config::CapturedOutput::new();
+                    let mut config = worker_config
+                        .clone()
+                        .into_captured(stdout.clone(), stderr.clone());
+                    let outcome = check_crate(&mut config, krate);
+                    drop(config);
+
+                    if sender
+                        .send((index, outcome, stdout.take(), stderr.take()))
+                        .is_err()
+                    {
+                        // The receiving end gave up, so there's no point in continuing.
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut finished = BTreeMap::new();
+        let mut outcomes = Vec::with_capacity(total);
+        for (index, outcome, stdout, stderr) in receiver {
+            finished.insert(index, (outcome, stdout, stderr));
+            while let Some((outcome, stdout, stderr)) = finished.remove(&outcomes.len()) {
+                config.stderr().write_all(&stderr)?;
+                config.stdout().write_all(&stdout)?;
+                outcomes.push(outcome);
+            }
+        }
+        Ok(outcomes)
+    })
+}
+
 #[cold]
 fn log_terminal_error(config: &mut GlobalConfig, err: TerminalError) -> anyhow::Error {
     match err {
-        TerminalError::WithAdvice(err, advice) => {
+        TerminalError::WithAdvice(err, advice) | TerminalError::BuildFailure(_, err, advice) => {
             if let Err(err) = config.log_error(|config| {
                 writeln!(config.stderr(), "{advice}")?;
                 Ok(())
@@ -791,6 +1941,10 @@ pub struct CrateReport {
     skipped_checks: usize,
     /// Witness statistics produced while evaluating this crate, if any.
     witness_statistics: Option<WitnessStatistics>,
+    /// How long each phase of checking this crate took.
+    timings: timings::CrateTimings,
+    /// Whether all lints were skipped because the public API is identical to the baseline.
+    api_unchanged: bool,
 }
 
 impl CrateReport {
@@ -847,6 +2001,12 @@ impl CrateReport {
         self.detected_bump
     }
 
+    /// Whether the check was skipped because the crate's public API
+    /// is identical to the baseline's, so no lints could possibly fire.
+    pub fn api_unchanged(&self) -> bool {
+        self.api_unchanged
+    }
+
     /// Additional witness-related statistics for this crate, if any were produced.
     pub fn witness_statistics(&self) -> Option<&WitnessStatistics> {
         self.witness_statistics.as_ref()
@@ -935,6 +2095,16 @@ impl WitnessStatistics {
         }
     }
 
+    /// Add up the statistics from checking a crate on several build targets.
+    pub(crate) fn combined_with(&self, other: &Self) -> Self {
+        Self::new(
+            self.not_confirmed_by_witness + other.not_confirmed_by_witness,
+            self.consistency_check_mismatches + other.consistency_check_mismatches,
+            self.consistency_check_errors + other.consistency_check_errors,
+            self.required_witness_errors + other.required_witness_errors,
+        )
+    }
+
     pub(crate) fn is_empty(&self) -> bool {
         self.not_confirmed_by_witness == 0
             && self.consistency_check_mismatches == 0
@@ -973,57 +2143,232 @@ fn generate_crate_data(
     generation_settings: data_generation::GenerationSettings,
     current_loader: &rustdoc_gen::StatefulRustdocGenerator<'_, rustdoc_gen::ReadyState<'_>>,
     baseline_loader: &rustdoc_gen::StatefulRustdocGenerator<'_, rustdoc_gen::ReadyState<'_>>,
+    timings: &mut timings::CrateTimings,
 ) -> Result<DataStorage, TerminalError> {
-    let current_crate = current_loader.load_rustdoc(
+    let mut current_crate = current_loader.load_rustdoc(
         config,
-        generation_settings,
+        generation_settings.clone(),
         data_generation::CacheSettings::ReadWrite(()),
+        timings,
     )?;
+    let mut baseline_crate = baseline_loader.load_rustdoc(
+        config,
+        generation_settings.clone(),
+        data_generation::CacheSettings::ReadWrite(()),
+        timings,
+    )?;
+
+    let crate_name = &baseline_loader.get_crate_data().name;
+    let current_toolchain = current_loader.get_crate_data().toolchain;
+    let baseline_toolchain = baseline_loader.get_crate_data().toolchain;
 
-    let baseline_crate_name = &baseline_loader.get_crate_data().name;
-    let current_rustdoc_version = current_crate.version();
+    // Rustdoc generated with different toolchains may use different format versions,
+    // and regenerating it with the same toolchains won't change that.
+    if current_toolchain != baseline_toolchain
+        && baseline_crate.storage.version() != current_crate.storage.version()
+    {
+        let describe = |toolchain: Option<&str>| match toolchain {
+            Some(toolchain) => format!("toolchain `{toolchain}`"),
+            None => "the default toolchain".to_string(),
+        };
+        config
+            .shell_warn(format_args!(
+                "the current rustdoc for {crate_name} was generated by {} \
+                 in format v{}, but the baseline rustdoc was generated by {} in format v{}",
+                describe(current_toolchain),
+                current_crate.storage.version(),
+                describe(baseline_toolchain),
+                baseline_crate.storage.version(),
+            ))
+            .into_terminal_result()?;
+        return Err(rustdoc_format_mismatch_error(
+            current_crate.storage.version(),
+            baseline_crate.storage.version(),
+            "note: use toolchains that emit the same rustdoc JSON format version, \
+             for example by passing the same `--toolchain` for both crates\n",
+        ));
+    }
 
-    let baseline_crate = {
-        let mut baseline_crate = baseline_loader.load_rustdoc(
+    // Either side's rustdoc JSON may have been cached, or archived from an older toolchain.
+    // Regenerate the sides we have the sources for with the installed toolchain,
+    // so that both sides use the same format version.
+    //
+    // Fix for: https://github.com/obi1kenobi/cargo-semver-checks/issues/415
+    let order = regeneration_order(
+        (
+            current_crate.storage.version(),
+            current_loader.get_data_request().is_some(),
+        ),
+        (
+            baseline_crate.storage.version(),
+            baseline_loader.get_data_request().is_some(),
+        ),
+    );
+    for side in order {
+        if baseline_crate.storage.version() == current_crate.storage.version() {
+            break;
+        }
+        let (loader, loaded_crate, kind) = match side {
+            CrateSide::Current => (current_loader, &mut current_crate, "current"),
+            CrateSide::Baseline => (baseline_loader, &mut baseline_crate, "baseline"),
+        };
+        config
+            .shell_status(
+                "Regenerating",
+                format_args!(
+                    "{kind} rustdoc for {crate_name} in format v{}",
+                    loaded_crate.storage.version()
+                ),
+            )
+            .into_terminal_result()?;
+        *loaded_crate = loader.load_rustdoc(
             config,
-            generation_settings,
-            data_generation::CacheSettings::ReadWrite(()),
+            generation_settings.clone(),
+            data_generation::CacheSettings::WriteOnly(()),
+            timings,
         )?;
+    }
 
-        // The baseline rustdoc JSON may have been cached; ensure its rustdoc version matches
-        // the version emitted by the currently-installed toolchain.
-        //
-        // The baseline and current rustdoc JSONs should have the same version.
-        // If the baseline rustdoc version doesn't match, delete the cached baseline and rebuild it.
-        //
-        // Fix for: https://github.com/obi1kenobi/cargo-semver-checks/issues/415
-        if baseline_crate.version() != current_rustdoc_version {
-            let crate_name = baseline_crate_name;
-            config
-                .shell_status(
-                    "Removing",
-                    format_args!("stale cached baseline rustdoc for {crate_name}"),
-                )
-                .into_terminal_result()?;
+    if baseline_crate.storage.version() != current_crate.storage.version() {
+        let advice = format_mismatch_advice(
+            current_loader.rustdoc_file(),
+            baseline_loader.rustdoc_file(),
+            current_crate.storage.version(),
+            baseline_crate.storage.version(),
+        );
+        return Err(rustdoc_format_mismatch_error(
+            current_crate.storage.version(),
+            baseline_crate.storage.version(),
+            &advice,
+        ));
+    }
 
-            baseline_crate = baseline_loader.load_rustdoc(
-                config,
-                generation_settings,
-                data_generation::CacheSettings::WriteOnly(()),
-            )?;
+    check_build_flags_match(config, crate_name, &current_crate, &baseline_crate)?;
 
-            assert_eq!(
-                baseline_crate.version(),
-                current_rustdoc_version,
-                "Deleting and regenerating the baseline JSON file did not resolve the rustdoc \
-                 version mismatch."
-            );
-        }
+    Ok(DataStorage::new(current_crate, baseline_crate))
+}
 
-        baseline_crate
+/// Items only built for one target, or gated on a `--cfg` value or target feature
+/// set for only one of the two builds, would look added or removed.
+fn check_build_flags_match(
+    config: &mut GlobalConfig,
+    crate_name: &str,
+    current_crate: &LoadedRustdoc,
+    baseline_crate: &LoadedRustdoc,
+) -> Result<(), TerminalError> {
+    let (Some(current), Some(baseline)) = (&current_crate.build_flags, &baseline_crate.build_flags)
+    else {
+        // Rustdoc loaded from a file only says how it was built
+        // if there's a build flags file next to it.
+        return Ok(());
     };
 
-    Ok(DataStorage::new(current_crate, baseline_crate))
+    if current.target_triple != baseline.target_triple {
+        return Err(TerminalError::WithAdvice(
+            anyhow::anyhow!(
+                "current rustdoc for {crate_name} was built for target `{}`, \
+                 but baseline rustdoc for target `{}`",
+                current.target_triple,
+                baseline.target_triple,
+            ),
+            "note: items that only exist on one of the targets would be reported \
+             as added or removed;\n      \
+             build both with the same `--target` and cargo `build.target` config\n"
+                .to_string(),
+        ));
+    }
+
+    if let Some(differences) = current.differences(baseline) {
+        config
+            .shell_warn(format_args!(
+                "current and baseline rustdoc for {crate_name} were built with different flags:\n\
+                 {differences}\
+                 note: items gated on these flags may be reported as added or removed;\n      \
+                 use the same RUSTFLAGS and RUSTDOCFLAGS for both"
+            ))
+            .into_terminal_result()?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrateSide {
+    Current,
+    Baseline,
+}
+
+/// The order in which to regenerate the current and baseline rustdoc to resolve
+/// a format version mismatch, given each side's format version and whether
+/// we have the sources to regenerate it from.
+///
+/// Rustdoc loaded from a file can't be regenerated, so then the other side is
+/// the only option, whichever format is older. If both sides can be regenerated,
+/// the older format is tried first: it's the one more likely to come from a stale cache.
+fn regeneration_order(current: (u32, bool), baseline: (u32, bool)) -> Vec<CrateSide> {
+    let mut sides = vec![
+        (CrateSide::Current, current),
+        (CrateSide::Baseline, baseline),
+    ];
+    sides.retain(|(_, (_, has_sources))| *has_sources);
+    sides.sort_by_key(|(_, (version, _))| *version);
+    sides.into_iter().map(|(side, _)| side).collect()
+}
+
+/// Explains why rustdoc format versions still mismatch after regenerating what we could.
+fn format_mismatch_advice(
+    current_file: Option<&Path>,
+    baseline_file: Option<&Path>,
+    current_version: u32,
+    baseline_version: u32,
+) -> String {
+    let mut advice = String::new();
+    for (path, kind, flag, other_version) in [
+        (
+            current_file,
+            "current",
+            "--current-rustdoc",
+            baseline_version,
+        ),
+        (
+            baseline_file,
+            "baseline",
+            "--baseline-rustdoc",
+            current_version,
+        ),
+    ] {
+        if let Some(path) = path {
+            writeln!(
+                advice,
+                "note: the {kind} rustdoc was loaded from `{}` via `{flag}`, \
+                 so it can't be regenerated automatically;\n      \
+                 regenerate it with a toolchain that emits format v{other_version}, \
+                 or check the {kind} crate's sources instead",
+                path.display(),
+            )
+            .expect("formatting failed");
+        }
+    }
+    if advice.is_empty() {
+        advice.push_str(
+            "note: regenerating the rustdoc did not resolve the mismatch; \
+             this is probably a bug in cargo-semver-checks\n",
+        );
+    }
+    advice
+}
+
+fn rustdoc_format_mismatch_error(
+    current_version: u32,
+    baseline_version: u32,
+    advice: &str,
+) -> TerminalError {
+    TerminalError::WithAdvice(
+        anyhow::anyhow!(
+            "current rustdoc JSON format v{current_version} cannot be compared \
+             with baseline rustdoc JSON format v{baseline_version}",
+        ),
+        advice.to_string(),
+    )
 }
 
 fn manifest_path(project_root: &Path) -> anyhow::Result<PathBuf> {
@@ -1053,20 +2398,138 @@ fn manifest_path(project_root: &Path) -> anyhow::Result<PathBuf> {
     }
 }
 
-fn manifest_metadata(project_root: &Path) -> anyhow::Result<cargo_metadata::Metadata> {
-    let manifest_path = manifest_path(project_root)?;
-    let mut command = cargo_metadata::MetadataCommand::new();
-    let metadata = command.manifest_path(manifest_path).exec()?;
+/// Unlike [`manifest_metadata_no_deps`], this resolves dependencies,
+/// so it must not touch the network when `offline`.
+fn manifest_metadata(
+    project_root: &Path,
+    toolchain: Option<&str>,
+    offline: bool,
+) -> anyhow::Result<cargo_metadata::Metadata> {
+    let mut command = metadata_command(project_root, toolchain)?;
+    if offline {
+        command.other_options(vec!["--offline".to_string()]);
+    }
+    let metadata = command.exec()?;
     Ok(metadata)
 }
 
-fn manifest_metadata_no_deps(project_root: &Path) -> anyhow::Result<cargo_metadata::Metadata> {
-    let manifest_path = manifest_path(project_root)?;
-    let mut command = cargo_metadata::MetadataCommand::new();
-    let metadata = command.manifest_path(manifest_path).no_deps().exec()?;
+fn manifest_metadata_no_deps(
+    project_root: &Path,
+    toolchain: Option<&str>,
+) -> anyhow::Result<cargo_metadata::Metadata> {
+    let metadata = metadata_command(project_root, toolchain)?
+        .no_deps()
+        .exec()?;
     Ok(metadata)
 }
 
+/// `cargo metadata` for the project, run with the toolchain its rustdoc is generated with,
+/// so a toolchain pinned by the project doesn't override the selected one.
+fn metadata_command(
+    project_root: &Path,
+    toolchain: Option<&str>,
+) -> anyhow::Result<cargo_metadata::MetadataCommand> {
+    let mut command = cargo_metadata::MetadataCommand::new();
+    command.manifest_path(manifest_path(project_root)?);
+    if let Some(toolchain) = toolchain {
+        command.env("RUSTUP_TOOLCHAIN", toolchain);
+    }
+    Ok(command)
+}
+
+/// The features of a package that aren't its `default` feature.
+fn non_default_features(package: &cargo_metadata::Package) -> Vec<String> {
+    package
+        .features
+        .keys()
+        .filter(|feature| *feature != "default")
+        .cloned()
+        .collect()
+}
+
+/// Describe a configuration a crate is checked in, to tell it apart from others:
+/// its baseline and build target, if several are checked,
+/// and its extra features, if there's a matrix.
+fn describe_configuration(
+    baseline: Option<&str>,
+    build_target: Option<&str>,
+    extra_features: Option<&[String]>,
+) -> String {
+    let features = extra_features.map(|features| {
+        if features.is_empty() {
+            "no extra features".to_string()
+        } else {
+            format!("features {}", features.join(","))
+        }
+    });
+    baseline
+        .into_iter()
+        .chain(build_target)
+        .map(str::to_string)
+        .chain(features)
+        .join(", ")
+}
+
+/// Describe a baseline, to tell it apart from the others it's checked against.
+fn describe_baseline(baseline: &Rustdoc) -> String {
+    match &baseline.source {
+        RustdocSource::VersionFromRegistry(Some(version)) => format!("baseline v{version}"),
+        RustdocSource::VersionFromRegistry(None) => "latest baseline".to_string(),
+        RustdocSource::Revision(_, rev) => format!("baseline {rev}"),
+        RustdocSource::Rustdoc(path)
+        | RustdocSource::Root(path)
+        | RustdocSource::CrateFile(path) => format!("baseline {}", path.display()),
+    }
+}
+
+/// The targets docs.rs builds documentation for, according to a package's
+/// `[package.metadata.docs.rs]` table: its `default-target` followed by its `targets`.
+/// Empty if neither is set, in which case the default target is checked.
+fn docs_rs_build_targets(package_metadata: &serde_json::Value) -> Vec<String> {
+    let docs_rs = &package_metadata["docs"]["rs"];
+    docs_rs["default-target"]
+        .as_str()
+        .into_iter()
+        .chain(
+            docs_rs["targets"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(serde_json::Value::as_str),
+        )
+        .map(str::to_string)
+        .unique()
+        .collect()
+}
+
+/// Determine the version of rustc in the given rustup toolchain, or the default one if unset.
+fn rustc_version_for_toolchain(toolchain: Option<&str>) -> rustc_version::Result<semver::Version> {
+    let Some(toolchain) = toolchain else {
+        return rustc_version::version();
+    };
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let mut command = std::process::Command::new(rustc);
+    command.env("RUSTUP_TOOLCHAIN", toolchain);
+    rustc_version::VersionMeta::for_command(command).map(|meta| meta.semver)
+}
+
+/// Whether cargo is configured to run offline, through `CARGO_NET_OFFLINE` or `net.offline`
+/// in the configuration that applies in `config_dir`, or in the working directory if unset.
+fn cargo_net_offline_configured(config_dir: Option<&Path>) -> bool {
+    if let Ok(value) = std::env::var("CARGO_NET_OFFLINE") {
+        return value == "true" || value == "1";
+    }
+    let config = match config_dir {
+        Some(dir) => cargo_config2::Config::load_with_cwd(dir),
+        None => cargo_config2::Config::load(),
+    };
+    // If cargo config fails to load, cargo itself will report the problem shortly.
+    config
+        .ok()
+        .and_then(|config| config.net.offline)
+        .unwrap_or(false)
+}
+
 fn get_cache_dir() -> anyhow::Result<PathBuf> {
     let project_dirs =
         ProjectDirs::from("", "", "cargo-semver-checks").context("can't determine project dirs")?;
@@ -1075,20 +2538,242 @@ fn get_cache_dir() -> anyhow::Result<PathBuf> {
     Ok(cache_dir.to_path_buf())
 }
 
-fn get_target_dir_from_project_root(source: &RustdocSource) -> anyhow::Result<Option<PathBuf>> {
+fn get_target_dir_from_project_root(
+    source: &RustdocSource,
+    toolchain: Option<&str>,
+) -> anyhow::Result<Option<PathBuf>> {
     Ok(match source {
         RustdocSource::Root(root) => {
-            let metadata = manifest_metadata_no_deps(root)?;
+            let metadata = manifest_metadata_no_deps(root, toolchain)?;
             let target = metadata.target_directory.as_std_path().join(util::SCOPE);
             Some(target)
         }
         RustdocSource::Revision(root, rev) => {
-            let metadata = manifest_metadata_no_deps(root)?;
+            let metadata = manifest_metadata_no_deps(root, toolchain)?;
             let target = metadata.target_directory.as_std_path().join(util::SCOPE);
             let target = target.join(format!("git-{}", util::slugify(rev)));
             Some(target)
         }
         RustdocSource::Rustdoc(_path) => None,
+        RustdocSource::CrateFile(_path) => None,
         RustdocSource::VersionFromRegistry(_version) => None,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+    use std::time::Duration;
+
+    use crate::GlobalConfig;
+    use crate::config::CapturedOutput;
+
+    #[test]
+    fn docs_rs_targets_start_with_the_default_target() {
+        let metadata = serde_json::json!({
+            "docs": {
+                "rs": {
+                    "default-target": "x86_64-unknown-linux-gnu",
+                    "targets": ["x86_64-pc-windows-msvc", "x86_64-unknown-linux-gnu"],
+                }
+            }
+        });
+        assert_eq!(
+            super::docs_rs_build_targets(&metadata),
+            ["x86_64-unknown-linux-gnu", "x86_64-pc-windows-msvc"],
+        );
+        assert!(super::docs_rs_build_targets(&serde_json::Value::Null).is_empty());
+    }
+
+    #[test]
+    fn offline_mode_is_configured_by_the_current_project() {
+        if std::env::var_os("CARGO_NET_OFFLINE").is_some() {
+            // The environment takes precedence over the project's configuration.
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-net-offline-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        fs_err::create_dir_all(dir.join(".cargo")).expect("failed to create temp dir");
+        fs_err::write(
+            dir.join("Cargo.toml"),
+            "[package]\nname = \"demo\"\nversion = \"1.0.0\"\n",
+        )
+        .expect("failed to write manifest");
+        // The project's configuration applies, even though the tests run in another directory.
+        fs_err::write(dir.join(".cargo/config.toml"), "[net]\noffline = true\n")
+            .expect("failed to write cargo config");
+
+        assert!(crate::Check::new(crate::Rustdoc::from_root(&dir)).is_offline());
+        assert!(crate::Check::new(crate::Rustdoc::from_root(dir.join("Cargo.toml"))).is_offline());
+
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn build_targets_serialize_like_a_single_build_target() {
+        let serialized_targets = |build_targets: &[&str]| {
+            let mut check = crate::Check::new(crate::Rustdoc::from_root("."));
+            check.set_build_targets(build_targets.iter().copied());
+            serde_json::to_value(&check).unwrap()["build_target"].clone()
+        };
+        assert_eq!(serialized_targets(&[]), serde_json::Value::Null);
+        assert_eq!(
+            serialized_targets(&["x86_64-unknown-linux-gnu"]),
+            serde_json::json!("x86_64-unknown-linux-gnu"),
+        );
+        assert_eq!(
+            serialized_targets(&["x86_64-unknown-linux-gnu", "wasm32-unknown-unknown"]),
+            serde_json::json!(["x86_64-unknown-linux-gnu", "wasm32-unknown-unknown"]),
+        );
+    }
+
+    #[test]
+    fn feature_matrix_lists_feature_sets_to_check() {
+        use std::num::NonZeroUsize;
+
+        use crate::FeatureMatrix;
+
+        let features = ["a", "b", "c"].map(String::from);
+        let sets = |matrix: FeatureMatrix| -> Vec<String> {
+            matrix
+                .feature_sets(&features)
+                .iter()
+                .map(|set| set.join(","))
+                .collect()
+        };
+
+        assert_eq!(sets(FeatureMatrix::EachFeature), ["", "a", "b", "c"]);
+        assert_eq!(
+            sets(FeatureMatrix::Powerset { depth: None }),
+            ["", "a", "b", "c", "a,b", "a,c", "b,c", "a,b,c"]
+        );
+        assert_eq!(
+            sets(FeatureMatrix::Powerset {
+                depth: NonZeroUsize::new(2)
+            }),
+            ["", "a", "b", "c", "a,b", "a,c", "b,c"]
+        );
+    }
+
+    #[test]
+    fn feature_powersets_without_depth_are_capped() {
+        use std::num::NonZeroUsize;
+
+        use crate::{FeatureMatrix, MAX_POWERSET_FEATURE_SETS};
+
+        let powerset = FeatureMatrix::Powerset { depth: None };
+        // All 2^8 combinations fit.
+        assert_eq!(powerset.capped(8), powerset);
+        // The 1 + 9 + 36 + 84 + 126 sets of at most 4 features fit, the 126 of 5 don't.
+        assert_eq!(
+            powerset.capped(9),
+            FeatureMatrix::Powerset {
+                depth: NonZeroUsize::new(4)
+            },
+        );
+        let features: Vec<String> = (0..40).map(|n| format!("f{n}")).collect();
+        let capped = powerset.capped(features.len());
+        assert_eq!(
+            capped,
+            FeatureMatrix::Powerset {
+                depth: NonZeroUsize::new(1)
+            },
+        );
+        assert_eq!(capped.feature_sets(&features).len(), 41);
+        // Explicit depths and single features are left alone.
+        let deep = FeatureMatrix::Powerset {
+            depth: NonZeroUsize::new(5),
+        };
+        assert_eq!(deep.capped(40), deep);
+        assert_eq!(
+            FeatureMatrix::EachFeature.capped(400),
+            FeatureMatrix::EachFeature
+        );
+        assert!(
+            powerset.capped(12).feature_sets(&features[..12]).len() <= MAX_POWERSET_FEATURE_SETS
+        );
+    }
+
+    #[test]
+    fn format_mismatches_regenerate_the_sides_with_sources() {
+        use super::CrateSide::{Baseline, Current};
+        use super::regeneration_order;
+
+        // Rustdoc loaded from a file can't be regenerated, even if its format is the older one.
+        assert_eq!(regeneration_order((57, false), (60, true)), [Baseline]);
+        assert_eq!(regeneration_order((60, true), (57, false)), [Current]);
+        // With sources for both sides, the older format is regenerated first.
+        assert_eq!(
+            regeneration_order((60, true), (57, true)),
+            [Baseline, Current]
+        );
+        assert_eq!(
+            regeneration_order((57, true), (60, true)),
+            [Current, Baseline]
+        );
+        assert!(regeneration_order((57, false), (60, false)).is_empty());
+    }
+
+    #[test]
+    fn format_mismatch_errors_explain_what_cannot_be_regenerated() {
+        use std::path::Path;
+
+        use super::{TerminalError, format_mismatch_advice, rustdoc_format_mismatch_error};
+
+        let advice = format_mismatch_advice(Some(Path::new("current.json")), None, 57, 60);
+        assert_eq!(
+            advice,
+            "note: the current rustdoc was loaded from `current.json` via `--current-rustdoc`, \
+             so it can't be regenerated automatically;\n      \
+             regenerate it with a toolchain that emits format v60, \
+             or check the current crate's sources instead\n",
+        );
+        let advice = format_mismatch_advice(None, None, 57, 60);
+        assert!(advice.contains("probably a bug"), "{advice}");
+
+        let TerminalError::WithAdvice(error, error_advice) =
+            rustdoc_format_mismatch_error(57, 60, &advice)
+        else {
+            panic!("format mismatches should come with advice");
+        };
+        assert_eq!(
+            error.to_string(),
+            "current rustdoc JSON format v57 cannot be compared with baseline rustdoc JSON format v60",
+        );
+        assert_eq!(error_advice, advice);
+    }
+
+    #[test]
+    fn concurrent_checks_print_output_in_crate_order() {
+        let out = CapturedOutput::new();
+        let err = CapturedOutput::new();
+        let mut config = GlobalConfig::new();
+        config.set_stdout(Box::new(out.clone()));
+        config.set_stderr(Box::new(err.clone()));
+        config.set_color_choice(false);
+
+        // Earlier crates take longer, so they finish last.
+        let crates: Vec<u64> = (0..6).collect();
+        let outcomes = super::check_crates_concurrently(&mut config, 3, crates, |config, n| {
+            std::thread::sleep(Duration::from_millis(10 * (6 - n)));
+            writeln!(config.stderr(), "checking {n}")?;
+            writeln!(config.stdout(), "report {n}")?;
+            if n == 4 {
+                anyhow::bail!("crate {n} failed");
+            }
+            Ok(n)
+        })
+        .expect("failed to print output");
+
+        let results: Vec<_> = outcomes.iter().map(|o| o.as_ref().ok().copied()).collect();
+        assert_eq!(results, [Some(0), Some(1), Some(2), Some(3), None, Some(5)]);
+
+        let expected_err: String = (0..6).map(|n| format!("checking {n}\n")).collect();
+        let expected_out: String = (0..6).map(|n| format!("report {n}\n")).collect();
+        assert_eq!(String::from_utf8(err.take()).unwrap(), expected_err);
+        assert_eq!(String::from_utf8(out.take()).unwrap(), expected_out);
+    }
+}
@@ -1,13 +1,22 @@
 #![deny(unsafe_code)]
 
-use std::{collections::HashSet, env, ffi::OsStr, io::Write as _, path::PathBuf};
+use std::{
+    collections::HashSet,
+    env,
+    ffi::OsStr,
+    io::Write as _,
+    num::NonZeroUsize,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 
 use anstream::ColorChoice as AnstreamChoice;
 use anstyle::{AnsiColor, Color, Reset, Style};
 use cargo_config2::Config;
 use cargo_semver_checks::{
-    FeatureFlag, GlobalConfig, PackageSelection, ReleaseType, Rustdoc, RustdocIndexingMode,
-    ScopeSelection, SemverQuery, WitnessGeneration,
+    DependencyLockfile, FeatureFlag, FeatureMatrix, GlobalConfig, PackageSelection,
+    PrereleasePolicy, PrunePolicy, ReleaseType, Rustdoc, RustdocCache, RustdocIndexingMode,
+    ScopeSelection, SemverQuery, TimingsFormat, VersioningPolicy, WitnessGeneration,
 };
 use clap::{Args, CommandFactory, Parser, Subcommand};
 
@@ -193,6 +202,18 @@ fn main() {
 
     let check_release = match args.command {
         Some(SemverChecksCommands::CheckRelease(c)) => c,
+        Some(SemverChecksCommands::Cache(c)) => {
+            exit_on_error(true, || run_cache_command(&mut config, &c));
+            std::process::exit(0);
+        }
+        Some(SemverChecksCommands::Bisect(b)) => {
+            exit_on_error(true, || run_bisect_command(&mut config, &b));
+            std::process::exit(0);
+        }
+        Some(SemverChecksCommands::Bugreport(b)) => {
+            exit_on_error(true, || run_bugreport_command(&mut config, b));
+            std::process::exit(0);
+        }
         None => args.check_release,
     };
 
@@ -212,7 +233,7 @@ fn check_exit_code(report: &cargo_semver_checks::Report) -> i32 {
     }
 }
 
-fn exit_on_error<T>(log_errors: bool, mut inner: impl FnMut() -> anyhow::Result<T>) -> T {
+fn exit_on_error<T>(log_errors: bool, inner: impl FnOnce() -> anyhow::Result<T>) -> T {
     match inner() {
         Ok(x) => x,
         Err(err) => {
@@ -224,6 +245,237 @@ fn exit_on_error<T>(log_errors: bool, mut inner: impl FnMut() -> anyhow::Result<
     }
 }
 
+fn run_bugreport_command(
+    config: &mut GlobalConfig,
+    command: BugreportCommand,
+) -> anyhow::Result<()> {
+    let BugreportCommand {
+        bundle,
+        include_rustdoc,
+        check_release,
+    } = command;
+    let Some(bundle) = bundle else {
+        print_issue_url(config);
+        return Ok(());
+    };
+    let check: cargo_semver_checks::Check = check_release.into();
+    check.write_bug_report_bundle(config, &bundle, include_rustdoc)
+}
+
+fn run_bisect_command(config: &mut GlobalConfig, command: &BisectCommand) -> anyhow::Result<()> {
+    if command.check_release.baseline_rev.is_empty() {
+        anyhow::bail!(
+            "`bisect` needs a revision where the check passes, set with `--baseline-rev`"
+        );
+    }
+
+    let check: cargo_semver_checks::Check = command.check_release.clone().into();
+    let commit = check.bisect(config, &command.current_rev, command.lint.as_deref())?;
+    config.shell_status("Found", "the first failing commit")?;
+    writeln!(config.stdout(), "{} {}", commit.id(), commit.summary())?;
+    Ok(())
+}
+
+fn run_cache_command(config: &mut GlobalConfig, command: &CacheCommand) -> anyhow::Result<()> {
+    let cache = match &command.cache_dir {
+        Some(dir) => RustdocCache::new(dir),
+        None => RustdocCache::user_cache()?,
+    };
+
+    match &command.action {
+        CacheAction::List => {
+            let entries = cache.entries()?;
+            let now = SystemTime::now();
+            let mut rows = vec![[
+                "crate".to_owned(),
+                "version".to_owned(),
+                "target".to_owned(),
+                "size".to_owned(),
+                "age".to_owned(),
+            ]];
+            for entry in &entries {
+                rows.push([
+                    entry.crate_name().to_owned(),
+                    entry.version().to_owned(),
+                    entry.target().to_owned(),
+                    format_size(entry.size()),
+                    format_age(now.duration_since(entry.modified()).unwrap_or_default()),
+                ]);
+            }
+            let mut widths = [0; 5];
+            for row in &rows {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.len());
+                }
+            }
+            let mut stdout = config.stdout();
+            for row in &rows {
+                writeln!(
+                    stdout,
+                    "{0:<1$} {2:<3$} {4:<5$} {6:>7$} {8:>9$}",
+                    row[0],
+                    widths[0],
+                    row[1],
+                    widths[1],
+                    row[2],
+                    widths[2],
+                    row[3],
+                    widths[3],
+                    row[4],
+                    widths[4],
+                )?;
+            }
+            drop(stdout);
+
+            config.shell_note(format_args!(
+                "{} entries in {}",
+                entries.len(),
+                cache.dir().display()
+            ))
+        }
+        CacheAction::Size => {
+            let entries = cache.entries()?;
+            let size = entries.iter().map(|entry| entry.size()).sum();
+            writeln!(config.stdout(), "{}", format_size(size))?;
+            config.shell_note(format_args!(
+                "{} entries in {}",
+                entries.len(),
+                cache.dir().display()
+            ))
+        }
+        CacheAction::Prune(prune) => {
+            let mut policy = PrunePolicy::new();
+            if let Some(max_size) = prune.max_size {
+                policy.set_max_size(max_size);
+            }
+            if let Some(max_age) = prune.max_age {
+                policy.set_max_age(max_age);
+            }
+            if let Some(keep_versions) = prune.keep_versions {
+                policy.set_keep_versions(keep_versions);
+            }
+
+            let removed = cache.prune(&policy)?;
+            config.log_verbose(|config| {
+                for entry in &removed {
+                    config.shell_status("Removing", entry.slug())?;
+                }
+                Ok(())
+            })?;
+            config.shell_status(
+                "Pruned",
+                format_args!(
+                    "{} entries, {} freed",
+                    removed.len(),
+                    format_size(removed.iter().map(|entry| entry.size()).sum()),
+                ),
+            )
+        }
+        CacheAction::Clear => {
+            let freed = cache.clear()?;
+            config.shell_status(
+                "Cleared",
+                format_args!("{}, {} freed", cache.dir().display(), format_size(freed)),
+            )
+        }
+    }
+}
+
+/// Format a size in bytes with a binary unit suffix, like `1.5 MiB`.
+fn format_size(size: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = size as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{size} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Format an age in its largest whole unit, like `3d` or `5h`.
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    AGE_UNITS
+        .iter()
+        .rev()
+        .find(|(_, unit_secs)| secs >= *unit_secs)
+        .map_or_else(
+            || format!("{secs}s"),
+            |(suffix, unit_secs)| format!("{}{suffix}", secs / unit_secs),
+        )
+}
+
+/// Suffixes accepted for ages, and their lengths in seconds, in increasing order.
+const AGE_UNITS: [(&str, u64); 5] = [
+    ("s", 1),
+    ("m", 60),
+    ("h", 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("w", 7 * 24 * 60 * 60),
+];
+
+/// Parse a size like `500MiB`, `10G` or `1024`.
+fn parse_size(value: &str) -> Result<u64, String> {
+    let digits_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected a size like `10GiB`, found `{value}`"))?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => {
+            return Err(format!(
+                "unknown size unit `{unit}`, expected one of B, KiB, MiB, GiB, TiB"
+            ));
+        }
+    };
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("size `{value}` is too large"))
+}
+
+/// Parse an age like `30d`, `12h` or `2w`.
+fn parse_age(value: &str) -> Result<Duration, String> {
+    let digits_end = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(digits_end);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("expected an age like `30d`, found `{value}`"))?;
+    let (_, unit_secs) = AGE_UNITS
+        .iter()
+        .find(|(suffix, _)| *suffix == unit)
+        .ok_or_else(|| format!("unknown age unit `{unit}`, expected one of s, m, h, d, w"))?;
+    number
+        .checked_mul(*unit_secs)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("age `{value}` is too large"))
+}
+
+/// Parse a nonzero timeout, written like an age such as `30m` or `1h`.
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    let timeout = parse_age(value).map_err(|_| {
+        format!("expected a duration like `30m`, found `{value}`; units are s, m, h, d, w")
+    })?;
+    if timeout.is_zero() {
+        return Err("the timeout must be longer than zero".to_string());
+    }
+    Ok(timeout)
+}
+
 fn sanitize_bugreport_output(output: &str) -> String {
     output
         .split_inclusive('\n')
@@ -425,9 +677,94 @@ impl UnstableOptions {
 
 /// Check your crate for semver violations.
 #[derive(Debug, Subcommand)]
+// Parsed once per run, so the size difference between subcommands doesn't matter.
+#[allow(clippy::large_enum_variant)]
 enum SemverChecksCommands {
     #[command(alias = "diff-files")]
     CheckRelease(CheckRelease),
+
+    /// Inspect and clean up the cache of generated rustdoc JSON.
+    Cache(CacheCommand),
+
+    /// Find the first commit after `--baseline-rev` at which the check fails.
+    Bisect(BisectCommand),
+
+    /// Print system information for a bug report, or bundle everything needed
+    /// to reproduce a check into an archive.
+    Bugreport(BugreportCommand),
+}
+
+#[derive(Debug, Args)]
+struct BugreportCommand {
+    /// Run the check with the other arguments given, then write a tar archive with
+    /// its configuration, tool versions, cargo config, placeholder manifests
+    /// and commands to reproduce it
+    #[arg(long, value_name = "PATH")]
+    bundle: Option<PathBuf>,
+
+    /// Include the rustdoc JSON that was compared in the bundle, which may make it large
+    #[arg(long, requires = "bundle")]
+    include_rustdoc: bool,
+
+    #[command(flatten)]
+    check_release: CheckRelease,
+}
+
+#[derive(Debug, Args)]
+struct BisectCommand {
+    /// Git revision at which the check fails; commits up to it are searched
+    #[arg(long, value_name = "REV")]
+    current_rev: String,
+
+    /// Find the first commit at which this lint fires, instead of the first failing one
+    #[arg(long, value_name = "ID")]
+    lint: Option<String>,
+
+    #[command(flatten)]
+    check_release: CheckRelease,
+}
+
+#[derive(Debug, Args)]
+struct CacheCommand {
+    /// Cache directory to manage, such as a project's `target/semver-checks/cache`
+    /// [default: the per-user cache directory]
+    #[arg(long, value_name = "PATH", global = true)]
+    cache_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    action: CacheAction,
+}
+
+#[derive(Debug, Subcommand)]
+enum CacheAction {
+    /// List the cached rustdoc JSON entries.
+    List,
+
+    /// Print the total size of the cache.
+    Size,
+
+    /// Remove cache entries by age, total size, or number of versions per crate.
+    Prune(PruneArgs),
+
+    /// Remove everything in the cache.
+    Clear,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = true)]
+struct PruneArgs {
+    /// Remove the least recently written entries until the cache fits in this size,
+    /// like `10GiB` or `500M`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_size: Option<u64>,
+
+    /// Remove entries written longer ago than this, like `30d`, `12h` or `2w`
+    #[arg(long, value_name = "AGE", value_parser = parse_age)]
+    max_age: Option<Duration>,
+
+    /// Keep only entries for this many of the newest versions of each crate
+    #[arg(long, value_name = "N")]
+    keep_versions: Option<NonZeroUsize>,
 }
 
 #[derive(Debug, Args, Clone)]
@@ -439,6 +776,10 @@ struct CheckRelease {
     pub workspace: clap_cargo::Workspace,
 
     /// The current rustdoc json output to test for semver violations.
+    /// May be zstd-compressed.
+    ///
+    /// The target and flags it was built with may be given in a
+    /// `<JSON_PATH>.build-flags.json` file, to check that they match the other crate's.
     #[arg(
         long,
         short_alias = 'c',
@@ -456,27 +797,35 @@ struct CheckRelease {
             "baseline_version",
             "baseline_rev",
             "baseline_root",
+            "baseline_crate_file",
         ]
     )]
     current_rustdoc: Option<PathBuf>,
 
-    /// Version from registry to lookup for a baseline
+    /// Version from registry to lookup for a baseline.
+    /// Repeat to check against each of several versions
     #[arg(
         long,
         value_name = "X.Y.Z",
         help_heading = "Baseline",
         group = "baseline"
     )]
-    baseline_version: Option<String>,
+    baseline_version: Vec<String>,
 
-    /// Git revision to lookup for a baseline
+    /// Git revision to lookup for a baseline.
+    /// Repeat to check against each of several revisions
     #[arg(
         long,
         value_name = "REV",
         help_heading = "Baseline",
         group = "baseline"
     )]
-    baseline_rev: Option<String>,
+    baseline_rev: Vec<String>,
+
+    /// Check against every non-yanked registry release in the current version's
+    /// semver-compatible series, instead of only the latest one
+    #[arg(long, help_heading = "Baseline", group = "baseline")]
+    baseline_all_in_major: bool,
 
     /// Directory containing baseline crate source
     #[arg(
@@ -487,7 +836,59 @@ struct CheckRelease {
     )]
     baseline_root: Option<PathBuf>,
 
+    /// Registry to look up baseline versions in, as named in cargo config's `[registries]`
+    /// [default: crates.io]
+    #[arg(
+        long,
+        value_name = "NAME",
+        help_heading = "Baseline",
+        conflicts_with_all = [
+            "baseline_rev",
+            "baseline_root",
+            "baseline_rustdoc",
+            "baseline_crate_file",
+        ]
+    )]
+    registry: Option<String>,
+
+    /// Run without accessing the network, using only already-downloaded crates
+    /// and registry index entries
+    #[arg(long)]
+    offline: bool,
+
+    /// Resolve both crates' dependencies starting from this `Cargo.lock`,
+    /// or from the current workspace's `Cargo.lock` if no path is given
+    #[arg(
+        long,
+        value_name = "PATH",
+        num_args = 0..=1,
+        conflicts_with = "current_rustdoc"
+    )]
+    lockfile: Option<Option<PathBuf>>,
+
+    /// Abort if any `cargo` invocation generating rustdoc runs longer than this,
+    /// like `30m` or `1h`
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Abort if any lint runs longer than this on a single crate, like `30s` or `5m`
+    #[arg(long, value_name = "DURATION", value_parser = parse_timeout)]
+    query_timeout: Option<Duration>,
+
+    /// Packaged crate to use as a baseline, such as one made by `cargo package`
+    #[arg(
+        long,
+        value_name = "CRATE_PATH",
+        help_heading = "Baseline",
+        group = "baseline"
+    )]
+    baseline_crate_file: Option<PathBuf>,
+
     /// The rustdoc json file to use as a semver baseline.
+    /// May be zstd-compressed.
+    ///
+    /// The target and flags it was built with may be given in a
+    /// `<JSON_PATH>.build-flags.json` file, to check that they match the other crate's.
     #[arg(
         long,
         short_alias = 'b',
@@ -516,6 +917,16 @@ struct CheckRelease {
     )]
     release_type: Option<ReleaseType>,
 
+    /// Which changes pre-releases of the same version may make relative to each other
+    /// [default: breaking-allowed]
+    #[arg(value_enum, long, value_name = "POLICY", help_heading = "Overrides")]
+    prerelease_policy: Option<PrereleasePolicy>,
+
+    /// Which changes each kind of release may make,
+    /// instead of the policy configured in each crate's manifest [default: cargo]
+    #[arg(value_enum, long, value_name = "POLICY", help_heading = "Overrides")]
+    versioning_policy: Option<VersioningPolicy>,
+
     /// Use only the crate-defined default features, as well as any features
     /// added explicitly via other flags.
     ///
@@ -583,10 +994,105 @@ struct CheckRelease {
     )]
     all_features: bool,
 
+    /// Also check the crate with each of its features enabled on its own,
+    /// on top of the features chosen by other flags. Findings are reported together,
+    /// noting which features each one was found with.
+    #[arg(
+        long,
+        help_heading = "Features",
+        conflicts_with_all = ["feature_powerset", "current_rustdoc", "baseline_rustdoc"]
+    )]
+    each_feature: bool,
+
+    /// Also check the crate with every combination of its features,
+    /// on top of the features chosen by other flags.
+    #[arg(
+        long,
+        help_heading = "Features",
+        conflicts_with_all = ["current_rustdoc", "baseline_rustdoc"]
+    )]
+    feature_powerset: bool,
+
+    /// Only check combinations of at most N features with `--feature-powerset`.
+    /// Without it, crates with many features are checked with at most 256 feature sets.
+    #[arg(
+        long,
+        value_name = "N",
+        help_heading = "Features",
+        requires = "feature_powerset"
+    )]
+    depth: Option<NonZeroUsize>,
+
+    /// Also report public items that the crate no longer provides with its default features,
+    /// such as ones moved behind a non-default feature, naming that feature when possible.
+    #[arg(
+        long,
+        help_heading = "Features",
+        conflicts_with_all = ["current_rustdoc", "baseline_rustdoc"]
+    )]
+    check_feature_gating: bool,
+
     /// Which target to build the crate for, to check platform-specific APIs, e.g.
     /// `x86_64-unknown-linux-gnu`.
+    ///
+    /// May be given several times to check each target, with findings reported together.
     #[arg(long = "target")]
-    build_target: Option<String>,
+    build_target: Vec<String>,
+
+    /// Check each crate on the targets listed in its `[package.metadata.docs.rs]` table.
+    #[arg(long, conflicts_with_all = ["build_target", "current_rustdoc"])]
+    docs_rs_targets: bool,
+
+    /// Rustup toolchain to generate rustdoc with, as in `cargo +<TOOLCHAIN>`,
+    /// for both the current and baseline crates.
+    #[arg(long, value_name = "TOOLCHAIN")]
+    toolchain: Option<String>,
+
+    /// Rustup toolchain to generate the current crate's rustdoc with.
+    #[arg(
+        long,
+        value_name = "TOOLCHAIN",
+        help_heading = "Current",
+        conflicts_with_all = ["toolchain", "current_rustdoc"]
+    )]
+    current_toolchain: Option<String>,
+
+    /// Rustup toolchain to generate the baseline crate's rustdoc with.
+    #[arg(
+        long,
+        value_name = "TOOLCHAIN",
+        help_heading = "Baseline",
+        conflicts_with_all = ["toolchain", "baseline_rustdoc"]
+    )]
+    baseline_toolchain: Option<String>,
+
+    /// Report the time spent generating and parsing rustdoc, building indexes,
+    /// and running each lint and its witnesses.
+    ///
+    /// Prints a summary to stderr by default; `--timings=json` and `--timings=html`
+    /// write a JSON or HTML report into the target directory.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FMT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text"
+    )]
+    timings: Option<TimingsFormat>,
+
+    /// Run all lints even when the baseline and current public APIs are identical.
+    ///
+    /// By default, lints are skipped for crates whose public API didn't change.
+    #[arg(long)]
+    check_unchanged_api: bool,
+
+    /// Number of crates to check in parallel. Defaults to one at a time.
+    ///
+    /// Up to N crates generate rustdoc while up to N others run lints.
+    /// Output for each crate is still printed together and in the usual order.
+    #[arg(long, short = 'j', value_name = "N")]
+    jobs: Option<NonZeroUsize>,
 
     #[clap(flatten)]
     unstable_options: UnstableOptions,
@@ -637,10 +1143,14 @@ impl From<CheckRelease> for cargo_semver_checks::Check {
             selection.set_excluded_packages(value.workspace.exclude);
             check.set_package_selection(selection);
         }
-        let custom_baseline = {
-            if let Some(baseline_version) = value.baseline_version {
-                Some(Rustdoc::from_registry(baseline_version))
-            } else if let Some(baseline_rev) = value.baseline_rev {
+        let custom_baseline: Vec<Rustdoc> = {
+            if !value.baseline_version.is_empty() {
+                value
+                    .baseline_version
+                    .into_iter()
+                    .map(Rustdoc::from_registry)
+                    .collect()
+            } else if !value.baseline_rev.is_empty() {
                 let root = if let Some(baseline_root) = value.baseline_root {
                     lenient_baseline_root(baseline_root)
                 } else if let Some(current_root) = current_project_root {
@@ -648,9 +1158,15 @@ impl From<CheckRelease> for cargo_semver_checks::Check {
                 } else {
                     std::env::current_dir().expect("can't determine current directory")
                 };
-                Some(Rustdoc::from_git_revision(root, baseline_rev))
+                value
+                    .baseline_rev
+                    .into_iter()
+                    .map(|baseline_rev| Rustdoc::from_git_revision(&root, baseline_rev))
+                    .collect()
             } else if let Some(baseline_rustdoc) = value.baseline_rustdoc {
-                Some(Rustdoc::from_path(baseline_rustdoc))
+                vec![Rustdoc::from_path(baseline_rustdoc)]
+            } else if let Some(baseline_crate_file) = value.baseline_crate_file {
+                vec![Rustdoc::from_crate_file(baseline_crate_file)]
             } else {
                 // Either there's a manually-set baseline root path, or fall through
                 // to the default behavior.
@@ -658,15 +1174,47 @@ impl From<CheckRelease> for cargo_semver_checks::Check {
                     .baseline_root
                     .map(lenient_baseline_root)
                     .map(Rustdoc::from_root)
+                    .into_iter()
+                    .collect()
             }
         };
-        if let Some(baseline) = custom_baseline {
-            check.set_baseline(baseline);
+        if !custom_baseline.is_empty() {
+            check.set_baselines(custom_baseline);
+        }
+        if value.baseline_all_in_major {
+            check.set_baseline_all_in_major(true);
+        }
+        if let Some(registry) = value.registry {
+            check.set_registry(registry);
+        }
+        if value.offline {
+            check.set_offline(true);
+        }
+        match value.lockfile {
+            Some(Some(path)) => {
+                check.set_dependency_lockfile(DependencyLockfile::File(path));
+            }
+            Some(None) => {
+                check.set_dependency_lockfile(DependencyLockfile::CurrentProject);
+            }
+            None => {}
+        }
+        if let Some(timeout) = value.timeout {
+            check.set_generation_timeout(timeout);
+        }
+        if let Some(timeout) = value.query_timeout {
+            check.set_query_timeout(timeout);
         }
 
         if let Some(release_type) = value.release_type {
             check.set_release_type(release_type);
         }
+        if let Some(prerelease_policy) = value.prerelease_policy {
+            check.set_prerelease_policy(prerelease_policy);
+        }
+        if let Some(versioning_policy) = value.versioning_policy {
+            check.set_versioning_policy(versioning_policy);
+        }
 
         if value.all_features {
             check.with_all_features();
@@ -692,8 +1240,40 @@ impl From<CheckRelease> for cargo_semver_checks::Check {
 
         check.set_extra_features(current_features, baseline_features);
 
-        if let Some(build_target) = value.build_target {
-            check.set_build_target(build_target);
+        if value.each_feature {
+            check.set_feature_matrix(FeatureMatrix::EachFeature);
+        } else if value.feature_powerset {
+            check.set_feature_matrix(FeatureMatrix::Powerset { depth: value.depth });
+        }
+        check.set_check_feature_gating(value.check_feature_gating);
+
+        if !value.build_target.is_empty() {
+            check.set_build_targets(value.build_target);
+        }
+        if value.docs_rs_targets {
+            check.set_docs_rs_targets(true);
+        }
+
+        if let Some(toolchain) = value.toolchain {
+            check.set_toolchain(toolchain);
+        }
+        if let Some(toolchain) = value.current_toolchain {
+            check.set_current_toolchain(toolchain);
+        }
+        if let Some(toolchain) = value.baseline_toolchain {
+            check.set_baseline_toolchain(toolchain);
+        }
+
+        if let Some(format) = value.timings {
+            check.set_timings(format);
+        }
+
+        if value.check_unchanged_api {
+            check.set_check_unchanged_api(true);
+        }
+
+        if let Some(jobs) = value.jobs {
+            check.set_jobs(jobs);
         }
 
         if value.unstable_options.stability_aware {
@@ -759,11 +1339,16 @@ fn validate_feature_flags(config: &mut GlobalConfig, args: &SemverChecks) -> any
 
     if !config.feature_flag_enabled(FeatureFlag::UNSTABLE_OPTIONS) {
         let unstable_options = match &args.command {
-            Some(SemverChecksCommands::CheckRelease(cr)) => &cr.unstable_options,
-            None => &args.check_release.unstable_options,
+            Some(SemverChecksCommands::CheckRelease(cr)) => Some(&cr.unstable_options),
+            Some(SemverChecksCommands::Cache(_)) => None,
+            Some(SemverChecksCommands::Bisect(b)) => Some(&b.check_release.unstable_options),
+            Some(SemverChecksCommands::Bugreport(b)) => Some(&b.check_release.unstable_options),
+            None => Some(&args.check_release.unstable_options),
         };
 
-        let non_default_options = unstable_options.non_default();
+        let non_default_options = unstable_options
+            .map(UnstableOptions::non_default)
+            .unwrap_or_default();
 
         if !non_default_options.is_empty() {
             let mut message = String::from(
@@ -946,3 +1531,300 @@ fn stability_aware_adds_no_extra_validation_requirements() {
         "--stability-aware should not add validation requirements beyond -Z unstable-options",
     );
 }
+
+#[test]
+fn cache_prune_parses_sizes_and_ages() {
+    let Cargo::SemverChecks(args) = Cargo::try_parse_from([
+        "cargo",
+        "semver-checks",
+        "cache",
+        "prune",
+        "--max-size",
+        "10GiB",
+        "--max-age",
+        "30d",
+        "--keep-versions",
+        "3",
+    ])
+    .expect("args should parse");
+
+    let Some(SemverChecksCommands::Cache(CacheCommand {
+        cache_dir: None,
+        action: CacheAction::Prune(prune),
+    })) = args.command
+    else {
+        panic!("expected `cache prune` command, got {:?}", args.command);
+    };
+    assert_eq!(prune.max_size, Some(10 << 30));
+    assert_eq!(prune.max_age, Some(Duration::from_secs(30 * 24 * 60 * 60)));
+    assert_eq!(prune.keep_versions, NonZeroUsize::new(3));
+
+    assert_eq!(parse_size("500M"), Ok(500 << 20));
+    assert_eq!(parse_size("1024"), Ok(1024));
+    assert!(parse_size("10 parsecs").is_err());
+    assert_eq!(parse_age("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+    assert!(parse_age("12").is_err());
+}
+
+#[test]
+fn cache_prune_requires_a_limit() {
+    let err = Cargo::try_parse_from(["cargo", "semver-checks", "cache", "prune"])
+        .expect_err("prune without limits should be rejected");
+    assert_eq!(err.kind(), clap::error::ErrorKind::MissingRequiredArgument);
+}
+
+/// The check that `cargo semver-checks` runs with the given arguments.
+#[cfg(test)]
+fn check_for(args: &[&str]) -> cargo_semver_checks::Check {
+    let Cargo::SemverChecks(SemverChecks { check_release, .. }) =
+        Cargo::parse_from(["cargo", "semver-checks"].iter().chain(args));
+    check_release.into()
+}
+
+#[test]
+fn baseline_crate_file_is_used_as_baseline() {
+    let mut expected = check_for(&[]);
+    expected.set_baseline(Rustdoc::from_crate_file("foo-1.2.3.crate"));
+    assert_eq!(
+        check_for(&["--baseline-crate-file", "foo-1.2.3.crate"]),
+        expected
+    );
+}
+
+#[test]
+fn toolchain_flags_select_rustdoc_toolchains() {
+    let mut expected = check_for(&[]);
+    expected.set_toolchain("nightly");
+    assert_eq!(check_for(&["--toolchain", "nightly"]), expected);
+
+    let mut expected = check_for(&[]);
+    expected
+        .set_current_toolchain("nightly")
+        .set_baseline_toolchain("1.85.0");
+    assert_eq!(
+        check_for(&[
+            "--current-toolchain",
+            "nightly",
+            "--baseline-toolchain",
+            "1.85.0"
+        ]),
+        expected
+    );
+
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--toolchain",
+            "nightly",
+            "--baseline-toolchain",
+            "1.85.0",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn feature_matrix_flags_select_feature_sets() {
+    let mut expected = check_for(&["--only-explicit-features"]);
+    expected.set_feature_matrix(FeatureMatrix::EachFeature);
+    assert_eq!(
+        check_for(&["--only-explicit-features", "--each-feature"]),
+        expected
+    );
+
+    let mut expected = check_for(&[]);
+    expected.set_feature_matrix(FeatureMatrix::Powerset {
+        depth: NonZeroUsize::new(2),
+    });
+    assert_eq!(check_for(&["--feature-powerset", "--depth", "2"]), expected);
+
+    assert!(Cargo::try_parse_from(["cargo", "semver-checks", "--depth", "2"]).is_err());
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--each-feature",
+            "--feature-powerset"
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn check_feature_gating_flag_enables_the_check() {
+    let mut expected = check_for(&[]);
+    expected.set_check_feature_gating(true);
+    assert_eq!(check_for(&["--check-feature-gating"]), expected);
+
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--check-feature-gating",
+            "--baseline-rustdoc",
+            "baseline.json",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn bisect_takes_the_range_and_check_settings() {
+    let Cargo::SemverChecks(SemverChecks {
+        command: Some(SemverChecksCommands::Bisect(bisect)),
+        ..
+    }) = Cargo::parse_from([
+        "cargo",
+        "semver-checks",
+        "bisect",
+        "--baseline-rev",
+        "v1.0.0",
+        "--current-rev",
+        "main",
+        "--lint",
+        "function_missing",
+        "--default-features",
+    ])
+    else {
+        panic!("expected the bisect subcommand");
+    };
+
+    assert_eq!(bisect.current_rev, "main");
+    assert_eq!(bisect.lint.as_deref(), Some("function_missing"));
+    assert_eq!(bisect.check_release.baseline_rev, ["v1.0.0"]);
+    assert!(bisect.check_release.default_features);
+
+    assert!(Cargo::try_parse_from(["cargo", "semver-checks", "bisect"]).is_err());
+}
+
+#[test]
+fn baseline_flags_can_name_several_baselines() {
+    let mut expected = check_for(&[]);
+    expected.set_baselines([
+        Rustdoc::from_registry("1.0.0"),
+        Rustdoc::from_registry("1.1.0"),
+    ]);
+    assert_eq!(
+        check_for(&["--baseline-version", "1.0.0", "--baseline-version", "1.1.0"]),
+        expected
+    );
+
+    let mut expected = check_for(&[]);
+    expected.set_baseline_all_in_major(true);
+    assert_eq!(check_for(&["--baseline-all-in-major"]), expected);
+
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--baseline-all-in-major",
+            "--baseline-version",
+            "1.0.0",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn prerelease_flags_set_the_release_type_and_policy() {
+    let mut expected = check_for(&[]);
+    expected
+        .set_release_type(ReleaseType::Prerelease)
+        .set_prerelease_policy(PrereleasePolicy::BreakingInAlpha);
+    assert_eq!(
+        check_for(&[
+            "--release-type",
+            "prerelease",
+            "--prerelease-policy",
+            "breaking-in-alpha",
+        ]),
+        expected
+    );
+}
+
+#[test]
+fn versioning_policy_flag_sets_the_policy() {
+    let mut expected = check_for(&[]);
+    expected.set_versioning_policy(VersioningPolicy::StrictZeroVer);
+    assert_eq!(
+        check_for(&["--versioning-policy", "strict-zero-ver"]),
+        expected
+    );
+    expected.set_versioning_policy(VersioningPolicy::Calendar);
+    assert_eq!(check_for(&["--versioning-policy", "calendar"]), expected);
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--versioning-policy",
+            "anything-goes",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn lockfile_flag_seeds_dependency_resolution() {
+    let mut expected = check_for(&[]);
+    expected.set_dependency_lockfile(DependencyLockfile::CurrentProject);
+    assert_eq!(check_for(&["--lockfile"]), expected);
+
+    let mut expected = check_for(&[]);
+    expected.set_dependency_lockfile(DependencyLockfile::File("baseline.lock".into()));
+    assert_eq!(check_for(&["--lockfile", "baseline.lock"]), expected);
+
+    assert!(
+        Cargo::try_parse_from([
+            "cargo",
+            "semver-checks",
+            "--lockfile",
+            "--current-rustdoc",
+            "current.json",
+        ])
+        .is_err()
+    );
+}
+
+#[test]
+fn timeout_flags_set_time_limits() {
+    let mut expected = check_for(&[]);
+    expected.set_generation_timeout(Duration::from_secs(30 * 60));
+    expected.set_query_timeout(Duration::from_secs(45));
+    assert_eq!(
+        check_for(&["--timeout", "30m", "--query-timeout", "45s"]),
+        expected
+    );
+
+    assert_eq!(parse_timeout("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+    assert!(parse_timeout("0s").is_err());
+    assert!(parse_timeout("soon").is_err());
+}
+
+#[test]
+fn bugreport_subcommand_bundles_a_check() {
+    let Cargo::SemverChecks(SemverChecks {
+        command: Some(SemverChecksCommands::Bugreport(bugreport)),
+        ..
+    }) = Cargo::parse_from([
+        "cargo",
+        "semver-checks",
+        "bugreport",
+        "--bundle",
+        "out.tar",
+        "--include-rustdoc",
+        "--baseline-version",
+        "1.2.3",
+    ])
+    else {
+        panic!("expected the bugreport subcommand");
+    };
+    assert_eq!(bugreport.bundle, Some(PathBuf::from("out.tar")));
+    assert!(bugreport.include_rustdoc);
+    assert_eq!(bugreport.check_release.baseline_version, ["1.2.3"]);
+
+    assert!(
+        Cargo::try_parse_from(["cargo", "semver-checks", "bugreport", "--include-rustdoc"])
+            .is_err()
+    );
+}
@@ -3,7 +3,7 @@ use std::collections::BTreeMap;
 use anyhow::Context;
 use serde::Deserialize;
 
-use crate::{LintLevel, OverrideMap, QueryOverride, RequiredSemverUpdate};
+use crate::{LintLevel, OverrideMap, QueryOverride, RequiredSemverUpdate, VersioningPolicy};
 
 #[derive(Debug, Clone)]
 pub(crate) struct Manifest {
@@ -114,6 +114,9 @@ pub(crate) struct MetadataTable {
 pub(crate) struct SemverChecksTable {
     /// Holds the `lints` table, if it is declared.
     pub(crate) lints: Option<LintTable>,
+    /// Holds the `versioning-policy` key, if it is declared.
+    #[serde(default, rename = "versioning-policy")]
+    pub(crate) versioning_policy: Option<VersioningPolicy>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -267,6 +270,18 @@ pub(crate) fn deserialize_lint_table(
     Ok(table.and_then(|table| table.config.and_then(|config| config.lints)))
 }
 
+/// Helper function to deserialize the optional `versioning-policy` key from a
+/// [`serde_json::Value`] holding a `[package/workspace.metadata]` table.
+///
+/// Returns an `Err` if the `cargo-semver-checks` table is present
+/// but invalid.  Returns `Ok(None)` if the key is not present.
+pub(crate) fn deserialize_versioning_policy(
+    metadata: &serde_json::Value,
+) -> anyhow::Result<Option<VersioningPolicy>> {
+    let table = Option::<MetadataTable>::deserialize(metadata)?;
+    Ok(table.and_then(|table| table.config.and_then(|config| config.versioning_policy)))
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -385,6 +400,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn versioning_policy_is_read_from_the_table() {
+        let metadata = serde_json::json! {{
+            "cargo-semver-checks": {
+                "versioning-policy": "strict-zero-ver",
+                "lints": { "one": "warn" },
+            }
+        }};
+        assert_eq!(
+            super::deserialize_versioning_policy(&metadata).expect("table should be valid"),
+            Some(crate::VersioningPolicy::StrictZeroVer)
+        );
+
+        let metadata = serde_json::json! {{ "cargo-semver-checks": { "lints": {} } }};
+        assert_eq!(
+            super::deserialize_versioning_policy(&metadata).expect("table should be valid"),
+            None
+        );
+
+        let metadata = serde_json::json! {{
+            "cargo-semver-checks": { "versioning-policy": "anything-goes" }
+        }};
+        super::deserialize_versioning_policy(&metadata)
+            .expect_err("unknown policies should be rejected");
+    }
+
     #[test]
     fn workspace_key_false_is_error() {
         serde_json::from_value::<LintTable>(serde_json::json! {{
@@ -0,0 +1,115 @@
+//! Limits on how many crates may be in each phase of being checked at once.
+//!
+//! Generating rustdoc mostly waits on `cargo`, while running lints keeps a CPU busy.
+//! Limiting each phase separately lets rustdoc generation for some crates
+//! overlap with running lints on others, instead of each crate taking turns.
+
+use std::sync::{Condvar, Mutex};
+
+/// How many crates may be generating rustdoc, and how many may be running lints, at once.
+#[derive(Debug)]
+pub(crate) struct PhaseLimits {
+    generation: Semaphore,
+    linting: Semaphore,
+}
+
+impl PhaseLimits {
+    /// Allow up to `jobs` crates in each phase at once.
+    pub(crate) fn new(jobs: usize) -> Self {
+        Self {
+            generation: Semaphore::new(jobs),
+            linting: Semaphore::new(jobs),
+        }
+    }
+
+    /// Wait until another crate may generate rustdoc, which it may until the permit is dropped.
+    pub(crate) fn generating(&self) -> Permit<'_> {
+        self.generation.acquire()
+    }
+
+    /// Wait until another crate may run lints, which it may until the permit is dropped.
+    pub(crate) fn linting(&self) -> Permit<'_> {
+        self.linting.acquire()
+    }
+}
+
+#[derive(Debug)]
+struct Semaphore {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Semaphore {
+    fn new(permits: usize) -> Self {
+        Self {
+            available: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    fn acquire(&self) -> Permit<'_> {
+        let available = self.available.lock().expect("phase limit lock poisoned");
+        let mut available = self
+            .released
+            .wait_while(available, |available| *available == 0)
+            .expect("phase limit lock poisoned");
+        *available -= 1;
+        Permit(self)
+    }
+}
+
+/// Allows one crate to be in a phase, until it's dropped.
+#[must_use = "the phase ends as soon as its permit is dropped"]
+pub(crate) struct Permit<'a>(&'a Semaphore);
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().expect("phase limit lock poisoned") += 1;
+        self.0.released.notify_one();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::PhaseLimits;
+
+    #[test]
+    fn phases_are_limited_separately() {
+        let limits = PhaseLimits::new(2);
+        let generating = AtomicUsize::new(0);
+        let linting = AtomicUsize::new(0);
+        let most_generating = AtomicUsize::new(0);
+        let most_linting = AtomicUsize::new(0);
+        let most_in_both = AtomicUsize::new(0);
+
+        let enter = |phase: &AtomicUsize, most: &AtomicUsize| {
+            let now = phase.fetch_add(1, Ordering::SeqCst) + 1;
+            most.fetch_max(now, Ordering::SeqCst);
+            most_in_both.fetch_max(
+                generating.load(Ordering::SeqCst) + linting.load(Ordering::SeqCst),
+                Ordering::SeqCst,
+            );
+            std::thread::sleep(Duration::from_millis(20));
+            phase.fetch_sub(1, Ordering::SeqCst);
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let permit = limits.generating();
+                    enter(&generating, &most_generating);
+                    drop(permit);
+                    let _permit = limits.linting();
+                    enter(&linting, &most_linting);
+                });
+            }
+        });
+
+        assert_eq!(most_generating.load(Ordering::SeqCst), 2);
+        assert_eq!(most_linting.load(Ordering::SeqCst), 2);
+        // Some crates ran lints while others were still generating rustdoc.
+        assert!(most_in_both.load(Ordering::SeqCst) > 2);
+    }
+}
@@ -1,8 +1,14 @@
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, LazyLock, RwLock},
+};
 
+use anyhow::Context as _;
 use ron::extensions::Extensions;
 use serde::{Deserialize, Serialize};
 use trustfall::{FieldValue, TransparentValue};
+use trustfall_core::ir::IndexedQuery;
+use trustfall_rustdoc::VersionedRustdocAdapter;
 
 use crate::ReleaseType;
 
@@ -75,6 +81,21 @@ impl ActualSemverUpdate {
             (_, _) => false,
         }
     }
+
+    /// The smaller of the two updates.
+    pub(crate) fn smaller(self, other: Self) -> Self {
+        let size = |update| match update {
+            ActualSemverUpdate::NotChanged => 0,
+            ActualSemverUpdate::Patch => 1,
+            ActualSemverUpdate::Minor => 2,
+            ActualSemverUpdate::Major => 3,
+        };
+        if size(other) < size(self) {
+            other
+        } else {
+            self
+        }
+    }
 }
 
 impl From<ReleaseType> for ActualSemverUpdate {
@@ -83,6 +104,8 @@ impl From<ReleaseType> for ActualSemverUpdate {
             ReleaseType::Major => Self::Major,
             ReleaseType::Minor => Self::Minor,
             ReleaseType::Patch => Self::Patch,
+            // Absent a policy saying otherwise, pre-releases may make any change.
+            ReleaseType::Prerelease => Self::Major,
         }
     }
 }
@@ -171,9 +194,32 @@ impl SemverQuery {
     }
 
     pub fn all_queries() -> BTreeMap<String, SemverQuery> {
+        LintRegistry::get().queries().clone()
+    }
+}
+
+/// The built-in lints, parsed on first use and shared for the rest of the process.
+///
+/// Also holds each lint's compiled Trustfall query, so that checking many crates
+/// only parses and validates each query once per rustdoc format version.
+pub(crate) struct LintRegistry {
+    queries: BTreeMap<String, SemverQuery>,
+    /// Compiled queries keyed by rustdoc format version and lint id,
+    /// since each rustdoc format version has its own schema.
+    compiled: RwLock<HashMap<(u32, String), Arc<IndexedQuery>>>,
+}
+
+static LINT_REGISTRY: LazyLock<LintRegistry> = LazyLock::new(LintRegistry::load);
+
+impl LintRegistry {
+    pub(crate) fn get() -> &'static Self {
+        &LINT_REGISTRY
+    }
+
+    fn load() -> Self {
         let mut queries = BTreeMap::default();
         for (id, query_text) in get_queries() {
-            let query = Self::from_ron_str(query_text).unwrap_or_else(|e| {
+            let query = SemverQuery::from_ron_str(query_text).unwrap_or_else(|e| {
                 panic!(
                     "\
                 Failed to parse a query: {e}
@@ -187,7 +233,45 @@ impl SemverQuery {
             assert!(id_conflict.is_none(), "{id_conflict:?}");
         }
 
-        queries
+        Self {
+            queries,
+            compiled: RwLock::default(),
+        }
+    }
+
+    /// All built-in lints, keyed by lint id.
+    pub(crate) fn queries(&self) -> &BTreeMap<String, SemverQuery> {
+        &self.queries
+    }
+
+    /// Returns the given built-in lint's query, compiled against the adapter's schema.
+    pub(crate) fn compiled_query(
+        &self,
+        adapter: &VersionedRustdocAdapter<'_>,
+        query: &SemverQuery,
+    ) -> anyhow::Result<Arc<IndexedQuery>> {
+        let key = (adapter.version(), query.id.clone());
+        if let Some(compiled) = self
+            .compiled
+            .read()
+            .expect("lint registry lock poisoned")
+            .get(&key)
+        {
+            return Ok(Arc::clone(compiled));
+        }
+
+        // Compile outside the lock, so other lints aren't blocked meanwhile.
+        // If another thread compiles the same query at the same time, keep the first one.
+        let compiled = trustfall_core::frontend::parse(adapter.schema(), &query.query)
+            .with_context(|| format!("failed to compile query for lint `{}`", query.id))?;
+        let compiled = self
+            .compiled
+            .write()
+            .expect("lint registry lock poisoned")
+            .entry(key)
+            .or_insert(compiled)
+            .clone();
+        Ok(compiled)
     }
 }
 
@@ -407,8 +491,8 @@ mod tests {
     };
 
     use crate::query::{
-        InheritedValue, LintLevel, OverrideMap, OverrideStack, QueryOverride, RequiredSemverUpdate,
-        SemverQuery,
+        InheritedValue, LintLevel, LintRegistry, OverrideMap, OverrideStack, QueryOverride,
+        RequiredSemverUpdate, SemverQuery,
     };
     use crate::templating::make_handlebars_registry;
 
@@ -751,6 +835,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn lint_registry_compiles_each_query_once() {
+        let (_baseline, current) = get_test_crate_indexes("template");
+
+        let adapter =
+            VersionedRustdocAdapter::new(current, Some(current)).expect("failed to create adapter");
+        let registry = LintRegistry::get();
+        let semver_query = registry
+            .queries()
+            .values()
+            .next()
+            .expect("no lints registered");
+
+        let first = registry
+            .compiled_query(&adapter, semver_query)
+            .expect("not a valid query");
+        let second = registry
+            .compiled_query(&adapter, semver_query)
+            .expect("not a valid query");
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
     #[test]
     fn pub_use_handling() {
         let (_baseline, current) = get_test_crate_indexes("pub_use_handling");
@@ -0,0 +1,257 @@
+//! Stopping lint queries that run past their time budget or whose check was cancelled.
+//!
+//! Trustfall evaluates queries lazily, and a single result can take arbitrarily long
+//! to produce, so checking limits between results isn't enough. Instead, the adapter
+//! is wrapped so that every vertex and value it produces first checks the limits,
+//! and once they're exceeded, the query runs out of data and finishes early.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Instant;
+
+use trustfall::FieldValue;
+use trustfall::provider::{
+    Adapter, AsVertex, ContextIterator, ContextOutcomeIterator, EdgeParameters, ResolveEdgeInfo,
+    ResolveInfo, VertexIterator,
+};
+use trustfall_core::interpreter::execution::interpret_ir;
+use trustfall_core::ir::IndexedQuery;
+use trustfall_rustdoc::VersionedRustdocAdapter;
+
+use crate::CancellationToken;
+
+type QueryResult = BTreeMap<Arc<str>, FieldValue>;
+
+/// Why a query stopped before producing all its results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Interruption {
+    TimedOut,
+    Cancelled,
+}
+
+/// The limits a query runs under, and whether it has exceeded them.
+///
+/// Once a query is interrupted, its results are incomplete and must be discarded.
+#[derive(Debug)]
+pub(crate) struct QueryLimits {
+    deadline: Option<Instant>,
+    cancellation: Option<CancellationToken>,
+    interruption: Cell<Option<Interruption>>,
+}
+
+impl QueryLimits {
+    pub(crate) fn new(deadline: Option<Instant>, cancellation: Option<CancellationToken>) -> Self {
+        Self {
+            deadline,
+            cancellation,
+            interruption: Cell::new(None),
+        }
+    }
+
+    /// Why the query was interrupted, if it was.
+    pub(crate) fn interruption(&self) -> Option<Interruption> {
+        self.interruption.get()
+    }
+
+    /// Whether the query may keep running, recording why not if it may not.
+    fn check(&self) -> bool {
+        if self.interruption.get().is_none() {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                self.interruption.set(Some(Interruption::Cancelled));
+            } else if self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+            {
+                self.interruption.set(Some(Interruption::TimedOut));
+            }
+        }
+        self.interruption.get().is_none()
+    }
+
+    fn limit<'a, T: 'a>(
+        self: &Rc<Self>,
+        iter: impl Iterator<Item = T> + 'a,
+    ) -> Box<dyn Iterator<Item = T> + 'a> {
+        let limits = Rc::clone(self);
+        Box::new(iter.take_while(move |_| limits.check()))
+    }
+}
+
+/// Runs the query like [`VersionedRustdocAdapter::run_query_with_indexed_query`],
+/// but stops producing results once `limits` are exceeded.
+// Trustfall takes the adapter in an `Arc`, even though the query stays on this thread.
+#[allow(clippy::arc_with_non_send_sync)]
+pub(crate) fn run_query_with_limits<'a, K: Into<Arc<str>>, V: Into<FieldValue>>(
+    adapter: &'a VersionedRustdocAdapter<'a>,
+    query: Arc<IndexedQuery>,
+    arguments: BTreeMap<K, V>,
+    limits: &Rc<QueryLimits>,
+) -> anyhow::Result<Box<dyn Iterator<Item = QueryResult> + 'a>> {
+    let arguments = Arc::new(
+        arguments
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect(),
+    );
+    let limits = Rc::clone(limits);
+    Ok(match adapter {
+        VersionedRustdocAdapter::V57(_, adapter) => interpret_ir(
+            Arc::new(LimitedAdapter { adapter, limits }),
+            query,
+            arguments,
+        )?,
+        VersionedRustdocAdapter::V60(_, adapter) => interpret_ir(
+            Arc::new(LimitedAdapter { adapter, limits }),
+            query,
+            arguments,
+        )?,
+        VersionedRustdocAdapter::V61(_, adapter) => interpret_ir(
+            Arc::new(LimitedAdapter { adapter, limits }),
+            query,
+            arguments,
+        )?,
+        _ => unreachable!("unsupported rustdoc format version v{}", adapter.version()),
+    })
+}
+
+/// An adapter that stops producing data once its query's limits are exceeded.
+struct LimitedAdapter<A> {
+    adapter: A,
+    limits: Rc<QueryLimits>,
+}
+
+impl<'vertex, A: Adapter<'vertex>> Adapter<'vertex> for LimitedAdapter<A> {
+    type Vertex = A::Vertex;
+
+    fn resolve_starting_vertices(
+        &self,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        resolve_info: &ResolveInfo,
+    ) -> VertexIterator<'vertex, Self::Vertex> {
+        self.limits.limit(self.adapter.resolve_starting_vertices(
+            edge_name,
+            parameters,
+            resolve_info,
+        ))
+    }
+
+    fn resolve_property<V: AsVertex<Self::Vertex> + 'vertex>(
+        &self,
+        contexts: ContextIterator<'vertex, V>,
+        type_name: &Arc<str>,
+        property_name: &Arc<str>,
+        resolve_info: &ResolveInfo,
+    ) -> ContextOutcomeIterator<'vertex, V, FieldValue> {
+        self.limits.limit(self.adapter.resolve_property(
+            contexts,
+            type_name,
+            property_name,
+            resolve_info,
+        ))
+    }
+
+    fn resolve_neighbors<V: AsVertex<Self::Vertex> + 'vertex>(
+        &self,
+        contexts: ContextIterator<'vertex, V>,
+        type_name: &Arc<str>,
+        edge_name: &Arc<str>,
+        parameters: &EdgeParameters,
+        resolve_info: &ResolveEdgeInfo,
+    ) -> ContextOutcomeIterator<'vertex, V, VertexIterator<'vertex, Self::Vertex>> {
+        let limits = Rc::clone(&self.limits);
+        self.limits.limit(
+            self.adapter
+                .resolve_neighbors(contexts, type_name, edge_name, parameters, resolve_info)
+                .map(move |(context, neighbors)| (context, limits.limit(neighbors))),
+        )
+    }
+
+    fn resolve_coercion<V: AsVertex<Self::Vertex> + 'vertex>(
+        &self,
+        contexts: ContextIterator<'vertex, V>,
+        type_name: &Arc<str>,
+        coerce_to_type: &Arc<str>,
+        resolve_info: &ResolveInfo,
+    ) -> ContextOutcomeIterator<'vertex, V, bool> {
+        self.limits.limit(self.adapter.resolve_coercion(
+            contexts,
+            type_name,
+            coerce_to_type,
+            resolve_info,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use trustfall_rustdoc::{VersionedIndex, load_rustdoc};
+
+    use super::*;
+    use crate::query::LintRegistry;
+
+    fn run_lint(limits: QueryLimits) -> (usize, Option<Interruption>) {
+        let load = |version: &str| {
+            load_rustdoc(
+                Path::new(&format!(
+                    "./localdata/test_data/function_missing/{version}/rustdoc.json"
+                )),
+                None,
+            )
+            .expect("failed to load rustdoc, did you forget to run ./scripts/regenerate_test_rustdocs.sh ?")
+        };
+        let (baseline, current) = (load("old"), load("new"));
+        let (baseline, current) = (
+            VersionedIndex::from_storage(&baseline),
+            VersionedIndex::from_storage(&current),
+        );
+        let adapter = VersionedRustdocAdapter::new(&current, Some(&baseline))
+            .expect("failed to create adapter");
+        let lint = &LintRegistry::get().queries()["function_missing"];
+        let query = LintRegistry::get()
+            .compiled_query(&adapter, lint)
+            .expect("failed to compile query");
+
+        let limits = Rc::new(limits);
+        let results = run_query_with_limits(&adapter, query, lint.arguments.clone(), &limits)
+            .expect("failed to run query")
+            .count();
+        (results, limits.interruption())
+    }
+
+    #[test]
+    fn queries_within_limits_produce_all_results() {
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let (results, interruption) = run_lint(QueryLimits::new(
+            Some(deadline),
+            Some(CancellationToken::new()),
+        ));
+        assert!(results > 0);
+        assert_eq!(interruption, None);
+    }
+
+    #[test]
+    fn queries_stop_at_the_deadline() {
+        let (results, interruption) = run_lint(QueryLimits::new(Some(Instant::now()), None));
+        assert_eq!(results, 0);
+        assert_eq!(interruption, Some(Interruption::TimedOut));
+    }
+
+    #[test]
+    fn queries_stop_once_cancelled() {
+        let cancellation = CancellationToken::new();
+        cancellation.cancel();
+        let (results, interruption) = run_lint(QueryLimits::new(None, Some(cancellation)));
+        assert_eq!(results, 0);
+        assert_eq!(interruption, Some(Interruption::Cancelled));
+    }
+}
@@ -0,0 +1,530 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context as _, bail};
+use tame_index::index::FileLock;
+use tame_index::{IndexKrate, IndexVersion, KrateName};
+
+/// The name cargo uses for the crates.io source in `[source]` replacement tables.
+const CRATES_IO_SOURCE: &str = "crates-io";
+
+/// The index of the registry that registry baselines are looked up in.
+///
+/// Resolved from cargo configuration the same way cargo does it, so `[source]` replacement
+/// applies: crates.io or a `[registries]` entry may be replaced by a mirror,
+/// a local registry, or a directory of vendored crates.
+pub(crate) struct RegistryIndex {
+    /// The `[registries]` name of the registry, or `None` for crates.io.
+    name: Option<String>,
+
+    /// The source that the registry's crates actually come from, for use in messages.
+    description: String,
+
+    kind: IndexKind,
+}
+
+enum IndexKind {
+    Sparse(tame_index::index::RemoteSparseIndex),
+    /// A sparse index that we may not update, in offline mode, or a git index,
+    /// which we never fetch ourselves. Only the entries that cargo already
+    /// downloaded are available.
+    Cached(tame_index::index::ComboIndexCache),
+    Local(tame_index::index::LocalRegistry),
+    /// Vendored crates, as made by `cargo vendor`. These have no index,
+    /// so we read their manifests instead.
+    Directory(PathBuf),
+}
+
+impl core::fmt::Debug for RegistryIndex {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RegistryIndex")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("kind", &"<elided>")
+            .finish()
+    }
+}
+
+impl RegistryIndex {
+    /// Open the index of the named registry from `[registries]` in cargo config,
+    /// or of crates.io if `name` is `None`.
+    ///
+    /// When `offline`, remote indexes aren't contacted, and only their entries
+    /// already cached by cargo are used. Git indexes are never fetched,
+    /// so the same applies to them.
+    pub(crate) fn open(name: Option<&str>, offline: bool) -> anyhow::Result<Self> {
+        let cargo_config = cargo_config2::Config::load().context("failed to load cargo config")?;
+        Self::open_with_config(&cargo_config, name, offline)
+    }
+
+    fn open_with_config(
+        cargo_config: &cargo_config2::Config,
+        name: Option<&str>,
+        offline: bool,
+    ) -> anyhow::Result<Self> {
+        let source_name =
+            replaced_source_name(cargo_config, &registry_source_name(cargo_config, name)?)?;
+        let replacement = cargo_config.source.get(&source_name);
+
+        let (description, kind) = if let Some(path) =
+            replacement.and_then(|source| source.local_registry.as_ref())
+        {
+            let utf8_path = tame_index::PathBuf::try_from(path.clone())
+                .with_context(|| format!("non-UTF-8 local registry path {}", path.display()))?;
+            let registry = tame_index::index::LocalRegistry::open(utf8_path, false)
+                .with_context(|| format!("failed to open local registry at {}", path.display()))?;
+            (
+                format!("local registry at {}", path.display()),
+                IndexKind::Local(registry),
+            )
+        } else if let Some(path) = replacement.and_then(|source| source.directory.as_ref()) {
+            (
+                format!("vendored crates at {}", path.display()),
+                IndexKind::Directory(path.clone()),
+            )
+        } else {
+            let index_url =
+                if let Some(url) = replacement.and_then(|source| source.registry.as_deref()) {
+                    tame_index::IndexUrl::NonCratesIo(url.to_owned().into())
+                } else if source_name == CRATES_IO_SOURCE {
+                    // Also handles `registries.crates-io.protocol`.
+                    tame_index::IndexUrl::crates_io(None, None, None)
+                        .context("failed to obtain crates.io url")?
+                } else {
+                    tame_index::IndexUrl::NonCratesIo(
+                        registry_index_url(cargo_config, &source_name)?.into(),
+                    )
+                };
+            if offline || !index_url.is_sparse() {
+                let description = format!("local cache of registry `{}`", index_url.as_str());
+                let location = tame_index::IndexLocation::new(index_url);
+                let cache = tame_index::index::ComboIndexCache::new(location)
+                    .context("failed to open cached registry index")?;
+                (description, IndexKind::Cached(cache))
+            } else {
+                let description = format!("registry `{}`", index_url.as_str());
+                (description, open_sparse_index(index_url)?)
+            }
+        };
+
+        Ok(Self {
+            name: name.map(ToOwned::to_owned),
+            description,
+            kind,
+        })
+    }
+
+    /// The `[registries]` name of the registry, or `None` for crates.io.
+    pub(crate) fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Look up all versions of a crate, or `None` if the registry doesn't have it.
+    pub(crate) fn krate(&self, name: &str, lock: &FileLock) -> anyhow::Result<Option<IndexKrate>> {
+        let krate_name: KrateName<'_> = name
+            .try_into()
+            .with_context(|| format!("invalid crate name `{name}`"))?;
+        let krate = match &self.kind {
+            IndexKind::Sparse(index) => index.krate(krate_name, false, lock)?,
+            IndexKind::Cached(index) => index.cached_krate(krate_name, lock)?,
+            IndexKind::Local(index) => index.cached_krate(krate_name, lock)?,
+            IndexKind::Directory(dir) => vendored_krate(dir, name)?,
+        };
+        Ok(krate)
+    }
+
+    /// Explain where a crate that wasn't found in the registry was looked up.
+    pub(crate) fn not_found_message(&self, crate_name: &str) -> String {
+        match (&self.name, &self.kind) {
+            (None, IndexKind::Sparse(..)) => format!(
+                "{crate_name} not found in registry (crates.io). \
+                For workarounds check \
+                https://github.com/obi1kenobi/cargo-semver-checks#does-the-crate-im-checking-have-to-be-published-on-cratesio",
+            ),
+            (_, IndexKind::Cached(..)) => format!(
+                "{crate_name} not found in the {}; \
+                 in offline mode or for git registries, \
+                 only crates that cargo already downloaded are available",
+                self.description
+            ),
+            _ => format!("{crate_name} not found in {}", self.description),
+        }
+    }
+
+    /// Whether the index may be missing versions that exist in the registry,
+    /// because we only have the parts of it that cargo downloaded earlier.
+    pub(crate) fn is_cached_only(&self) -> bool {
+        matches!(self.kind, IndexKind::Cached(..))
+    }
+}
+
+/// The name of the `[source]` entry for the named registry, or for crates.io if `name` is `None`.
+///
+/// Like cargo, this matches a registry's source by the URL of its index,
+/// so the entry may be named differently from the registry.
+fn registry_source_name(
+    cargo_config: &cargo_config2::Config,
+    name: Option<&str>,
+) -> anyhow::Result<String> {
+    let Some(name) = name else {
+        return Ok(CRATES_IO_SOURCE.to_owned());
+    };
+    let index_url = registry_index_url(cargo_config, name)?;
+    let source_name = cargo_config
+        .source
+        .iter()
+        .find(|(_, source)| {
+            source
+                .registry
+                .as_deref()
+                .is_some_and(|url| url.trim_end_matches('/') == index_url.trim_end_matches('/'))
+        })
+        .map_or(name, |(source_name, _)| source_name.as_str());
+    Ok(source_name.to_owned())
+}
+
+/// Follow `[source.<name>] replace-with` entries to the source that crates actually come from.
+fn replaced_source_name(
+    cargo_config: &cargo_config2::Config,
+    source_name: &str,
+) -> anyhow::Result<String> {
+    let mut current = source_name.to_owned();
+    let mut seen = BTreeSet::new();
+    while let Some(replace_with) = cargo_config
+        .source
+        .get(&current)
+        .and_then(|source| source.replace_with.as_deref())
+    {
+        if !seen.insert(current.clone()) {
+            bail!("cargo config `[source]` replacement of `{source_name}` contains a cycle");
+        }
+        current = replace_with.to_owned();
+    }
+    Ok(current)
+}
+
+/// The index URL of a registry from `[registries]`, which may be overridden
+/// through a `CARGO_REGISTRIES_<NAME>_INDEX` environment variable.
+fn registry_index_url(cargo_config: &cargo_config2::Config, name: &str) -> anyhow::Result<String> {
+    let env_var = format!(
+        "CARGO_REGISTRIES_{}_INDEX",
+        name.to_ascii_uppercase().replace('-', "_")
+    );
+    if let Ok(url) = std::env::var(&env_var) {
+        return Ok(url);
+    }
+
+    cargo_config
+        .registries
+        .get(name)
+        .and_then(|registry| registry.index.clone())
+        .with_context(|| {
+            format!(
+                "registry `{name}` is not defined in cargo config; \
+                 add its index with `[registries.{name}] index = \"...\"` \
+                 or set {env_var}"
+            )
+        })
+}
+
+fn open_sparse_index(index_url: tame_index::IndexUrl<'_>) -> anyhow::Result<IndexKind> {
+    let url = index_url.as_str().to_owned();
+    let location = tame_index::IndexLocation::new(index_url);
+
+    // reqwest uses rustls-no-provider; install a ring provider once if needed.
+    if rustls::crypto::CryptoProvider::get_default().is_none() {
+        let _ = rustls::crypto::ring::default_provider().install_default();
+    }
+
+    let sparse = tame_index::index::SparseIndex::new(location)
+        .with_context(|| format!("failed to open sparse index `{url}`"))?;
+    let client = tame_index::external::reqwest::blocking::Client::builder()
+        .build()
+        .context("failed to build HTTP client")?;
+    Ok(IndexKind::Sparse(
+        tame_index::index::RemoteSparseIndex::new(sparse, client),
+    ))
+}
+
+/// Build index entries for a crate from a directory of vendored crates.
+///
+/// Vendored crates have normalized manifests, so the package's name, version, features,
+/// and optional dependencies can be read directly from them.
+pub(crate) fn vendored_krate(dir: &Path, name: &str) -> anyhow::Result<Option<IndexKrate>> {
+    let mut versions = Vec::new();
+    for entry in fs_err::read_dir(dir)? {
+        let manifest_path = entry?.path().join("Cargo.toml");
+        if !manifest_path.is_file() {
+            continue;
+        }
+        let manifest: toml::Table = toml::from_str(&fs_err::read_to_string(&manifest_path)?)
+            .with_context(|| format!("failed to parse {}", manifest_path.display()))?;
+        let Some(package) = manifest.get("package").and_then(toml::Value::as_table) else {
+            continue;
+        };
+        if package.get("name").and_then(toml::Value::as_str) != Some(name) {
+            continue;
+        }
+        let version = package
+            .get("version")
+            .and_then(toml::Value::as_str)
+            .with_context(|| format!("no package version in {}", manifest_path.display()))?;
+
+        // Optional dependencies may also be declared per target, like
+        // `[target.'cfg(unix)'.dependencies]`, and still enable implicit features.
+        let target_dependencies = manifest
+            .get("target")
+            .and_then(toml::Value::as_table)
+            .into_iter()
+            .flatten()
+            .filter_map(|(target, table)| {
+                let dependencies = table.get("dependencies")?.as_table()?;
+                Some((Some(target.as_str()), dependencies))
+            });
+        let optional_dependencies: Vec<_> = manifest
+            .get("dependencies")
+            .and_then(toml::Value::as_table)
+            .map(|dependencies| (None, dependencies))
+            .into_iter()
+            .chain(target_dependencies)
+            .flat_map(|(target, dependencies)| {
+                dependencies
+                    .iter()
+                    .map(move |(dependency, spec)| (target, dependency, spec))
+            })
+            .filter(|(_, _, spec)| {
+                spec.get("optional").and_then(toml::Value::as_bool) == Some(true)
+            })
+            .map(|(target, dependency, _)| {
+                serde_json::json!({
+                    "name": dependency,
+                    "req": "*",
+                    "features": [],
+                    "optional": true,
+                    "default_features": true,
+                    "target": target,
+                    "kind": "normal",
+                })
+            })
+            .collect();
+
+        let features = manifest
+            .get("features")
+            .cloned()
+            .unwrap_or_else(|| toml::Value::Table(toml::Table::new()));
+        let index_entry = serde_json::json!({
+            "name": name,
+            "vers": version,
+            "deps": optional_dependencies,
+            "features": features,
+            "cksum": "0".repeat(64),
+            "yanked": false,
+        });
+        let index_version: IndexVersion = serde_json::from_value(index_entry)
+            .with_context(|| format!("failed to read {}", manifest_path.display()))?;
+        versions.push(index_version);
+    }
+
+    Ok((!versions.is_empty()).then_some(IndexKrate { versions }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use tame_index::index::FileLock;
+
+    use super::{RegistryIndex, vendored_krate};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "cargo-semver-checks-{label}-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ))
+    }
+
+    fn write_file(path: &Path, contents: &str) {
+        fs_err::create_dir_all(path.parent().expect("path has a parent"))
+            .expect("failed to create dir");
+        fs_err::write(path, contents).expect("failed to write file");
+    }
+
+    /// Open a registry index as configured by a `.cargo/config.toml` in `dir`.
+    fn open_in(dir: &Path, name: Option<&str>) -> RegistryIndex {
+        let cargo_config =
+            cargo_config2::Config::load_with_cwd(dir).expect("failed to load cargo config");
+        RegistryIndex::open_with_config(&cargo_config, name, true)
+            .expect("failed to open registry index")
+    }
+
+    #[test]
+    fn crates_io_replaced_with_vendored_crates() {
+        let dir = temp_dir("replaced-crates-io");
+        write_file(
+            &dir.join(".cargo/config.toml"),
+            r#"
+            [source.crates-io]
+            replace-with = "vendored-sources"
+
+            [source.vendored-sources]
+            directory = "vendor"
+            "#,
+        );
+        write_file(
+            &dir.join("vendor/demo/Cargo.toml"),
+            r#"
+            [package]
+            name = "demo"
+            version = "0.3.1"
+            "#,
+        );
+
+        let index = open_in(&dir, None);
+        assert!(!index.is_cached_only());
+        let krate = index
+            .krate("demo", &FileLock::unlocked())
+            .expect("failed to read vendored crates")
+            .expect("demo is vendored");
+        assert_eq!(krate.versions[0].version.as_str(), "0.3.1");
+        assert!(
+            index
+                .not_found_message("missing")
+                .starts_with("missing not found in vendored crates at ")
+        );
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn named_registry_replaced_with_local_registry() {
+        let dir = temp_dir("local-registry");
+        write_file(
+            &dir.join(".cargo/config.toml"),
+            r#"
+            [registries.internal]
+            index = "sparse+https://registry.invalid/index/"
+
+            [source.internal-index]
+            registry = "sparse+https://registry.invalid/index/"
+            replace-with = "mirror"
+
+            [source.mirror]
+            replace-with = "on-disk"
+
+            [source.on-disk]
+            local-registry = "registry"
+            "#,
+        );
+        write_file(
+            &dir.join("registry/index/de/mo/demo"),
+            concat!(
+                r#"{"name":"demo","vers":"1.0.0","deps":[],"features":{},"cksum":""#,
+                "0000000000000000000000000000000000000000000000000000000000000000",
+                r#"","yanked":false}"#,
+                "\n",
+            ),
+        );
+
+        let index = open_in(&dir, Some("internal"));
+        assert_eq!(index.name(), Some("internal"));
+        let krate = index
+            .krate("demo", &FileLock::unlocked())
+            .expect("failed to read local registry")
+            .expect("demo is in the local registry");
+        assert_eq!(krate.versions[0].version.as_str(), "1.0.0");
+        assert!(
+            index
+                .krate("other", &FileLock::unlocked())
+                .expect("failed to read local registry")
+                .is_none()
+        );
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn source_replacement_cycles_are_rejected() {
+        let dir = temp_dir("replacement-cycle");
+        write_file(
+            &dir.join(".cargo/config.toml"),
+            r#"
+            [source.crates-io]
+            replace-with = "mirror"
+
+            [source.mirror]
+            replace-with = "crates-io"
+            "#,
+        );
+
+        let cargo_config =
+            cargo_config2::Config::load_with_cwd(&dir).expect("failed to load cargo config");
+        let err = RegistryIndex::open_with_config(&cargo_config, None, true)
+            .expect_err("a replacement cycle can't be resolved");
+        assert!(err.to_string().contains("contains a cycle"), "{err}");
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn vendored_crates_become_index_entries() {
+        let dir = temp_dir("vendored");
+        for (subdir, manifest) in [
+            (
+                "serde",
+                r#"
+                [package]
+                name = "serde"
+                version = "1.0.200"
+
+                [features]
+                default = ["std"]
+                std = []
+
+                [dependencies.serde_derive]
+                version = "1"
+                optional = true
+
+                [target.'cfg(unix)'.dependencies.libc]
+                version = "0.2"
+                optional = true
+                "#,
+            ),
+            (
+                "syn",
+                r#"
+                [package]
+                name = "syn"
+                version = "2.0.0"
+                "#,
+            ),
+        ] {
+            fs_err::create_dir_all(dir.join(subdir)).expect("failed to create vendored crate");
+            fs_err::write(dir.join(subdir).join("Cargo.toml"), manifest)
+                .expect("failed to write vendored manifest");
+        }
+
+        let krate = vendored_krate(&dir, "serde")
+            .expect("failed to read vendored crates")
+            .expect("serde is vendored");
+        assert_eq!(krate.versions.len(), 1);
+        let version = &krate.versions[0];
+        assert_eq!(version.version.as_str(), "1.0.200");
+        assert!(version.features().any(|(feature, _)| feature == "std"));
+        assert!(
+            version
+                .dependencies()
+                .iter()
+                .any(|dep| dep.is_optional() && dep.name.as_str() == "serde_derive")
+        );
+        assert!(version.dependencies().iter().any(|dep| {
+            dep.is_optional()
+                && dep.name.as_str() == "libc"
+                && dep.target.as_deref().map(|target| target.as_str()) == Some("cfg(unix)")
+        }));
+
+        assert!(
+            vendored_krate(&dir, "tokio")
+                .expect("failed to read vendored crates")
+                .is_none()
+        );
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+}
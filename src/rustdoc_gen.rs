@@ -1,25 +1,35 @@
 use std::borrow::Cow;
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::path::PathBuf;
 
 use anyhow::{Context as _, bail};
 use itertools::Itertools;
 use serde::Serialize;
 use tame_index::IndexKrate;
-use trustfall_rustdoc::VersionedStorage;
 
 use crate::GlobalConfig;
-use crate::data_generation::{CrateDataRequest, IntoTerminalResult as _, TerminalError};
+use crate::data_generation::{
+    self, BuildFlags, CrateDataRequest, IntoTerminalResult as _, LoadedRustdoc, RustdocJsonFile,
+    TerminalError,
+};
 use crate::manifest::Manifest;
+use crate::registry::RegistryIndex;
+use crate::timings::{CrateTimings, TimingPhase};
 use crate::util::atomic_write;
 
 #[derive(Debug, Clone)]
 pub(crate) enum CrateSource<'a> {
     Registry {
         versioned_krate: &'a tame_index::IndexVersion,
+        /// The `[registries]` name of the registry, or `None` for crates.io.
+        registry: Option<&'a str>,
+        /// A directory of vendored crates that the registry is replaced with, if any.
+        vendored_crates: Option<&'a std::path::Path>,
     },
     ManifestPath {
         manifest: &'a Manifest,
+        /// An identifier of the exact contents of the sources, such as a git tree id, if known.
+        source_tree: Option<&'a str>,
     },
 }
 
@@ -35,7 +45,9 @@ impl CrateSource<'_> {
                 .map(|(k, _v)| k)
                 .cloned()
                 .collect(),
-            Self::ManifestPath { manifest } => manifest.parsed.features.keys().cloned().collect(),
+            Self::ManifestPath { manifest, .. } => {
+                manifest.parsed.features.keys().cloned().collect()
+            }
         }
     }
 
@@ -51,7 +63,7 @@ impl CrateSource<'_> {
                 .filter(|dep| dep.is_optional())
                 .map(|dep| dep.name.to_string())
                 .collect(),
-            Self::ManifestPath { manifest } => {
+            Self::ManifestPath { manifest, .. } => {
                 let mut dependencies = manifest.parsed.dependencies.clone();
                 for target in manifest.parsed.target.values() {
                     // Fixes https://github.com/obi1kenobi/cargo-semver-checks/issues/369
@@ -77,7 +89,7 @@ impl CrateSource<'_> {
             Self::Registry {
                 versioned_krate, ..
             } => versioned_krate.features().flat_map(|(_k, v)| v).collect(),
-            Self::ManifestPath { manifest } => {
+            Self::ManifestPath { manifest, .. } => {
                 manifest.parsed.features.values().flatten().collect()
             }
         };
@@ -255,6 +267,10 @@ pub(crate) struct CrateDataForRustdoc<'a> {
     pub(crate) name: String,
     pub(crate) feature_config: &'a FeatureConfig,
     pub(crate) build_target: Option<&'a str>,
+    /// The rustup toolchain to generate rustdoc with, if not the default one.
+    pub(crate) toolchain: Option<&'a str>,
+    /// The lockfile to seed dependency resolution with, if any.
+    pub(crate) lockfile: Option<&'a std::path::Path>,
 }
 
 pub(crate) fn generate_data_request<'a>(
@@ -274,7 +290,9 @@ pub(crate) fn generate_data_request<'a>(
 
     match crate_source {
         CrateSource::Registry {
-            versioned_krate, ..
+            versioned_krate,
+            registry,
+            vendored_crates,
         } => CrateDataRequest::from_index(
             versioned_krate,
             default_features,
@@ -284,8 +302,15 @@ pub(crate) fn generate_data_request<'a>(
                 crate_data.crate_type,
                 crate::rustdoc_gen::CrateType::Baseline { .. }
             ),
-        ),
-        CrateSource::ManifestPath { manifest } => CrateDataRequest::from_local_project(
+        )
+        .with_registry(registry)
+        .with_vendored_crates(vendored_crates)
+        .with_toolchain(crate_data.toolchain)
+        .with_lockfile(crate_data.lockfile),
+        CrateSource::ManifestPath {
+            manifest,
+            source_tree,
+        } => CrateDataRequest::from_local_project(
             manifest,
             default_features,
             extra_features,
@@ -294,7 +319,10 @@ pub(crate) fn generate_data_request<'a>(
                 crate_data.crate_type,
                 crate::rustdoc_gen::CrateType::Baseline { .. }
             ),
-        ),
+        )
+        .with_source_tree(source_tree)
+        .with_toolchain(crate_data.toolchain)
+        .with_lockfile(crate_data.lockfile),
     }
 }
 
@@ -306,6 +334,9 @@ where
         TerminalError::WithAdvice(err, advice) => {
             TerminalError::WithAdvice(err.context(context), advice)
         }
+        TerminalError::BuildFailure(kind, err, advice) => {
+            TerminalError::BuildFailure(kind, err.context(context), advice)
+        }
         TerminalError::Other(err) => TerminalError::Other(err.context(context)),
     }
 }
@@ -316,11 +347,12 @@ pub(crate) fn generate_rustdoc(
     cache_settings: super::data_generation::CacheSettings<()>,
     target_root: PathBuf,
     data_request: &CrateDataRequest<'_>,
-) -> Result<VersionedStorage, TerminalError> {
+    timings: &mut CrateTimings,
+) -> Result<LoadedRustdoc, TerminalError> {
     let cache_dir = target_root.join("cache");
     let cache_settings = cache_settings.with_path(cache_dir.as_path());
 
-    let mut callbacks = crate::callbacks::Callbacks::new(config);
+    let mut callbacks = crate::callbacks::Callbacks::new(config, timings);
     data_request.resolve(
         &target_root,
         cache_settings,
@@ -329,13 +361,94 @@ pub(crate) fn generate_rustdoc(
     )
 }
 
+/// Generate the rustdoc of several crates' registry baselines together, sharing the build
+/// of their dependencies, and save it to the rustdoc cache that [`generate_rustdoc`] reads.
+///
+/// This only makes checking faster: any rustdoc that isn't generated here,
+/// such as when this fails, is generated separately for each crate as usual.
+pub(crate) fn prebuild_registry_baselines(
+    config: &mut GlobalConfig,
+    generation_settings: &super::data_generation::GenerationSettings,
+    generator: &RustdocGenerator,
+    crate_data: &[CrateDataForRustdoc<'_>],
+) -> anyhow::Result<()> {
+    let RustdocGenerator::Registry(registry_generator) = generator else {
+        return Ok(());
+    };
+
+    // Looking up the baselines again when each crate is checked repeats any warnings,
+    // and any errors, so they're discarded here.
+    let mut lookup_config = config.worker_config().into_captured(
+        crate::config::CapturedOutput::new(),
+        crate::config::CapturedOutput::new(),
+    );
+    let coupled = crate_data
+        .iter()
+        .filter_map(|crate_data| {
+            StatefulRustdocGenerator::couple_data(generator, &mut lookup_config, crate_data).ok()
+        })
+        .collect_vec();
+    let ready = coupled
+        .iter()
+        .filter_map(|coupled| coupled.prepare_generator(&mut lookup_config).ok())
+        .collect_vec();
+    let requests = ready
+        .iter()
+        .filter_map(|ready| ready.get_data_request())
+        .collect_vec();
+
+    let target_root = &registry_generator.target_root;
+    let start = std::time::Instant::now();
+    let prebuilt = super::data_generation::prebuild_registry_baselines(
+        target_root,
+        &target_root.join("cache"),
+        &requests,
+        generation_settings,
+        &mut |crates| {
+            // Ignore terminal printing failures.
+            let _ =
+                config.shell_status("Building", format_args!("{} (baseline)", crates.join(", ")));
+        },
+    )?;
+    if !prebuilt.is_empty() {
+        config.shell_status(
+            "Built",
+            format_args!("[{:>8.3}s] (baseline)", start.elapsed().as_secs_f32()),
+        )?;
+    }
+    Ok(())
+}
+
 pub(crate) enum RustdocGenerator {
     File(RustdocFromFile),
     ProjectRoot(RustdocFromProjectRoot),
     GitRevision(RustdocFromGitRevision),
+    CrateFile(RustdocFromCrateFile),
     Registry(RustdocFromRegistry),
 }
 
+impl RustdocGenerator {
+    /// The registry releases that a crate version must stay compatible with:
+    /// the non-yanked, non-pre-release versions in its semver-compatible series,
+    /// up to and including `version_current`, oldest first.
+    ///
+    /// Empty if this generator doesn't look up a registry, or is set to a specific version.
+    pub(crate) fn compatible_registry_versions(
+        &self,
+        config: &mut GlobalConfig,
+        crate_data: &CrateDataForRustdoc<'_>,
+        version_current: &semver::Version,
+    ) -> Result<Vec<semver::Version>, TerminalError> {
+        match self {
+            Self::Registry(generator) if generator.version.is_none() => {
+                let krate = generator.get_krate(config, crate_data)?;
+                Ok(compatible_baseline_versions(&krate, version_current))
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
 impl From<RustdocFromFile> for RustdocGenerator {
     fn from(value: RustdocFromFile) -> Self {
         Self::File(value)
@@ -354,6 +467,12 @@ impl From<RustdocFromGitRevision> for RustdocGenerator {
     }
 }
 
+impl From<RustdocFromCrateFile> for RustdocGenerator {
+    fn from(value: RustdocFromCrateFile) -> Self {
+        Self::CrateFile(value)
+    }
+}
+
 impl From<RustdocFromRegistry> for RustdocGenerator {
     fn from(value: RustdocFromRegistry) -> Self {
         Self::Registry(value)
@@ -377,6 +496,10 @@ pub(crate) enum CoupledState<'a> {
     GitRevision {
         generator: &'a RustdocFromGitRevision,
     },
+    // CrateFile variant exists purely for improved errors
+    CrateFile {
+        generator: &'a RustdocFromCrateFile,
+    },
     // Registry requests need a list of crate versions to query
     Registry {
         generator: &'a RustdocFromRegistry,
@@ -420,6 +543,8 @@ impl<'a> StatefulRustdocGenerator<'a, CoupledState<'a>> {
 
             RustdocGenerator::GitRevision(generator) => CoupledState::GitRevision { generator },
 
+            RustdocGenerator::CrateFile(generator) => CoupledState::CrateFile { generator },
+
             RustdocGenerator::Registry(generator) => {
                 let krate = generator.get_krate(config, crate_data).map_err(|err| {
                     terminal_context(
@@ -464,6 +589,12 @@ impl<'a> StatefulRustdocGenerator<'a, CoupledState<'a>> {
                 })?;
                 (source, &generator.path.target_root)
             }
+            CoupledState::CrateFile { generator } => {
+                let source = generator.get_crate_source(crate_data).map_err(|err| {
+                    terminal_context(err, "failed to retrieve local crate data from crate file")
+                })?;
+                (source, &generator.target_root)
+            }
             CoupledState::Registry { generator, krate } => {
                 let source = generator
                     .get_crate_source(crate_data, krate)
@@ -495,15 +626,34 @@ impl<'a> StatefulRustdocGenerator<'a, ReadyState<'a>> {
         }
     }
 
-    /// Load rustdoc from this generator into a [`VersionedStorage`]
+    /// Get the path of the rustdoc JSON file this generator loads, if it loads one
+    /// instead of generating rustdoc.
+    pub(crate) fn rustdoc_file(&self) -> Option<&std::path::Path> {
+        match &self.coupled_state {
+            ReadyState::File { generator } => Some(&generator.path),
+            ReadyState::Generator { .. } => None,
+        }
+    }
+
+    /// Load rustdoc from this generator into a [`LoadedRustdoc`]
     pub(crate) fn load_rustdoc(
         &self,
         config: &mut GlobalConfig,
         generation_settings: super::data_generation::GenerationSettings,
         cache_settings: super::data_generation::CacheSettings<()>,
-    ) -> Result<VersionedStorage, TerminalError> {
+        timings: &mut CrateTimings,
+    ) -> Result<LoadedRustdoc, TerminalError> {
         match &self.coupled_state {
-            ReadyState::File { generator } => generator.load_rustdoc(),
+            ReadyState::File { generator } => {
+                let start = std::time::Instant::now();
+                let data = generator.load_rustdoc(generation_settings.fingerprint_api)?;
+                let kind = match self.crate_data.crate_type {
+                    CrateType::Current => "current",
+                    CrateType::Baseline { .. } => "baseline",
+                };
+                timings.record(TimingPhase::ParseRustdoc, kind, start.elapsed());
+                Ok(data)
+            }
 
             ReadyState::Generator {
                 target_root,
@@ -514,6 +664,7 @@ impl<'a> StatefulRustdocGenerator<'a, ReadyState<'a>> {
                 cache_settings,
                 target_root.to_path_buf(),
                 data_request,
+                timings,
             ),
         }
     }
@@ -522,17 +673,38 @@ impl<'a> StatefulRustdocGenerator<'a, ReadyState<'a>> {
 #[derive(Debug)]
 pub(crate) struct RustdocFromFile {
     path: PathBuf,
+
+    /// Where a compressed file is decompressed to while it's loaded.
+    target_root: PathBuf,
 }
 
 impl RustdocFromFile {
-    pub(crate) fn new(path: PathBuf) -> Self {
-        Self { path }
+    pub(crate) fn new(path: PathBuf, target_root: PathBuf) -> Self {
+        Self { path, target_root }
     }
 
-    pub(crate) fn load_rustdoc(&self) -> Result<VersionedStorage, TerminalError> {
-        trustfall_rustdoc::load_rustdoc(&self.path, None)
-            .with_context(|| format!("failed to load rustdoc from file at `{:?}`", self.path))
-            .into_terminal_result()
+    pub(crate) fn load_rustdoc(
+        &self,
+        fingerprint_api: bool,
+    ) -> Result<LoadedRustdoc, TerminalError> {
+        // The file may be zstd-compressed, e.g. when archived as a CI artifact.
+        let json = RustdocJsonFile::open(&self.path, &self.target_root)
+            .with_context(|| format!("failed to read rustdoc from file at `{:?}`", self.path))
+            .into_terminal_result()?;
+        let (storage, api_fingerprint) =
+            data_generation::load_rustdoc(json.path(), None, fingerprint_api)
+                .with_context(|| format!("failed to load rustdoc from file at `{:?}`", self.path))
+                .into_terminal_result()?;
+
+        // The file loaded successfully, so it's unlikely that fingerprinting it fails.
+        // If it does, we simply won't be able to skip the check when nothing changed.
+        let api_fingerprint = api_fingerprint.and_then(Result::ok);
+        let build_flags = BuildFlags::read_sidecar(&self.path).into_terminal_result()?;
+        Ok(LoadedRustdoc {
+            storage,
+            api_fingerprint,
+            build_flags,
+        })
     }
 }
 
@@ -543,6 +715,9 @@ pub(crate) struct RustdocFromProjectRoot {
     manifest_errors: HashMap<PathBuf, anyhow::Error>,
     duplicate_packages: HashMap<String, Vec<PathBuf>>,
     target_root: PathBuf,
+    /// An identifier of the exact contents of the project's sources, such as a git tree id,
+    /// if known. Rustdoc for such sources can be cached, since the identifier pins them down.
+    source_tree: Option<String>,
 }
 
 impl RustdocFromProjectRoot {
@@ -617,9 +792,28 @@ impl RustdocFromProjectRoot {
             manifest_errors,
             duplicate_packages,
             target_root: target_root.to_owned(),
+            source_tree: None,
         })
     }
 
+    /// If the project is in a git repository whose working tree matches the `HEAD` commit,
+    /// record an identifier of its sources based on that commit's tree, so its rustdoc can be cached.
+    pub(crate) fn with_clean_worktree_source_tree(mut self, config: &mut GlobalConfig) -> Self {
+        match clean_worktree_source_tree(&self.project_root, self.manifests.values()) {
+            Ok(source_tree) => self.source_tree = source_tree,
+            Err(e) => {
+                // Ignore terminal printing failures.
+                let _ = config.log_verbose(|config| {
+                    config.shell_note(format_args!(
+                        "not caching rustdoc for local project, \
+                         failed to check whether its git working tree is clean: {e:#}"
+                    ))
+                });
+            }
+        }
+        self
+    }
+
     fn get_crate_source(
         &self,
         crate_data: &CrateDataForRustdoc<'_>,
@@ -653,7 +847,10 @@ impl RustdocFromProjectRoot {
             }
         }).into_terminal_result()?;
 
-        Ok(CrateSource::ManifestPath { manifest })
+        Ok(CrateSource::ManifestPath {
+            manifest,
+            source_tree: self.source_tree.as_deref(),
+        })
     }
 }
 
@@ -679,7 +876,8 @@ impl RustdocFromGitRevision {
         fs_err::create_dir_all(&tree_dir)?;
         extract_tree(tree_id, &tree_dir)?;
 
-        let path = RustdocFromProjectRoot::new(&tree_dir, target)?;
+        let mut path = RustdocFromProjectRoot::new(&tree_dir, target)?;
+        path.source_tree = Some(tree_id.to_string());
         Ok(Self { path })
     }
 
@@ -697,6 +895,170 @@ impl RustdocFromGitRevision {
     }
 }
 
+/// Generates rustdoc from a packaged `.crate` file, such as one made by `cargo package`.
+///
+/// The package is unpacked as a vendored crate, which cargo is configured to use
+/// in place of a dedicated registry, so it's built just like a crate downloaded from one.
+#[derive(Debug)]
+pub(crate) struct RustdocFromCrateFile {
+    target_root: PathBuf,
+    /// The directory the package is unpacked into, in the layout of `cargo vendor`.
+    vendor_dir: PathBuf,
+    package_name: String,
+    krate: IndexKrate,
+}
+
+/// The `[registries]` name that cargo looks up crates from `.crate` files in.
+const CRATE_FILE_REGISTRY: &str = "semver-checks-crate-file";
+
+impl RustdocFromCrateFile {
+    pub(crate) fn new(
+        crate_file: &std::path::Path,
+        target: &std::path::Path,
+        config: &mut GlobalConfig,
+    ) -> anyhow::Result<Self> {
+        // The package's contents never change, so the file's hash identifies the sources
+        // well enough to cache their rustdoc, and to reuse them once unpacked.
+        let contents_hash = {
+            use sha2::Digest as _;
+            use std::io::Read as _;
+
+            let mut hasher = sha2::Sha256::new();
+            let mut file = fs_err::File::open(crate_file)?;
+            let mut buffer = vec![0; 64 * 1024];
+            loop {
+                let read = file.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+            hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>()
+        };
+        let vendor_dir =
+            std::path::absolute(target.join(format!("crate-{}", &contents_hash[..16])))?;
+
+        let package_dir = match unpacked_package_dir(&vendor_dir)? {
+            Some(package_dir) => package_dir,
+            None => {
+                config.shell_status("Unpacking", crate_file.display())?;
+                unpack_crate_file(crate_file, &contents_hash, &vendor_dir)?
+            }
+        };
+
+        let manifest = Manifest::parse_standalone(package_dir.join("Cargo.toml"))?;
+        let package_name = crate::manifest::get_package_name(&manifest)?.to_owned();
+        let krate =
+            crate::registry::vendored_krate(&vendor_dir, &package_name)?.with_context(|| {
+                format!(
+                    "failed to read package {package_name} from crate file {}",
+                    crate_file.display()
+                )
+            })?;
+
+        Ok(Self {
+            target_root: target.to_owned(),
+            vendor_dir,
+            package_name,
+            krate,
+        })
+    }
+
+    pub(crate) fn get_crate_source(
+        &self,
+        crate_data: &CrateDataForRustdoc<'_>,
+    ) -> Result<CrateSource<'_>, TerminalError> {
+        if crate_data.name != self.package_name {
+            return Err(TerminalError::Other(anyhow::format_err!(
+                "package `{}` not found in crate file, which contains package `{}`",
+                crate_data.name,
+                self.package_name,
+            )));
+        }
+
+        Ok(CrateSource::Registry {
+            versioned_krate: &self.krate.versions[0],
+            registry: Some(CRATE_FILE_REGISTRY),
+            vendored_crates: Some(&self.vendor_dir),
+        })
+    }
+}
+
+/// The package directory in `vendor_dir` of a `.crate` file that was unpacked earlier.
+fn unpacked_package_dir(vendor_dir: &std::path::Path) -> anyhow::Result<Option<PathBuf>> {
+    if !vendor_dir.is_dir() {
+        return Ok(None);
+    }
+    // The checksum file is written last, so its presence means unpacking completed.
+    let package_dir = fs_err::read_dir(vendor_dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter_ok(|path| path.join(".cargo-checksum.json").is_file())
+        .next()
+        .transpose()?;
+    Ok(package_dir)
+}
+
+/// Unpack a `.crate` file into `vendor_dir` in the layout of `cargo vendor`,
+/// returning the directory of its package.
+fn unpack_crate_file(
+    crate_file: &std::path::Path,
+    contents_hash: &str,
+    vendor_dir: &std::path::Path,
+) -> anyhow::Result<PathBuf> {
+    // Unpack next to the final location first, so concurrent runs never see a partial package.
+    let unpack_dir = vendor_dir.with_extension(format!("tmp-{:032x}", rand::random::<u128>()));
+    fs_err::create_dir_all(&unpack_dir)?;
+    let result = (|| {
+        let crate_file_reader = std::io::BufReader::new(fs_err::File::open(crate_file)?);
+        // `unpack` refuses to write outside `unpack_dir`, even for malicious archives.
+        tar::Archive::new(flate2::read::GzDecoder::new(crate_file_reader))
+            .unpack(&unpack_dir)
+            .with_context(|| format!("failed to unpack crate file {}", crate_file.display()))?;
+
+        // Packaged crates contain a single `{name}-{version}` directory with the sources.
+        let mut package_dirs = fs_err::read_dir(&unpack_dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .filter_ok(|path| path.join("Cargo.toml").is_file())
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let package_dir = match package_dirs.len() {
+            1 => package_dirs.pop().expect("length was checked"),
+            _ => bail!(
+                "crate file {} doesn't contain exactly one package; \
+                 was it made by `cargo package`?",
+                crate_file.display()
+            ),
+        };
+
+        // Cargo checks vendored crates against the checksum of the package they came from.
+        let checksum = serde_json::json!({ "files": {}, "package": contents_hash });
+        fs_err::write(
+            package_dir.join(".cargo-checksum.json"),
+            serde_json::to_vec(&checksum)?,
+        )?;
+
+        let file_name = package_dir
+            .file_name()
+            .expect("package dir has a file name");
+        let final_dir = vendor_dir.join(file_name);
+        fs_err::create_dir_all(vendor_dir)?;
+        if let Err(err) = std::fs::rename(&package_dir, &final_dir) {
+            // Another run may have finished unpacking the same file first.
+            if !final_dir.join(".cargo-checksum.json").is_file() {
+                return Err(err).with_context(|| {
+                    format!("failed to move unpacked crate to {}", final_dir.display())
+                });
+            }
+        }
+        Ok(final_dir)
+    })();
+    let _: std::io::Result<()> = std::fs::remove_dir_all(&unpack_dir);
+    result
+}
+
 fn extract_tree(tree: gix::Id<'_>, target: &std::path::Path) -> anyhow::Result<()> {
     for entry in tree.object()?.try_into_tree()?.iter() {
         let entry = entry?;
@@ -725,6 +1087,315 @@ fn extract_tree(tree: gix::Id<'_>, target: &std::path::Path) -> anyhow::Result<(
     Ok(())
 }
 
+/// Returns an identifier of the sources of the project at `project_root` if the git working
+/// tree containing it matches `HEAD`: no tracked file is modified and no untracked file is present.
+///
+/// Files that git ignores aren't part of `HEAD`'s tree, yet may still be build inputs.
+/// An ignored `Cargo.lock` or `.cargo/config.toml` in the directories of the local packages
+/// or their ancestors is hashed into the identifier alongside `HEAD`'s tree id. Any other
+/// ignored file in a package directory, like an `include!`d generated file, may be read
+/// by the build in ways we can't tell, so the sources get no identifier. Cargo's own
+/// build directories are skipped, since they're build outputs.
+///
+/// Returns `None` if the project isn't in a git repository, its working tree is dirty,
+/// it has ignored files that may be build inputs, or it has path dependencies
+/// outside the repository, whose contents git can't vouch for.
+fn clean_worktree_source_tree<'a>(
+    project_root: &std::path::Path,
+    manifests: impl IntoIterator<Item = &'a Manifest>,
+) -> anyhow::Result<Option<String>> {
+    let Ok(repo) = gix::ThreadSafeRepository::discover_with_environment_overrides(project_root)
+        .map(gix::Repository::from)
+    else {
+        return Ok(None);
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(None);
+    };
+    let workdir = fs_err::canonicalize(workdir)?;
+    // Fails if `HEAD` is unborn, in which case there's nothing to match.
+    let Ok(tree_id) = repo.rev_parse_single("HEAD^{tree}") else {
+        return Ok(None);
+    };
+    // Without an index, there's no cheap way to tell whether the working tree is modified.
+    let Some(index) = repo.try_index()? else {
+        return Ok(None);
+    };
+
+    if !worktree_matches_index(tree_id, &index, &workdir)? {
+        return Ok(None);
+    }
+
+    let tracked_files: HashSet<&gix::bstr::BStr> = index
+        .entries()
+        .iter()
+        .map(|entry| entry.path(&index))
+        .collect();
+    let is_tracked = |path: &std::path::Path| {
+        path.strip_prefix(&workdir).is_ok_and(|relative| {
+            let relative = gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative));
+            tracked_files.contains(relative.as_ref())
+        })
+    };
+
+    // All tracked files are unmodified, so any other non-ignored file is untracked.
+    for entry in ignore::WalkBuilder::new(&workdir)
+        .hidden(false)
+        .ignore(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build()
+    {
+        let entry = entry?;
+        if entry
+            .file_type()
+            .is_some_and(|file_type| file_type.is_file())
+            && !is_tracked(entry.path())
+        {
+            return Ok(None);
+        }
+    }
+
+    let Some(package_dirs) = local_package_dirs(&workdir, manifests)? else {
+        return Ok(None);
+    };
+
+    // Cargo reads the lockfile and configuration of the package directories
+    // and the directories enclosing them.
+    const CARGO_INPUTS: [&str; 3] = ["Cargo.lock", ".cargo/config.toml", ".cargo/config"];
+    let mut ignored_inputs = BTreeSet::new();
+    for dir in &package_dirs {
+        for ancestor in dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(&workdir))
+        {
+            for input in CARGO_INPUTS {
+                let path = ancestor.join(input);
+                if path.is_file() && !is_tracked(&path) {
+                    ignored_inputs.insert(path);
+                }
+            }
+        }
+    }
+
+    // Every non-ignored file is tracked by now, so any file that isn't is ignored by git.
+    for dir in &package_dirs {
+        for entry in ignore::WalkBuilder::new(dir)
+            .standard_filters(false)
+            .filter_entry(|entry| entry.file_name() != ".git" && !is_cargo_target_dir(entry))
+            .build()
+        {
+            let entry = entry?;
+            if entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_file())
+                && !is_tracked(entry.path())
+                && !ignored_inputs.contains(entry.path())
+            {
+                return Ok(None);
+            }
+        }
+    }
+
+    if ignored_inputs.is_empty() {
+        return Ok(Some(tree_id.to_string()));
+    }
+
+    use sha2::Digest as _;
+    let mut hasher = sha2::Sha256::new();
+    for path in ignored_inputs {
+        let relative = path.strip_prefix(&workdir).unwrap_or(&path);
+        let relative = gix::path::into_bstr(relative);
+        let contents = fs_err::read(&path)?;
+        // Length-prefix each field so adjacent files cannot collide by concatenating ambiguously.
+        for field in [relative.as_ref(), contents.as_slice()] {
+            hasher.update(field.len().to_le_bytes());
+            hasher.update(field);
+        }
+    }
+    let ignored_inputs_hash: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+
+    Ok(Some(format!("{tree_id}+{ignored_inputs_hash}")))
+}
+
+/// Checks that the index matches `tree`, and that the working tree matches the index.
+///
+/// Like `git status`, this trusts files whose stat information matches the index entry,
+/// and only reads and hashes the files that were touched since they were last staged.
+fn worktree_matches_index(
+    tree: gix::Id<'_>,
+    index: &gix::index::File,
+    workdir: &std::path::Path,
+) -> anyhow::Result<bool> {
+    let mut tree_blobs = HashMap::new();
+    if !collect_tree_blobs(tree, Default::default(), &mut tree_blobs)?
+        || tree_blobs.len() != index.entries().len()
+    {
+        return Ok(false);
+    }
+
+    let stat_options = gix::index::entry::stat::Options::default();
+    for entry in index.entries() {
+        let path = entry.path(index);
+        let executable = if entry.mode == gix::index::entry::Mode::FILE {
+            false
+        } else if entry.mode == gix::index::entry::Mode::FILE_EXECUTABLE {
+            true
+        } else {
+            return Ok(false);
+        };
+        if entry.stage() != gix::index::entry::Stage::Unconflicted
+            || entry.flags.intersects(
+                gix::index::entry::Flags::SKIP_WORKTREE | gix::index::entry::Flags::INTENT_TO_ADD,
+            )
+            || tree_blobs.get(path) != Some(&(entry.id, executable))
+        {
+            return Ok(false);
+        }
+
+        let file = workdir.join(gix::path::from_bstr(path));
+        let Ok(metadata) = gix::index::fs::Metadata::from_path_no_follow(&file) else {
+            return Ok(false);
+        };
+        if !metadata.is_file() {
+            return Ok(false);
+        }
+        // Files modified in the same instant the index was written may have
+        // the same stat information as before, so their contents must be checked.
+        let unchanged = gix::index::entry::Stat::from_fs(&metadata)
+            .is_ok_and(|stat| stat.matches(&entry.stat, stat_options))
+            && !entry.stat.is_racy(index.timestamp(), stat_options);
+        if !unchanged {
+            let contents = fs_err::read(&file)?;
+            let id =
+                gix::objs::compute_hash(index.object_hash(), gix::objs::Kind::Blob, &contents)?;
+            if id != entry.id {
+                return Ok(false);
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Collects the ids and executable bits of the blobs in `tree`, keyed by their path.
+///
+/// Returns `false` if the tree contains symlinks or submodules. These aren't extracted
+/// for git revisions either, so we can't vouch for the working tree matching the commit.
+fn collect_tree_blobs(
+    tree: gix::Id<'_>,
+    prefix: gix::bstr::BString,
+    blobs: &mut HashMap<gix::bstr::BString, (gix::ObjectId, bool)>,
+) -> anyhow::Result<bool> {
+    use gix::bstr::ByteVec as _;
+
+    for entry in tree.object()?.try_into_tree()?.iter() {
+        let entry = entry?;
+        let mode = entry.mode();
+        let mut path = prefix.clone();
+        if !path.is_empty() {
+            path.push_byte(b'/');
+        }
+        path.push_str(entry.filename());
+        if mode.is_tree() {
+            if !collect_tree_blobs(entry.id(), path, blobs)? {
+                return Ok(false);
+            }
+        } else if mode.is_blob() {
+            blobs.insert(path, (entry.id().detach(), mode.is_executable()));
+        } else {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// Returns the directories of the packages whose sources are built from the working tree:
+/// the local packages, and transitively their path dependencies and `[patch]`es,
+/// including the ones declared in the workspace manifests that enclose them.
+///
+/// Returns `None` if any of them is outside `workdir`.
+fn local_package_dirs<'a>(
+    workdir: &std::path::Path,
+    manifests: impl IntoIterator<Item = &'a Manifest>,
+) -> anyhow::Result<Option<BTreeSet<PathBuf>>> {
+    fn path_dependencies(
+        manifest: &cargo_toml::Manifest<crate::manifest::MetadataTable>,
+    ) -> impl Iterator<Item = &str> {
+        [&manifest.dependencies, &manifest.build_dependencies]
+            .into_iter()
+            .chain(
+                manifest
+                    .target
+                    .values()
+                    .flat_map(|target| [&target.dependencies, &target.build_dependencies]),
+            )
+            .chain(manifest.patch.values())
+            .chain(
+                manifest
+                    .workspace
+                    .as_ref()
+                    .map(|workspace| &workspace.dependencies),
+            )
+            .flat_map(|dependencies| dependencies.values())
+            .filter_map(|dependency| dependency.detail()?.path.as_deref())
+    }
+
+    let mut pending: Vec<PathBuf> = manifests
+        .into_iter()
+        .filter_map(|manifest| manifest.path.parent().map(ToOwned::to_owned))
+        .collect();
+    let mut package_dirs = BTreeSet::new();
+    let mut workspace_dirs = BTreeSet::new();
+    while let Some(dir) = pending.pop() {
+        // Path dependencies that don't exist will fail the build anyway.
+        let Ok(dir) = fs_err::canonicalize(&dir) else {
+            continue;
+        };
+        if !dir.starts_with(workdir) {
+            return Ok(None);
+        }
+        if !package_dirs.insert(dir.clone()) {
+            continue;
+        }
+
+        if let Ok(manifest) = Manifest::parse_standalone(dir.join("Cargo.toml")) {
+            pending.extend(path_dependencies(&manifest.parsed).map(|path| dir.join(path)));
+        }
+        for ancestor in dir
+            .ancestors()
+            .take_while(|ancestor| ancestor.starts_with(workdir))
+        {
+            if !workspace_dirs.insert(ancestor.to_owned()) {
+                continue;
+            }
+            if let Ok(manifest) = Manifest::parse_standalone(ancestor.join("Cargo.toml"))
+                && manifest.parsed.workspace.is_some()
+            {
+                pending.extend(path_dependencies(&manifest.parsed).map(|path| ancestor.join(path)));
+            }
+        }
+    }
+
+    Ok(Some(package_dirs))
+}
+
+/// Cargo marks the target directories it built in with a `CACHEDIR.TAG` file,
+/// and records the compiler's details in a `.rustc_info.json` file in any target directory
+/// it used, e.g. just for `cargo metadata`. Their contents are build outputs, not inputs.
+fn is_cargo_target_dir(entry: &ignore::DirEntry) -> bool {
+    entry
+        .file_type()
+        .is_some_and(|file_type| file_type.is_dir())
+        && ["CACHEDIR.TAG", ".rustc_info.json"]
+            .iter()
+            .any(|marker| entry.path().join(marker).is_file())
+}
+
 // From git2 crate
 #[cfg(unix)]
 fn bytes2str(b: &[u8]) -> &std::ffi::OsStr {
@@ -742,68 +1413,30 @@ fn bytes2str(b: &[u8]) -> &std::ffi::OsStr {
 pub(crate) struct RustdocFromRegistry {
     target_root: PathBuf,
     version: Option<semver::Version>,
-    index: tame_index::index::RemoteSparseIndex,
-}
-
-impl core::fmt::Debug for RustdocFromRegistry {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        f.debug_struct("RustdocFromRegistry")
-            .field("target_root", &self.target_root)
-            .field("version", &self.version)
-            .field("index", &"<elided>")
-            .finish()
-    }
+    /// Whether to check pre-releases against the previous pre-release of the same version,
+    /// rather than the last stable release.
+    prefer_prerelease_baselines: bool,
+    index: RegistryIndex,
 }
 
 impl RustdocFromRegistry {
-    pub fn new(target_root: &std::path::Path, _config: &mut GlobalConfig) -> anyhow::Result<Self> {
-        let index_url = tame_index::IndexUrl::crates_io(
-            // This is the config root, where .cargo/config.toml configuration files
-            // are crawled to determine if crates.io has been source replaced
-            // <https://doc.rust-lang.org/cargo/reference/source-replacement.html>
-            // if not specified it defaults to the current working directory,
-            // which is the same default that cargo uses, though note this can be
-            // extremely confusing if one can specify the manifest path of the
-            // crate from a different current working directory, though AFAICT
-            // this is not how this binary works
-            None,
-            // If set this overrides the CARGO_HOME that is used for both finding
-            // the "global" default config if not overriden during directory
-            // traversal to the root, as well as where the various registry
-            // indices/git sources are rooted. This is generally only useful
-            // for testing
-            None,
-            // If set, overrides the version of the cargo binary used, this is used
-            // as a fallback to determine if the version is 1.70.0+, which means
-            // the default crates.io registry to use is the sparse registry, else
-            // it is the old git registry
-            None,
-        )
-        .context("failed to obtain crates.io url")?;
-
-        if !index_url.is_sparse() {
-            bail!(
-                "registry index `{}` is not sparse; only sparse HTTP indexes are supported: \
-                set CARGO_REGISTRIES_CRATES_IO_PROTOCOL=sparse or update cargo config",
-                index_url.as_str()
-            );
-        }
-
-        // reqwest uses rustls-no-provider; install a ring provider once if needed.
-        if rustls::crypto::CryptoProvider::get_default().is_none() {
-            let _ = rustls::crypto::ring::default_provider().install_default();
-        }
-
-        let sparse = tame_index::index::SparseIndex::new(tame_index::IndexLocation::new(index_url))
-            .context("failed to open crates.io sparse index")?;
-        let client = tame_index::external::reqwest::blocking::Client::builder()
-            .build()
-            .context("failed to build HTTP client")?;
-        let index = tame_index::index::RemoteSparseIndex::new(sparse, client);
+    /// Look up baselines in the named registry from cargo config, or crates.io if `None`.
+    /// When `offline`, only the parts of the index that cargo already downloaded are used.
+    pub fn new(
+        target_root: &std::path::Path,
+        registry: Option<&str>,
+        offline: bool,
+        _config: &mut GlobalConfig,
+    ) -> anyhow::Result<Self> {
+        let index = RegistryIndex::open(registry, offline).with_context(|| match registry {
+            Some(name) => format!("failed to open index of registry `{name}`"),
+            None => "failed to open crates.io index".to_string(),
+        })?;
 
         Ok(Self {
             target_root: target_root.to_owned(),
             version: None,
+            prefer_prerelease_baselines: false,
             index,
         })
     }
@@ -812,36 +1445,35 @@ impl RustdocFromRegistry {
         self.version = Some(version);
     }
 
+    pub fn set_prefer_prerelease_baselines(&mut self, prefer_prerelease_baselines: bool) {
+        self.prefer_prerelease_baselines = prefer_prerelease_baselines;
+    }
+
     pub(crate) fn get_krate(
         &self,
         config: &mut GlobalConfig,
         crate_data: &CrateDataForRustdoc<'_>,
     ) -> Result<IndexKrate, TerminalError> {
         let lock = acquire_cargo_global_package_lock(config).into_terminal_result()?;
-        let validated_name = crate_data
-            .name
-            .as_str()
-            .try_into()
-            .expect("this should be impossible");
-        let krate = self.index.krate(validated_name, false, &lock)
-            .with_context(|| {
-                format!("failed to read index metadata for crate '{}'", crate_data.name)
-            }).into_terminal_result()?
+        let krate = self
+            .index
+            .krate(&crate_data.name, &lock)
             .with_context(|| {
-            anyhow::format_err!(
-                "{} not found in registry (crates.io). \
-        For workarounds check \
-        https://github.com/obi1kenobi/cargo-semver-checks#does-the-crate-im-checking-have-to-be-published-on-cratesio",
-                crate_data.name
-            )
-        }).into_terminal_result()?;
+                format!(
+                    "failed to read index metadata for crate '{}'",
+                    crate_data.name
+                )
+            })
+            .into_terminal_result()?
+            .with_context(|| self.index.not_found_message(&crate_data.name))
+            .into_terminal_result()?;
         drop(lock);
 
         Ok(krate)
     }
 
     fn get_crate_source<'a>(
-        &self,
+        &'a self,
         crate_data: &CrateDataForRustdoc<'_>,
         krate: &'a IndexKrate,
     ) -> Result<CrateSource<'a>, TerminalError> {
@@ -856,6 +1488,7 @@ impl RustdocFromRegistry {
                         highest_allowed_version,
                     } => highest_allowed_version.as_ref(),
                 },
+                self.prefer_prerelease_baselines,
             )
             .into_terminal_result()?
         };
@@ -867,25 +1500,38 @@ impl RustdocFromRegistry {
                 semver::Version::parse(v.version.as_str()).ok().as_ref() == Some(&base_version)
             })
             .with_context(|| {
+                let offline_note = if self.index.is_cached_only() {
+                    "; in offline mode, only versions that cargo already downloaded the index \
+                     entries for are available"
+                } else {
+                    ""
+                };
                 anyhow::format_err!(
-                    "crate {} version {} not found in registry",
+                    "crate {} version {} not found in registry{offline_note}",
                     crate_data.name,
                     base_version
                 )
             })
             .into_terminal_result()?;
 
-        Ok(CrateSource::Registry { versioned_krate })
+        Ok(CrateSource::Registry {
+            versioned_krate,
+            registry: self.index.name(),
+            vendored_crates: None,
+        })
     }
 }
 
 fn choose_baseline_version(
     krate: &IndexKrate,
     version_current: Option<&semver::Version>,
+    prefer_prerelease: bool,
 ) -> anyhow::Result<semver::Version> {
     // Try to avoid pre-releases
     // - Breaking changes are allowed between them
     // - Most likely the user cares about the last official release
+    // unless pre-releases are preferred, and the current version has some: then the last
+    // of them is what the current version must stay compatible with.
     if let Some(current) = version_current {
         let mut instances = krate
             .versions
@@ -897,10 +1543,25 @@ fn choose_baseline_version(
             .filter(|(v, _)| v <= current)
             .collect::<Vec<_>>();
         instances.sort();
-        instances
+        let is_prerelease_of_current = |v: &semver::Version| {
+            !v.pre.is_empty()
+                && (v.major, v.minor, v.patch) == (current.major, current.minor, current.patch)
+        };
+        let last_stable = instances
             .iter()
             .rev()
-            .find(|(v, yanked)| v.pre.is_empty() && !yanked)
+            .find(|(v, yanked)| v.pre.is_empty() && !yanked);
+        let last_prerelease = prefer_prerelease
+            .then(|| {
+                instances
+                    .iter()
+                    .rev()
+                    .find(|(v, yanked)| is_prerelease_of_current(v) && !yanked)
+            })
+            .flatten();
+        // A pre-release is only newer than the last stable release until its final release.
+        last_stable
+            .max(last_prerelease)
             .or_else(|| instances.last())
             .map(|(v, _)| v.clone())
             .with_context(|| {
@@ -927,6 +1588,34 @@ fn choose_baseline_version(
     }
 }
 
+/// The non-yanked, non-pre-release versions of the crate that are semver-compatible
+/// with `version_current` and not newer than it, oldest first.
+fn compatible_baseline_versions(
+    krate: &IndexKrate,
+    version_current: &semver::Version,
+) -> Vec<semver::Version> {
+    // Only changes in the left-most non-zero component are considered incompatible.
+    let is_compatible = |version: &semver::Version| match version_current {
+        semver::Version {
+            major: 0, minor: 0, ..
+        } => version.major == 0 && version.minor == 0 && version.patch == version_current.patch,
+        semver::Version {
+            major: 0, minor, ..
+        } => version.major == 0 && version.minor == *minor,
+        semver::Version { major, .. } => version.major == *major,
+    };
+    krate
+        .versions
+        .iter()
+        .filter(|iv| !iv.is_yanked())
+        .filter_map(|iv| semver::Version::parse(iv.version.as_str()).ok())
+        .filter(|version| {
+            version.pre.is_empty() && version <= version_current && is_compatible(version)
+        })
+        .sorted()
+        .collect()
+}
+
 fn acquire_cargo_global_package_lock(
     config: &mut GlobalConfig,
 ) -> anyhow::Result<tame_index::index::FileLock> {
@@ -945,7 +1634,10 @@ fn acquire_cargo_global_package_lock(
 mod tests {
     use tame_index::{IndexKrate, IndexVersion};
 
-    use super::choose_baseline_version;
+    use super::{
+        choose_baseline_version, clean_worktree_source_tree, compatible_baseline_versions,
+        unpack_crate_file, unpacked_package_dir,
+    };
 
     fn new_mock_version(version: semver::Version, yanked: bool) -> IndexVersion {
         let mut iv = IndexVersion::fake("test-crate", version.to_string());
@@ -968,7 +1660,7 @@ mod tests {
             semver::Version::parse(version_name)
                 .expect("current_version_name used in assertion should encode a valid version")
         });
-        let chosen_baseline = choose_baseline_version(&krate, current_version.as_ref())
+        let chosen_baseline = choose_baseline_version(&krate, current_version.as_ref(), false)
             .expect("choose_baseline_version should not return any error in the test case");
         assert_eq!(chosen_baseline, expected.parse().unwrap());
     }
@@ -1026,4 +1718,246 @@ mod tests {
             "1.2.1",
         );
     }
+
+    #[test]
+    fn clean_worktree_source_tree_covers_ignored_inputs() {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-clean-worktree-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        let repo = dir.join("repo");
+        fs_err::create_dir_all(repo.join("src")).expect("failed to create temp dir");
+        let write = |path: &str, contents: &str| {
+            let path = dir.join(path);
+            fs_err::create_dir_all(path.parent().unwrap()).expect("failed to create dir");
+            fs_err::write(path, contents).expect("failed to write file");
+        };
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(["-c", "commit.gpgsign=false"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .expect("failed to run git");
+            assert!(output.status.success(), "{output:?}");
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        let source_tree = || {
+            let manifest = crate::manifest::Manifest::parse(repo.join("Cargo.toml"))
+                .expect("failed to parse manifest");
+            clean_worktree_source_tree(&repo, [&manifest]).expect("failed to check worktree")
+        };
+
+        write(
+            "repo/Cargo.toml",
+            "[package]\nname = \"demo\"\nversion = \"1.0.0\"\n",
+        );
+        write("repo/src/lib.rs", "include!(\"../generated.rs\");\n");
+        write("repo/.gitignore", "/target\n/generated.rs\n");
+        git(&["init", "--quiet"]);
+        git(&["add", "."]);
+        git(&["commit", "--quiet", "-m", "initial"]);
+        let head_tree = git(&["rev-parse", "HEAD^{tree}"]);
+        assert_eq!(source_tree(), Some(head_tree.clone()));
+
+        // Build outputs are neither inputs nor hashed.
+        write("repo/target/.rustc_info.json", "{}");
+        assert_eq!(source_tree(), Some(head_tree.clone()));
+        write("repo/target/CACHEDIR.TAG", "");
+        write("repo/target/debug/output", "build output");
+        assert_eq!(source_tree(), Some(head_tree.clone()));
+
+        // Ignored lockfiles and cargo configuration are hashed into the identifier.
+        write("repo/.gitignore", "/target\n/generated.rs\n/Cargo.lock\n");
+        git(&["commit", "--quiet", "-am", "ignore lockfile"]);
+        let head_tree = git(&["rev-parse", "HEAD^{tree}"]);
+        write("repo/Cargo.lock", "version = 4\n");
+        let locked = source_tree().expect("worktree is clean");
+        assert!(locked.starts_with(&format!("{head_tree}+")), "{locked}");
+        write("repo/Cargo.lock", "version = 3\n");
+        let relocked = source_tree().expect("worktree is clean");
+        assert_ne!(relocked, locked);
+        fs_err::remove_file(repo.join("Cargo.lock")).expect("failed to remove file");
+        assert_eq!(source_tree(), Some(head_tree.clone()));
+
+        // Other ignored files may be build inputs we can't account for.
+        write("repo/generated.rs", "pub fn generated() {}\n");
+        assert_eq!(source_tree(), None);
+        fs_err::remove_file(repo.join("generated.rs")).expect("failed to remove file");
+        assert_eq!(source_tree(), Some(head_tree.clone()));
+
+        // Modified tracked files and untracked files make the working tree dirty.
+        write("repo/src/lib.rs", "include!(\"../modified.rs\");\n");
+        assert_eq!(source_tree(), None);
+        write("repo/src/lib.rs", "include!(\"../generated.rs\");\n");
+        assert_eq!(source_tree(), Some(head_tree));
+        write("repo/src/untracked.rs", "");
+        assert_eq!(source_tree(), None);
+        fs_err::remove_file(repo.join("src/untracked.rs")).expect("failed to remove file");
+
+        // Path dependencies outside the repository aren't pinned down by its commits.
+        write(
+            "other/Cargo.toml",
+            "[package]\nname = \"other\"\nversion = \"1.0.0\"\n",
+        );
+        write("other/src/lib.rs", "");
+        write(
+            "repo/Cargo.toml",
+            "[package]\nname = \"demo\"\nversion = \"1.0.0\"\n\n\
+             [dependencies]\nother = { path = \"../other\" }\n",
+        );
+        git(&["commit", "--quiet", "-am", "depend on other"]);
+        assert_eq!(source_tree(), None);
+
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn crate_files_are_unpacked_as_vendored_crates() {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-crate-file-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        fs_err::create_dir_all(&dir).expect("failed to create temp dir");
+        let crate_file = dir.join("demo-1.2.3.crate");
+        {
+            let encoder = flate2::write::GzEncoder::new(
+                fs_err::File::create(&crate_file).expect("failed to create crate file"),
+                flate2::Compression::default(),
+            );
+            let mut archive = tar::Builder::new(encoder);
+            for (path, contents) in [
+                (
+                    "demo-1.2.3/Cargo.toml",
+                    "[package]\nname = \"demo\"\nversion = \"1.2.3\"\n",
+                ),
+                ("demo-1.2.3/src/lib.rs", "pub fn demo() {}\n"),
+            ] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(contents.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                archive
+                    .append_data(&mut header, path, contents.as_bytes())
+                    .expect("failed to add file to crate");
+            }
+            archive
+                .into_inner()
+                .and_then(flate2::write::GzEncoder::finish)
+                .expect("failed to finish crate file");
+        }
+
+        let vendor_dir = dir.join("vendor");
+        assert_eq!(
+            unpacked_package_dir(&vendor_dir).expect("failed to look"),
+            None
+        );
+        let package_dir =
+            unpack_crate_file(&crate_file, "0123abcd", &vendor_dir).expect("failed to unpack");
+        assert_eq!(package_dir, vendor_dir.join("demo-1.2.3"));
+        assert!(package_dir.join("src/lib.rs").is_file());
+        let checksum: serde_json::Value = serde_json::from_str(
+            &fs_err::read_to_string(package_dir.join(".cargo-checksum.json"))
+                .expect("no checksum file"),
+        )
+        .expect("invalid checksum file");
+        assert_eq!(checksum["package"], "0123abcd");
+
+        // Later runs reuse the unpacked package.
+        assert_eq!(
+            unpacked_package_dir(&vendor_dir).expect("failed to look"),
+            Some(package_dir),
+        );
+        let krate = crate::registry::vendored_krate(&vendor_dir, "demo")
+            .expect("failed to read vendored crate")
+            .expect("demo is vendored");
+        assert_eq!(krate.versions[0].version.as_str(), "1.2.3");
+        fs_err::remove_dir_all(&dir).expect("failed to clean up");
+    }
+
+    #[test]
+    fn baseline_choosing_logic_can_prefer_the_previous_prerelease() {
+        let krate = IndexKrate {
+            versions: [
+                ("1.4.0", false),
+                ("2.0.0-alpha.1", false),
+                ("2.0.0-alpha.2", true),
+                ("2.1.0-alpha.1", false),
+            ]
+            .into_iter()
+            .map(|(version, yanked)| new_mock_version(version.parse().unwrap(), yanked))
+            .collect(),
+        };
+        let choose = |current: &str, prefer_prerelease| {
+            let current = semver::Version::parse(current).unwrap();
+            choose_baseline_version(&krate, Some(&current), prefer_prerelease)
+                .unwrap()
+                .to_string()
+        };
+
+        assert_eq!(choose("2.0.0-alpha.3", false), "1.4.0");
+        assert_eq!(choose("2.0.0-alpha.3", true), "2.0.0-alpha.1");
+        assert_eq!(choose("2.0.0", true), "2.0.0-alpha.1");
+        // Versions without pre-releases are checked against the last stable release.
+        assert_eq!(choose("1.5.0", true), "1.4.0");
+        assert_eq!(choose("2.0.0-alpha.1", true), "2.0.0-alpha.1");
+    }
+
+    fn assert_lists_compatible_versions(
+        versions: Vec<(&str, bool)>,
+        current_version_name: &str,
+        expected: &[&str],
+    ) {
+        let krate = IndexKrate {
+            versions: versions
+                .into_iter()
+                .map(|(version, yanked)| new_mock_version(version.parse().unwrap(), yanked))
+                .collect(),
+        };
+        let current_version = semver::Version::parse(current_version_name)
+            .expect("current_version_name used in assertion should encode a valid version");
+        let expected: Vec<semver::Version> = expected
+            .iter()
+            .map(|version| version.parse().unwrap())
+            .collect();
+        assert_eq!(
+            compatible_baseline_versions(&krate, &current_version),
+            expected
+        );
+    }
+
+    #[test]
+    fn compatible_versions_stay_in_the_current_major_series() {
+        assert_lists_compatible_versions(
+            vec![
+                ("0.9.0", false),
+                ("1.0.1", false),
+                ("1.0.0", false),
+                ("1.1.0", true),
+                ("1.2.0-rc1", false),
+                ("1.2.0", false),
+                ("1.3.0", false),
+                ("2.0.0", false),
+            ],
+            "1.2.1",
+            &["1.0.0", "1.0.1", "1.2.0"],
+        );
+    }
+
+    #[test]
+    fn compatible_versions_of_zero_versions_share_the_minor_version() {
+        assert_lists_compatible_versions(
+            vec![("0.1.0", false), ("0.2.0", false), ("0.2.3", false)],
+            "0.2.5",
+            &["0.2.0", "0.2.3"],
+        );
+        assert_lists_compatible_versions(
+            vec![("0.0.1", false), ("0.0.2", false)],
+            "0.0.2",
+            &["0.0.2"],
+        );
+    }
 }
@@ -0,0 +1,382 @@
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context as _;
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::GlobalConfig;
+use crate::util::atomic_write;
+
+/// How to report the time spent in each phase of a semver check.
+#[non_exhaustive]
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum TimingsFormat {
+    /// Print a human-readable summary to stderr.
+    Text,
+    /// Write a machine-readable JSON report into the target directory.
+    Json,
+    /// Write an HTML report into the target directory.
+    Html,
+}
+
+/// A phase of work whose duration is recorded in the timings report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TimingPhase {
+    GenerateRustdoc,
+    ParseRustdoc,
+    BuildIndexes,
+    RunQuery,
+    RunWitnesses,
+}
+
+impl TimingPhase {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::GenerateRustdoc => "generate rustdoc",
+            Self::ParseRustdoc => "parse rustdoc",
+            Self::BuildIndexes => "build indexes",
+            Self::RunQuery => "query",
+            Self::RunWitnesses => "witnesses",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct TimingEntry {
+    phase: TimingPhase,
+    /// Which item the phase applied to: `baseline` or `current` for rustdoc phases,
+    /// or the lint id for queries.
+    label: String,
+    #[serde(rename = "seconds", serialize_with = "serialize_seconds")]
+    duration: Duration,
+}
+
+/// Durations of the individual phases of checking one crate.
+#[derive(Debug, Clone, Default, Serialize)]
+pub(crate) struct CrateTimings {
+    entries: Vec<TimingEntry>,
+}
+
+impl CrateTimings {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(
+        &mut self,
+        phase: TimingPhase,
+        label: impl Into<String>,
+        duration: Duration,
+    ) {
+        self.entries.push(TimingEntry {
+            phase,
+            label: label.into(),
+            duration,
+        });
+    }
+
+    /// Add the timings from checking the crate in one of several configurations,
+    /// such as build targets or feature sets, labeling them with that configuration.
+    pub(crate) fn extend_for_configuration(&mut self, other: CrateTimings, configuration: &str) {
+        self.entries
+            .extend(other.entries.into_iter().map(|entry| TimingEntry {
+                label: format!("{configuration}: {}", entry.label),
+                ..entry
+            }));
+    }
+
+    pub(crate) fn total(&self) -> Duration {
+        self.entries.iter().map(|entry| entry.duration).sum()
+    }
+
+    /// Entries ordered from the most to the least expensive.
+    fn sorted_by_cost(&self) -> Vec<&TimingEntry> {
+        let mut entries: Vec<_> = self.entries.iter().collect();
+        entries.sort_by(|a, b| {
+            b.duration
+                .cmp(&a.duration)
+                .then_with(|| a.label.cmp(&b.label))
+        });
+        entries
+    }
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+/// How many of the slowest queries to list per crate in the text report.
+/// The JSON and HTML reports always list every query.
+const TEXT_REPORT_QUERY_LIMIT: usize = 20;
+
+pub(crate) fn report_timings<'a>(
+    config: &mut GlobalConfig,
+    format: TimingsFormat,
+    crates: impl IntoIterator<Item = (&'a str, &'a CrateTimings)>,
+    target_dir: &Path,
+) -> anyhow::Result<()> {
+    let crates: Vec<_> = crates.into_iter().collect();
+    match format {
+        TimingsFormat::Text => print_text_report(config, &crates),
+        TimingsFormat::Json => {
+            let report = serde_json::json!({ "crates": json_report(&crates) });
+            let path = write_report(
+                config,
+                "json",
+                serde_json::to_string_pretty(&report)?,
+                target_dir,
+            )?;
+            config.shell_note(format_args!("timing report saved to {}", path.display()))
+        }
+        TimingsFormat::Html => {
+            let path = write_report(config, "html", render_html(&crates), target_dir)?;
+            config.shell_note(format_args!("timing report saved to {}", path.display()))
+        }
+    }
+}
+
+fn print_text_report(
+    config: &mut GlobalConfig,
+    crates: &[(&str, &CrateTimings)],
+) -> anyhow::Result<()> {
+    for (name, timings) in crates {
+        config.shell_status(
+            "Timings",
+            format_args!("[{:>8.3}s] {name}", timings.total().as_secs_f32()),
+        )?;
+
+        let mut omitted_queries = 0usize;
+        let mut listed_queries = 0usize;
+        for entry in timings.sorted_by_cost() {
+            if entry.phase == TimingPhase::RunQuery {
+                if listed_queries == TEXT_REPORT_QUERY_LIMIT {
+                    omitted_queries += 1;
+                    continue;
+                }
+                listed_queries += 1;
+            }
+
+            config.log_info(|config| {
+                writeln!(
+                    config.stderr(),
+                    "{:12} [{:>8.3}s] {:<16} {}",
+                    "",
+                    entry.duration.as_secs_f32(),
+                    entry.phase.as_str(),
+                    entry.label,
+                )?;
+                Ok(())
+            })?;
+        }
+
+        if omitted_queries > 0 {
+            config.log_info(|config| {
+                writeln!(
+                    config.stderr(),
+                    "{:12} ... and {omitted_queries} faster queries, \
+                     use `--timings=json` to see all of them",
+                    "",
+                )?;
+                Ok(())
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct JsonCrateTimings<'a> {
+    name: &'a str,
+    #[serde(rename = "total_seconds")]
+    total: f64,
+    entries: Vec<&'a TimingEntry>,
+}
+
+fn json_report<'a>(crates: &[(&'a str, &'a CrateTimings)]) -> Vec<JsonCrateTimings<'a>> {
+    crates
+        .iter()
+        .map(|(name, timings)| JsonCrateTimings {
+            name,
+            total: timings.total().as_secs_f64(),
+            entries: timings.sorted_by_cost(),
+        })
+        .collect()
+}
+
+/// Write a report into the `timings` dir of the target directory, like cargo does.
+fn write_report(
+    config: &GlobalConfig,
+    extension: &str,
+    contents: String,
+    target_dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let report_dir = target_dir.join("timings");
+    fs_err::create_dir_all(&report_dir).context("failed to create timings report dir")?;
+    let report_path = report_dir.join(format!(
+        "cargo-semver-checks-timing-{}.{extension}",
+        config.run_id()
+    ));
+
+    atomic_write(&report_path, |writer| {
+        writer.write_all(contents.as_bytes())?;
+        Ok(())
+    })
+    .context("failed to write timing report")?;
+
+    Ok(report_path)
+}
+
+fn render_html(crates: &[(&str, &CrateTimings)]) -> String {
+    let mut html = String::with_capacity(4096);
+    html.push_str(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>cargo-semver-checks timings</title>\n<style>\n\
+         body { font-family: sans-serif; }\n\
+         table { border-collapse: collapse; margin-bottom: 2em; }\n\
+         th, td { padding: 0.2em 0.8em; text-align: left; }\n\
+         td.duration { text-align: right; font-family: monospace; }\n\
+         tr:nth-child(even) { background: #f0f0f0; }\n\
+         </style>\n</head>\n<body>\n<h1>cargo-semver-checks timings</h1>\n",
+    );
+
+    for (name, timings) in crates {
+        writeln!(
+            html,
+            "<h2>{} ({:.3}s)</h2>\n<table>\n<tr><th>Duration</th><th>Phase</th><th>Item</th></tr>",
+            escape_html(name),
+            timings.total().as_secs_f64(),
+        )
+        .expect("writing to a String should not fail");
+        for entry in timings.sorted_by_cost() {
+            writeln!(
+                html,
+                "<tr><td class=\"duration\">{:.3}s</td><td>{}</td><td>{}</td></tr>",
+                entry.duration.as_secs_f64(),
+                entry.phase.as_str(),
+                escape_html(&entry.label),
+            )
+            .expect("writing to a String should not fail");
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{
+        CrateTimings, TimingPhase, TimingsFormat, json_report, render_html, report_timings,
+    };
+    use crate::GlobalConfig;
+
+    fn sample_timings() -> CrateTimings {
+        let mut timings = CrateTimings::new();
+        timings.record(
+            TimingPhase::ParseRustdoc,
+            "baseline",
+            Duration::from_millis(20),
+        );
+        timings.record(
+            TimingPhase::GenerateRustdoc,
+            "baseline",
+            Duration::from_secs(3),
+        );
+        timings.record(
+            TimingPhase::RunQuery,
+            "function_missing",
+            Duration::from_millis(5),
+        );
+        timings.record(TimingPhase::RunQuery, "<odd>", Duration::from_millis(40));
+        timings
+    }
+
+    #[test]
+    fn entries_are_sorted_by_cost() {
+        let timings = sample_timings();
+        let labels: Vec<_> = timings
+            .sorted_by_cost()
+            .into_iter()
+            .map(|entry| entry.label.as_str())
+            .collect();
+
+        assert_eq!(
+            labels,
+            ["baseline", "<odd>", "baseline", "function_missing"]
+        );
+        assert_eq!(timings.total(), Duration::from_millis(3065));
+    }
+
+    #[test]
+    fn json_report_lists_phases_in_snake_case() {
+        let timings = sample_timings();
+        let report = serde_json::to_value(json_report(&[("example", &timings)]))
+            .expect("failed to serialize timings");
+
+        assert_eq!(report[0]["name"], "example");
+        assert_eq!(report[0]["entries"][0]["phase"], "generate_rustdoc");
+        assert_eq!(report[0]["entries"][0]["seconds"], 3.0);
+    }
+
+    #[test]
+    fn html_report_escapes_labels() {
+        let timings = sample_timings();
+        let html = render_html(&[("example", &timings)]);
+
+        assert!(html.contains("&lt;odd&gt;"));
+        assert!(!html.contains("<odd>"));
+    }
+
+    #[test]
+    fn json_report_is_written_into_target_dir() {
+        let target_dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-timings-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>(),
+        ));
+        let mut config = GlobalConfig::new();
+        let timings = sample_timings();
+        report_timings(
+            &mut config,
+            TimingsFormat::Json,
+            [("example", &timings)],
+            &target_dir,
+        )
+        .expect("failed to report timings");
+
+        let report_path = target_dir.join("timings").join(format!(
+            "cargo-semver-checks-timing-{}.json",
+            config.run_id()
+        ));
+        let report: serde_json::Value =
+            serde_json::from_str(&fs_err::read_to_string(&report_path).expect("no report"))
+                .expect("report is not JSON");
+        assert_eq!(report["crates"][0]["name"], "example");
+        fs_err::remove_dir_all(&target_dir).expect("failed to clean up");
+    }
+}
@@ -60,6 +60,11 @@ impl<'a> WitnessGenerationData<'a> {
         }
     }
 
+    /// The `target` directory root used for witness artifacts.
+    pub(crate) fn target_dir(&self) -> &Path {
+        &self.target_dir
+    }
+
     fn requested_build_target(&self) -> Result<Option<&str>> {
         let baseline_target = self.baseline.and_then(CrateDataRequest::build_target);
         let current_target = self.current.and_then(CrateDataRequest::build_target);
@@ -142,6 +142,7 @@ pub(super) fn make_lint_result(
         query_duration: std::time::Duration::ZERO,
         effective_required_update: RequiredSemverUpdate::Major,
         effective_lint_level: LintLevel::Deny,
+        result_configurations: Vec::new(),
     }
 }
 
@@ -235,13 +236,18 @@ pub(super) fn make_test_data_storage(
     let generation_settings = GenerationSettings {
         pass_through_stderr: false,
         use_color: false,
+        offline: false,
+        timeout: None,
+        cancellation: None,
+        recorder: None,
+        fingerprint_api: false,
     };
     let mut callbacks = NoopProgressCallbacks;
     let baseline_storage = baseline_request
         .resolve(
             target_dir,
             CacheSettings::None,
-            generation_settings,
+            generation_settings.clone(),
             &mut callbacks,
         )
         .expect("failed to resolve baseline rustdoc data for witness test");
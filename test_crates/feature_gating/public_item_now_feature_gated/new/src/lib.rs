@@ -0,0 +1,21 @@
+#![no_std]
+
+// Items moved behind a feature that isn't enabled by default.
+
+#[cfg(feature = "unstable")]
+pub fn now_feature_gated() {}
+
+#[cfg(feature = "unstable")]
+pub struct NowFeatureGated;
+
+#[cfg(feature = "unstable")]
+pub mod gated_module {
+    pub fn inside_gated_module() {}
+}
+
+// Items whose availability didn't change.
+
+pub fn still_available() {}
+
+#[cfg(feature = "unstable")]
+pub fn always_feature_gated() {}
@@ -0,0 +1,14 @@
+#![no_std]
+
+pub fn now_feature_gated() {}
+
+pub struct NowFeatureGated;
+
+pub mod gated_module {
+    pub fn inside_gated_module() {}
+}
+
+pub fn still_available() {}
+
+#[cfg(feature = "unstable")]
+pub fn always_feature_gated() {}
@@ -0,0 +1,93 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+fn temp_dir(label: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cargo-semver-checks-{label}-{}-{:032x}",
+        std::process::id(),
+        rand::random::<u128>()
+    ));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    dir
+}
+
+/// Package the test crate at `crate_dir` with `cargo package`, like a crate about to be published.
+fn cargo_package(crate_dir: &str, target_dir: &Path) -> PathBuf {
+    let output = std::process::Command::new("cargo")
+        .args([
+            "package",
+            "--no-verify",
+            "--allow-dirty",
+            "--offline",
+            "--quiet",
+        ])
+        .arg("--manifest-path")
+        .arg(Path::new(crate_dir).join("Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target_dir)
+        .output()
+        .expect("failed to run cargo package");
+    assert!(output.status.success(), "{output:?}");
+
+    let mut crate_files = std::fs::read_dir(target_dir.join("package"))
+        .expect("failed to read packaged crates")
+        .map(|entry| entry.expect("failed to read dir entry").path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "crate")
+        });
+    let crate_file = crate_files.next().expect("no crate file was packaged");
+    assert_eq!(crate_files.next(), None);
+    crate_file
+}
+
+#[test]
+fn baseline_crate_file_finds_breakage() {
+    let dir = temp_dir("baseline-crate-file-breakage");
+    let crate_file = cargo_package("test_crates/function_missing/old", &dir);
+
+    let output = cargo_semver_checks()
+        .args([
+            "semver-checks",
+            "check-release",
+            "--manifest-path=test_crates/function_missing/new/Cargo.toml",
+        ])
+        .arg("--baseline-crate-file")
+        .arg(&crate_file)
+        .assert()
+        .failure()
+        .get_output()
+        .clone();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("--- failure function_missing:"), "{stdout}");
+    assert!(
+        stdout.contains("function function_missing::will_be_removed_fn"),
+        "{stdout}"
+    );
+
+    std::fs::remove_dir_all(&dir).expect("failed to clean up");
+}
+
+#[test]
+fn baseline_crate_file_without_changes_passes() {
+    let dir = temp_dir("baseline-crate-file-unchanged");
+    let crate_file = cargo_package("test_crates/template/old", &dir);
+
+    cargo_semver_checks()
+        .args([
+            "semver-checks",
+            "check-release",
+            "--manifest-path=test_crates/template/new/Cargo.toml",
+        ])
+        .arg("--baseline-crate-file")
+        .arg(&crate_file)
+        .assert()
+        .success();
+
+    std::fs::remove_dir_all(&dir).expect("failed to clean up");
+}
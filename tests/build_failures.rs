@@ -0,0 +1,168 @@
+use std::path::PathBuf;
+
+use assert_cmd::Command;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// A crate named `fails_to_build` that doesn't build, with a path dependency `broken_dep`.
+struct FailingCrate {
+    dir: PathBuf,
+}
+
+impl FailingCrate {
+    fn new(label: &str, lib_rs: &str, build_rs: Option<&str>, dep_lib_rs: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-{label}-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        let dep_dir = dir.join("broken_dep");
+        std::fs::create_dir_all(dep_dir.join("src")).expect("failed to create temp dir");
+        std::fs::create_dir_all(dir.join("src")).expect("failed to create temp dir");
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\n\
+             name = \"fails_to_build\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2021\"\n\
+             \n\
+             [dependencies]\n\
+             broken_dep = { path = \"broken_dep\" }\n\
+             \n\
+             [workspace]\n",
+        )
+        .expect("failed to write manifest");
+        std::fs::write(dir.join("src/lib.rs"), lib_rs).expect("failed to write lib.rs");
+        if let Some(build_rs) = build_rs {
+            std::fs::write(dir.join("build.rs"), build_rs).expect("failed to write build.rs");
+        }
+        std::fs::write(
+            dep_dir.join("Cargo.toml"),
+            "[package]\n\
+             name = \"broken_dep\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2021\"\n\
+             publish = false\n",
+        )
+        .expect("failed to write manifest");
+        std::fs::write(dep_dir.join("src/lib.rs"), dep_lib_rs).expect("failed to write lib.rs");
+        Self { dir }
+    }
+
+    fn check_release(&self) -> assert_cmd::assert::Assert {
+        cargo_semver_checks()
+            .current_dir(&self.dir)
+            .args(["semver-checks", "check-release", "--baseline-root=."])
+            .env_remove("RUST_BACKTRACE")
+            .assert()
+            .failure()
+    }
+}
+
+impl Drop for FailingCrate {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// With `--message-format=json`, cargo only reports compiler errors as JSON messages,
+/// so finding the rendered error in the output shows it was parsed from them.
+#[test]
+fn compile_error_in_crate_is_diagnosed() {
+    let failing = FailingCrate::new(
+        "compile-error",
+        "pub fn function() -> MissingType { todo!() }\n",
+        None,
+        "",
+    );
+    failing
+        .check_release()
+        .stderr(predicates::str::contains(
+            "cannot find type `MissingType` in this scope",
+        ))
+        .stderr(predicates::str::contains(
+            "aborting due to failure to build rustdoc for crate fails_to_build v0.1.0: \
+             compilation failed",
+        ))
+        .stderr(predicates::str::contains(
+            "note: this is usually due to a compilation error in the crate,",
+        ))
+        .stderr(predicates::str::contains(
+            "note: the following command can be used to reproduce the error:",
+        ))
+        .stderr(predicates::str::contains("cargo check &&"));
+}
+
+#[test]
+fn compile_error_in_dependency_is_diagnosed() {
+    let failing = FailingCrate::new(
+        "dependency-compile-error",
+        "pub fn function() {}\n",
+        None,
+        "compile_error!(\"broken_dep is intentionally broken\");\n",
+    );
+    failing
+        .check_release()
+        .stderr(predicates::str::contains(
+            "error: broken_dep is intentionally broken",
+        ))
+        .stderr(predicates::str::contains(
+            "aborting due to failure to build rustdoc for crate fails_to_build v0.1.0: \
+             compilation failed",
+        ))
+        .stderr(predicates::str::contains(
+            "note: dependency broken_dep v0.1.0 of crate fails_to_build v0.1.0 \
+             failed to compile;",
+        ))
+        .stderr(predicates::str::contains("--lockfile"));
+}
+
+#[test]
+fn build_script_failure_is_diagnosed() {
+    let failing = FailingCrate::new(
+        "build-script-failure",
+        "pub fn function() {}\n",
+        Some("fn main() { panic!(\"the build script needs a tool that isn't here\"); }\n"),
+        "",
+    );
+    failing
+        .check_release()
+        .stderr(predicates::str::contains(
+            "the build script needs a tool that isn't here",
+        ))
+        .stderr(predicates::str::contains(
+            "aborting due to failure to build rustdoc for crate fails_to_build v0.1.0: \
+             a build script failed",
+        ))
+        .stderr(predicates::str::contains(
+            "note: the build script of fails_to_build v0.1.0 failed;",
+        ));
+}
+
+#[test]
+fn missing_system_library_is_diagnosed() {
+    let failing = FailingCrate::new(
+        "missing-system-library",
+        "pub fn function() {}\n",
+        Some(
+            "fn main() {\n\
+             \x20   eprintln!(\"could not find system library 'libexample' required by the \
+             'fails_to_build' crate\");\n\
+             \x20   std::process::exit(1);\n\
+             }\n",
+        ),
+        "",
+    );
+    failing
+        .check_release()
+        .stderr(predicates::str::contains(
+            "aborting due to failure to build rustdoc for crate fails_to_build v0.1.0: \
+             a system library is missing",
+        ))
+        .stderr(predicates::str::contains(
+            "note: a system library needed by fails_to_build v0.1.0 was not found;",
+        ))
+        .stderr(predicates::str::contains("`PKG_CONFIG_PATH`"));
+}
@@ -0,0 +1,189 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use predicates::boolean::PredicateBooleanExt as _;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// A git repository holding a copy of the `template` test crate, committed so its
+/// working tree is clean and checking it caches the generated rustdoc.
+struct TemplateRepo {
+    dir: PathBuf,
+}
+
+impl TemplateRepo {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-cache-command-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        std::fs::create_dir_all(dir.join("src")).expect("failed to create temp dir");
+        for file in ["Cargo.toml", "Cargo.lock", "src/lib.rs"] {
+            std::fs::copy(
+                Path::new("test_crates/template/new").join(file),
+                dir.join(file),
+            )
+            .expect("failed to copy test crate");
+        }
+        std::fs::write(dir.join(".gitignore"), "/target\n").expect("failed to write .gitignore");
+
+        let repo = Self { dir };
+        repo.git(&["init", "--quiet"]);
+        repo.git(&["add", "."]);
+        repo.git(&["commit", "--quiet", "-m", "initial"]);
+        repo
+    }
+
+    fn git(&self, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(args)
+            .current_dir(&self.dir)
+            .output()
+            .expect("failed to run git");
+        assert!(output.status.success(), "{output:?}");
+    }
+
+    /// Release the crate as `version`, committing the change.
+    fn set_version(&self, version: &str) {
+        for file in ["Cargo.toml", "Cargo.lock"] {
+            let path = self.dir.join(file);
+            let contents = std::fs::read_to_string(&path).expect("failed to read file");
+            let contents =
+                contents.replace("version = \"0.1.0\"", &format!("version = \"{version}\""));
+            std::fs::write(&path, contents).expect("failed to write file");
+        }
+        self.git(&["commit", "--quiet", "-am", version]);
+    }
+
+    /// Check the crate against itself, which generates and caches its rustdoc.
+    fn check(&self) {
+        cargo_semver_checks()
+            .current_dir(&self.dir)
+            .args(["semver-checks", "check-release", "--baseline-root=."])
+            .assert()
+            .success();
+    }
+
+    fn cache_dir(&self) -> PathBuf {
+        self.dir.join("target/semver-checks/cache")
+    }
+
+    fn cache(&self, args: &[&str]) -> String {
+        let output = cargo_semver_checks()
+            .args(["semver-checks", "cache", "--cache-dir"])
+            .arg(self.cache_dir())
+            .args(args)
+            .assert()
+            .success()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).expect("output isn't UTF-8")
+    }
+
+    fn cached_files(&self) -> Vec<String> {
+        let mut files: Vec<String> = std::fs::read_dir(self.cache_dir())
+            .expect("failed to read cache dir")
+            .map(|entry| {
+                entry
+                    .expect("failed to read cache dir entry")
+                    .file_name()
+                    .into_string()
+                    .expect("file name isn't UTF-8")
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Relabel the cached rustdoc with format `version`,
+    /// as if it was cached by an older or newer toolchain.
+    fn set_cached_format_version(&self, version: u32) {
+        for file in self.cached_files() {
+            if !file.ends_with(".json.zst") {
+                continue;
+            }
+            let path = self.cache_dir().join(file);
+            let compressed = std::fs::read(&path).expect("failed to read cached rustdoc");
+            let rustdoc = zstd::decode_all(compressed.as_slice()).expect("failed to decompress");
+            let mut rustdoc: serde_json::Value =
+                serde_json::from_slice(&rustdoc).expect("cached rustdoc isn't valid JSON");
+            rustdoc["format_version"] = version.into();
+            let compressed =
+                zstd::encode_all(rustdoc.to_string().as_bytes(), 0).expect("failed to compress");
+            std::fs::write(&path, compressed).expect("failed to write cached rustdoc");
+        }
+    }
+}
+
+impl Drop for TemplateRepo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The cache populated by checking two releases of a crate can be listed,
+/// pruned down to the newest release, and cleared.
+#[test]
+fn prune_and_clear_populated_cache() {
+    let repo = TemplateRepo::new();
+    repo.check();
+    repo.set_version("0.2.0");
+    repo.check();
+
+    let listed = repo.cache(&["list"]);
+    assert!(listed.contains("0_1_0"), "{listed}");
+    assert!(listed.contains("0_2_0"), "{listed}");
+    assert_eq!(repo.cached_files().len(), 4, "{:?}", repo.cached_files());
+
+    repo.cache(&["prune", "--keep-versions=1"]);
+    let listed = repo.cache(&["list"]);
+    assert!(!listed.contains("0_1_0"), "{listed}");
+    assert!(listed.contains("0_2_0"), "{listed}");
+    let files = repo.cached_files();
+    assert_eq!(files.len(), 2, "{files:?}");
+    assert!(
+        files.iter().all(|file| file.starts_with("template-0_2_0-")),
+        "{files:?}"
+    );
+
+    // A pruned cache is still used by later checks.
+    repo.check();
+    assert_eq!(repo.cached_files(), files);
+
+    repo.cache(&["clear"]);
+    assert_eq!(repo.cached_files(), Vec::<String>::new());
+    assert_eq!(repo.cache(&["size"]).trim(), "0 B");
+}
+
+/// Cached rustdoc in a different format than the baseline's is regenerated
+/// with the installed toolchain, which replaces the stale cache entry.
+#[test]
+fn stale_cached_rustdoc_is_regenerated() {
+    let repo = TemplateRepo::new();
+    repo.check();
+    repo.set_cached_format_version(60);
+
+    let baseline = Path::new("localdata/test_data/template/old/rustdoc.json")
+        .canonicalize()
+        .expect(
+            "failed to find rustdoc, did you forget to run ./scripts/regenerate_test_rustdocs.sh ?",
+        );
+    let check_against_baseline = || {
+        cargo_semver_checks()
+            .current_dir(&repo.dir)
+            .args(["semver-checks", "check-release", "--baseline-rustdoc"])
+            .arg(&baseline)
+            .assert()
+            .success()
+    };
+    check_against_baseline().stderr(predicates::str::contains(
+        "Regenerating current rustdoc for template in format v60",
+    ));
+    check_against_baseline().stderr(predicates::str::contains("Regenerating").not());
+}
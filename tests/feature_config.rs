@@ -1,4 +1,5 @@
 use assert_cmd::{Command, assert::Assert};
+use predicates::boolean::PredicateBooleanExt as _;
 
 struct CargoSemverChecks {
     args: Vec<String>,
@@ -393,3 +394,83 @@ fn feature_does_not_exist() {
         a.failure();
     });
 }
+
+#[test]
+fn item_moved_behind_non_default_feature() {
+    // With the feature enabled, the item is still there, so the lints alone pass.
+    CargoSemverChecks::new(
+        "test_crates/feature_gating/public_item_now_feature_gated/new/",
+        "test_crates/feature_gating/public_item_now_feature_gated/old/Cargo.toml",
+    )
+    .add_arg("--all-features")
+    .run_all()
+    .into_iter()
+    .for_each(|a| {
+        a.success();
+    });
+
+    CargoSemverChecks::new(
+        "test_crates/feature_gating/public_item_now_feature_gated/new/",
+        "test_crates/feature_gating/public_item_now_feature_gated/old/Cargo.toml",
+    )
+    .add_arg("--all-features")
+    .add_arg("--check-feature-gating")
+    .run_all()
+    .into_iter()
+    .for_each(|a| {
+        a.failure()
+            .stdout(predicates::str::contains(
+                "--- failure public_item_now_feature_gated:",
+            ))
+            .stdout(predicates::str::contains(
+                "function public_item_now_feature_gated::now_feature_gated now requires feature `unstable`",
+            ))
+            .stdout(predicates::str::contains("still_available").not());
+    });
+}
+
+/// Functions moved to another feature aren't found without any features enabled,
+/// but are with the feature they used to be under.
+#[test]
+fn function_moved_each_feature() {
+    CargoSemverChecks::new(
+        "test_crates/function_feature_changed/new/",
+        "test_crates/function_feature_changed/old/Cargo.toml",
+    )
+    .add_arg("--only-explicit-features")
+    .add_arg("--each-feature")
+    .run_all()
+    .into_iter()
+    .for_each(|a| {
+        a.failure()
+            .stdout(predicates::str::contains(
+                "function function_feature_changed::moving_from_feature_A_to_feature_B",
+            ))
+            .stdout(predicates::str::contains("    found in: features A\n"))
+            .stdout(predicates::str::contains(
+                "function function_feature_changed::moving_from_feature_B_to_feature_C",
+            ))
+            .stdout(predicates::str::contains("    found in: features B\n"));
+    });
+}
+
+/// Findings under several feature sets are reported once, listing each of the feature sets.
+/// With both `A` and `B` the first function is still there, so that set isn't listed for it.
+#[test]
+fn function_moved_feature_powerset() {
+    CargoSemverChecks::new(
+        "test_crates/function_feature_changed/new/",
+        "test_crates/function_feature_changed/old/Cargo.toml",
+    )
+    .add_arg("--only-explicit-features")
+    .add_arg("--feature-powerset")
+    .add_arg("--depth=2")
+    .run_with_subcommand()
+    .failure()
+    .stdout(predicates::str::contains(
+        "    found in: features A; features A,C; features A,new_feature\n",
+    ))
+    .stdout(predicates::str::contains(
+        "    found in: features B; features A,B; features B,new_feature\n",
+    ));
+}
@@ -175,7 +175,8 @@ fn cfg_conditional_compilation() {
 }
 
 /// Analogous test to the one above, but when the `--cfg` is not set.
-/// In this case, no breakage should be reported.
+/// In this case, no breakage should be reported. The public APIs are then identical,
+/// so the lints must be run explicitly to show that none of them fire.
 #[test]
 fn cfg_conditional_compilation_without_cfg_set() {
     assert_integration_test(
@@ -186,6 +187,7 @@ fn cfg_conditional_compilation_without_cfg_set() {
                 "test_crates/cfg_conditional_compilation/new",
                 "--baseline-root",
                 "test_crates/cfg_conditional_compilation/old",
+                "--check-unchanged-api",
             ]);
 
             set_snapshot_filters(settings);
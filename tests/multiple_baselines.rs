@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use predicates::boolean::PredicateBooleanExt as _;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// A git repository with two releases of the `function_missing` test crate:
+/// `v1` is its old version and `v2` its new version, which removes some functions.
+struct TwoReleases {
+    dir: PathBuf,
+}
+
+impl TwoReleases {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-multiple-baselines-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        std::fs::create_dir_all(dir.join("src")).expect("failed to create temp dir");
+        std::fs::write(dir.join(".gitignore"), "/target\n").expect("failed to write .gitignore");
+        let repo = Self { dir };
+        repo.git(&["init", "--quiet"]);
+        for (tag, version) in [("v1", "old"), ("v2", "new")] {
+            for file in ["Cargo.toml", "src/lib.rs"] {
+                std::fs::copy(
+                    Path::new("test_crates/function_missing")
+                        .join(version)
+                        .join(file),
+                    repo.dir.join(file),
+                )
+                .expect("failed to copy test crate");
+            }
+            repo.git(&["add", "."]);
+            repo.git(&["commit", "--quiet", "-m", tag]);
+            repo.git(&["tag", tag]);
+        }
+        repo
+    }
+
+    fn git(&self, args: &[&str]) {
+        let output = std::process::Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(["-c", "commit.gpgsign=false", "-c", "tag.gpgsign=false"])
+            .args(args)
+            .current_dir(&self.dir)
+            .output()
+            .expect("failed to run git");
+        assert!(output.status.success(), "{output:?}");
+    }
+
+    fn check(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        cargo_semver_checks()
+            .current_dir(&self.dir)
+            .args(["semver-checks", "check-release"])
+            .args(args)
+            .assert()
+    }
+}
+
+impl Drop for TwoReleases {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// The latest release matches the current crate, but the functions it removed
+/// are still breaking relative to the release before it.
+#[test]
+fn breakage_against_an_earlier_baseline() {
+    let repo = TwoReleases::new();
+    repo.check(&["--baseline-rev=v2"]).success();
+
+    repo.check(&["--baseline-rev=v1", "--baseline-rev=v2"])
+        .failure()
+        .stdout(predicates::str::contains(
+            "function function_missing::will_be_removed_fn",
+        ))
+        .stdout(predicates::str::contains("    found in: baseline v1\n"))
+        .stdout(predicates::str::contains("found in: baseline v2").not())
+        .stderr(predicates::str::contains("[baseline v1]"))
+        .stderr(predicates::str::contains("[baseline v2]"));
+}
+
+/// Findings are reported for each baseline they're found against.
+#[test]
+fn breakage_against_each_baseline() {
+    let repo = TwoReleases::new();
+    std::fs::write(repo.dir.join("src/lib.rs"), "#![no_std]\n").expect("failed to write lib.rs");
+
+    repo.check(&["--baseline-rev=v1", "--baseline-rev=v2"])
+        .failure()
+        .stdout(predicates::str::contains(
+            "function function_missing::will_be_removed_fn",
+        ))
+        .stdout(predicates::str::contains(
+            "function function_missing::my_pub_mod::pub_use_removed_fn",
+        ))
+        .stdout(predicates::str::contains("    found in: baseline v1\n"))
+        .stdout(predicates::str::contains("    found in: baseline v2\n"));
+}
@@ -0,0 +1,98 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// Copies of the `function_missing` test crate's versions, released as the given pre-releases.
+/// The new version removes some functions.
+struct Prereleases {
+    dir: PathBuf,
+}
+
+impl Prereleases {
+    fn new(baseline_version: &str, current_version: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-prerelease-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        for (side, version) in [("old", baseline_version), ("new", current_version)] {
+            let crate_dir = Path::new("test_crates/function_missing").join(side);
+            std::fs::create_dir_all(dir.join(side).join("src")).expect("failed to create temp dir");
+            std::fs::copy(
+                crate_dir.join("src/lib.rs"),
+                dir.join(side).join("src/lib.rs"),
+            )
+            .expect("failed to copy test crate");
+            let manifest = std::fs::read_to_string(crate_dir.join("Cargo.toml"))
+                .expect("failed to read manifest");
+            std::fs::write(
+                dir.join(side).join("Cargo.toml"),
+                manifest.replace("version = \"0.1.0\"", &format!("version = \"{version}\"")),
+            )
+            .expect("failed to write manifest");
+        }
+        Self { dir }
+    }
+
+    fn check(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        cargo_semver_checks()
+            .args(["semver-checks", "check-release", "--manifest-path"])
+            .arg(self.dir.join("new"))
+            .arg("--baseline-root")
+            .arg(self.dir.join("old"))
+            .args(args)
+            .assert()
+    }
+}
+
+impl Drop for Prereleases {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn prereleases_may_break_by_default() {
+    let prereleases = Prereleases::new("2.0.0-beta.1", "2.0.0-rc.1");
+    prereleases
+        .check(&[])
+        .success()
+        .stderr(predicates::str::contains(
+            "function_missing v2.0.0-beta.1 -> v2.0.0-rc.1 (major change)",
+        ));
+}
+
+#[test]
+fn breaking_in_alpha_policy() {
+    let prereleases = Prereleases::new("2.0.0-alpha.1", "2.0.0-alpha.2");
+    prereleases
+        .check(&["--prerelease-policy=breaking-in-alpha"])
+        .success();
+
+    let prereleases = Prereleases::new("2.0.0-beta.1", "2.0.0-rc.1");
+    prereleases
+        .check(&["--prerelease-policy=breaking-in-alpha"])
+        .failure()
+        .stderr(predicates::str::contains(
+            "function_missing v2.0.0-beta.1 -> v2.0.0-rc.1 (minor change)",
+        ))
+        .stdout(predicates::str::contains(
+            "function function_missing::will_be_removed_fn",
+        ));
+}
+
+#[test]
+fn compatible_policy_covers_the_final_release() {
+    let prereleases = Prereleases::new("2.0.0-rc.1", "2.0.0");
+    prereleases.check(&[]).success();
+    prereleases
+        .check(&["--prerelease-policy=compatible"])
+        .failure()
+        .stderr(predicates::str::contains(
+            "function_missing v2.0.0-rc.1 -> v2.0.0 (minor change)",
+        ));
+}
@@ -0,0 +1,458 @@
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use predicates::boolean::PredicateBooleanExt as _;
+use sha2::Digest as _;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// A copy of the `function_missing` test crate's new version, in a directory whose cargo
+/// configuration replaces a registry source with one that has its old version.
+/// Everything is on the local filesystem, so no network access is needed.
+struct ReplacedRegistry {
+    dir: PathBuf,
+}
+
+impl ReplacedRegistry {
+    fn new(label: &str, cargo_config: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-{label}-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        let project = dir.join("project");
+        std::fs::create_dir_all(project.join("src")).expect("failed to create temp dir");
+        std::fs::create_dir_all(project.join(".cargo")).expect("failed to create temp dir");
+        for file in ["Cargo.toml", "src/lib.rs"] {
+            std::fs::copy(
+                Path::new("test_crates/function_missing/new").join(file),
+                project.join(file),
+            )
+            .expect("failed to copy test crate");
+        }
+        std::fs::write(project.join(".cargo/config.toml"), cargo_config)
+            .expect("failed to write cargo config");
+        Self { dir }
+    }
+
+    /// Package the `function_missing` test crate at `crate_dir` as `version`
+    /// into the `local-registry` source at `../registry`.
+    fn publish_to_local_registry(&self, crate_dir: &str, version: &str) {
+        let package_dir = self.dir.join(format!("package-{version}"));
+        std::fs::create_dir_all(package_dir.join("src")).expect("failed to create temp dir");
+        let manifest = std::fs::read_to_string(Path::new(crate_dir).join("Cargo.toml"))
+            .expect("failed to read manifest");
+        std::fs::write(
+            package_dir.join("Cargo.toml"),
+            manifest.replace("version = \"0.1.0\"", &format!("version = \"{version}\"")),
+        )
+        .expect("failed to write manifest");
+        std::fs::copy(
+            Path::new(crate_dir).join("src/lib.rs"),
+            package_dir.join("src/lib.rs"),
+        )
+        .expect("failed to copy test crate");
+        self.publish_package(&package_dir, "function_missing", version);
+    }
+
+    /// Package the crate at `package_dir`, named `name` at `version`,
+    /// into the `local-registry` source at `../registry`, with an index entry for it.
+    fn publish_package(&self, package_dir: &Path, name: &str, version: &str) {
+        let output = std::process::Command::new("cargo")
+            .args([
+                "package",
+                "--no-verify",
+                "--allow-dirty",
+                "--offline",
+                "--quiet",
+            ])
+            .arg("--manifest-path")
+            .arg(package_dir.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(self.dir.join("target"))
+            .output()
+            .expect("failed to run cargo package");
+        assert!(output.status.success(), "{output:?}");
+        let crate_file = std::fs::read(
+            self.dir
+                .join(format!("target/package/{name}-{version}.crate")),
+        )
+        .expect("failed to read packaged crate");
+
+        let registry_dir = self.dir.join("registry");
+        // The index path of crate names with at least 4 characters.
+        let index_dir = registry_dir.join(format!("index/{}/{}", &name[..2], &name[2..4]));
+        std::fs::create_dir_all(&index_dir).expect("failed to create index");
+        std::fs::write(
+            registry_dir.join(format!("{name}-{version}.crate")),
+            &crate_file,
+        )
+        .expect("failed to write crate to registry");
+        let checksum: String = sha2::Sha256::digest(&crate_file)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        let mut index = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(index_dir.join(name))
+            .expect("failed to open index entry");
+        writeln!(
+            index,
+            r#"{{"name":"{name}","vers":"{version}","deps":[],"features":{{}},"cksum":"{checksum}","yanked":false}}"#
+        )
+        .expect("failed to write index entry");
+    }
+
+    /// Release the current crate as `version`.
+    fn set_version(&self, version: &str) {
+        let path = self.dir.join("project/Cargo.toml");
+        let manifest = std::fs::read_to_string(&path).expect("failed to read manifest");
+        std::fs::write(
+            &path,
+            manifest.replace("version = \"0.1.0\"", &format!("version = \"{version}\"")),
+        )
+        .expect("failed to write manifest");
+    }
+
+    fn check(&self, args: &[&str]) -> String {
+        let output = cargo_semver_checks()
+            .current_dir(self.dir.join("project"))
+            .args(["semver-checks", "check-release"])
+            .args(args)
+            .assert()
+            .failure()
+            .get_output()
+            .stdout
+            .clone();
+        String::from_utf8(output).expect("output isn't UTF-8")
+    }
+}
+
+impl Drop for ReplacedRegistry {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn assert_found_breakage(stdout: &str) {
+    assert!(stdout.contains("--- failure function_missing:"), "{stdout}");
+    assert!(
+        stdout.contains("function function_missing::will_be_removed_fn"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn crates_io_replaced_with_vendored_crates() {
+    let registry = ReplacedRegistry::new(
+        "vendored-baseline",
+        r#"
+[source.crates-io]
+replace-with = "vendored"
+
+[source.vendored]
+directory = "../vendor"
+"#,
+    );
+    let vendored = registry.dir.join("vendor/function_missing");
+    std::fs::create_dir_all(vendored.join("src")).expect("failed to create vendor dir");
+    for file in ["Cargo.toml", "src/lib.rs"] {
+        std::fs::copy(
+            Path::new("test_crates/function_missing/old").join(file),
+            vendored.join(file),
+        )
+        .expect("failed to vendor test crate");
+    }
+    std::fs::write(
+        vendored.join(".cargo-checksum.json"),
+        r#"{"files":{},"package":null}"#,
+    )
+    .expect("failed to write checksums");
+
+    assert_found_breakage(&registry.check(&[]));
+}
+
+#[test]
+fn named_registry_replaced_with_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "local-registry-baseline",
+        r#"
+[registries.internal]
+index = "sparse+https://registry.invalid/index/"
+
+[source.internal-index]
+registry = "sparse+https://registry.invalid/index/"
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    registry.publish_to_local_registry("test_crates/function_missing/old", "0.1.0");
+
+    assert_found_breakage(&registry.check(&["--registry=internal"]));
+}
+
+/// The latest compatible release matches the current crate, but the functions it removed
+/// are still breaking relative to the release before it.
+#[test]
+fn every_compatible_release_in_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "all-in-major",
+        r#"
+[source.crates-io]
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    registry.publish_to_local_registry("test_crates/function_missing/old", "0.1.0");
+    registry.publish_to_local_registry("test_crates/function_missing/new", "0.1.1");
+    registry.set_version("0.1.2");
+
+    cargo_semver_checks()
+        .current_dir(registry.dir.join("project"))
+        .args(["semver-checks", "check-release"])
+        .assert()
+        .success();
+
+    let stdout = registry.check(&["--baseline-all-in-major"]);
+    assert_found_breakage(&stdout);
+    assert!(
+        stdout.contains("    found in: baseline v0.1.0\n"),
+        "{stdout}"
+    );
+    assert!(!stdout.contains("found in: baseline v0.1.1"), "{stdout}");
+}
+
+/// Pre-releases are checked against the last stable release by default, but against
+/// the last pre-release of the same version with a pre-release policy.
+#[test]
+fn prerelease_baseline_in_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "prerelease-baseline",
+        r#"
+[source.crates-io]
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    registry.publish_to_local_registry("test_crates/function_missing/new", "1.0.0");
+    registry.publish_to_local_registry("test_crates/function_missing/old", "2.0.0-alpha.1");
+    registry.set_version("2.0.0-beta.1");
+
+    cargo_semver_checks()
+        .current_dir(registry.dir.join("project"))
+        .args(["semver-checks", "check-release"])
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "function_missing v1.0.0 -> v2.0.0-beta.1",
+        ));
+
+    assert_found_breakage(&registry.check(&["--prerelease-policy=compatible"]));
+}
+
+/// A dependency release that breaks the build is avoided by seeding dependency resolution
+/// with the project's lockfile, which predates it.
+#[test]
+fn lockfile_keeps_dependencies_locked_from_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "lockfile",
+        r#"
+[source.crates-io]
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    let dependency = registry.dir.join("dependency");
+    std::fs::create_dir_all(dependency.join("src")).expect("failed to create temp dir");
+    let publish_dependency = |version: &str, lib: &str| {
+        std::fs::write(
+            dependency.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"shared_dep\"\nversion = \"{version}\"\nedition = \"2021\"\n\
+                 description = \"test\"\nlicense = \"MIT\"\n"
+            ),
+        )
+        .expect("failed to write manifest");
+        std::fs::write(dependency.join("src/lib.rs"), lib).expect("failed to write lib.rs");
+        registry.publish_package(&dependency, "shared_dep", version);
+    };
+
+    publish_dependency("1.0.0", "pub struct Shared;\n");
+    let manifest_path = registry.dir.join("project/Cargo.toml");
+    let manifest = std::fs::read_to_string(&manifest_path).expect("failed to read manifest");
+    std::fs::write(
+        &manifest_path,
+        manifest.replace("[dependencies]\n", "[dependencies]\nshared_dep = \"1\"\n"),
+    )
+    .expect("failed to write manifest");
+    let output = std::process::Command::new("cargo")
+        .args(["generate-lockfile", "--offline"])
+        .current_dir(registry.dir.join("project"))
+        .output()
+        .expect("failed to run cargo generate-lockfile");
+    assert!(output.status.success(), "{output:?}");
+    publish_dependency("1.0.1", "compile_error!(\"broken release\");\n");
+
+    let check = |args: &[&str]| {
+        cargo_semver_checks()
+            .current_dir(registry.dir.join("project"))
+            .args(["semver-checks", "check-release", "--baseline-root=."])
+            .args(args)
+            .assert()
+    };
+    check(&[])
+        .failure()
+        .stderr(predicates::str::contains("broken release"));
+    check(&["--lockfile"]).success();
+
+    // A lockfile saved elsewhere works the same way.
+    std::fs::rename(
+        registry.dir.join("project/Cargo.lock"),
+        registry.dir.join("saved.lock"),
+    )
+    .expect("failed to move lockfile");
+    check(&["--lockfile=../saved.lock"]).success();
+}
+
+/// Replace the project with a workspace of crates at version 1.0.0, each with the given
+/// `lib.rs`, and publish them to the `local-registry` source as the same version
+/// with `published_lib_rs`, so that each crate's baseline is its registry release.
+fn publish_workspace(registry: &ReplacedRegistry, members: &[(&str, &str, &str)]) {
+    let project = registry.dir.join("project");
+    std::fs::remove_dir_all(project.join("src")).expect("failed to remove src");
+    let member_names = members
+        .iter()
+        .map(|(name, _, _)| format!("\"{name}\""))
+        .collect::<Vec<_>>()
+        .join(", ");
+    std::fs::write(
+        project.join("Cargo.toml"),
+        format!("[workspace]\nmembers = [{member_names}]\nresolver = \"2\"\n"),
+    )
+    .expect("failed to write manifest");
+    for (name, _, published_lib_rs) in members {
+        let member = project.join(name);
+        std::fs::create_dir_all(member.join("src")).expect("failed to create temp dir");
+        std::fs::write(
+            member.join("Cargo.toml"),
+            format!(
+                "[package]\nname = \"{name}\"\nversion = \"1.0.0\"\nedition = \"2021\"\n\
+                 description = \"test\"\nlicense = \"MIT\"\n"
+            ),
+        )
+        .expect("failed to write manifest");
+        std::fs::write(member.join("src/lib.rs"), published_lib_rs)
+            .expect("failed to write lib.rs");
+    }
+    // Cargo needs every member of the workspace to package any of them.
+    for (name, lib_rs, _) in members {
+        let member = project.join(name);
+        registry.publish_package(&member, name, "1.0.0");
+        std::fs::write(member.join("src/lib.rs"), lib_rs).expect("failed to write lib.rs");
+    }
+}
+
+/// The registry baselines of a workspace's crates are built together in one placeholder
+/// project, and checked as if each was built on its own.
+#[test]
+fn workspace_baselines_built_together_from_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "combined-baselines",
+        r#"
+[source.crates-io]
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    publish_workspace(
+        &registry,
+        &[
+            ("alpha_crate", "pub fn alpha() {}\n", "pub fn alpha() {}\n"),
+            (
+                "beta_crate",
+                "pub fn beta() {}\n",
+                "pub fn beta() {}\npub fn removed_from_beta() {}\n",
+            ),
+        ],
+    );
+
+    let assert = cargo_semver_checks()
+        .current_dir(registry.dir.join("project"))
+        .args(["semver-checks", "check-release", "--workspace"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Building alpha_crate v1.0.0, beta_crate v1.0.0 (baseline)",
+        ))
+        .stderr(predicates::str::contains("failed to build baselines together").not());
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(stdout.contains("--- failure function_missing:"), "{stdout}");
+    assert!(
+        stdout.contains("function beta_crate::removed_from_beta"),
+        "{stdout}"
+    );
+}
+
+/// If the combined build fails, each baseline is built on its own, so one broken
+/// baseline doesn't keep the other crates from being checked.
+#[test]
+fn workspace_baselines_fall_back_to_separate_builds_from_local_registry() {
+    let registry = ReplacedRegistry::new(
+        "combined-baselines-fallback",
+        r#"
+[source.crates-io]
+replace-with = "on-disk"
+
+[source.on-disk]
+local-registry = "../registry"
+"#,
+    );
+    publish_workspace(
+        &registry,
+        &[
+            ("alpha_crate", "pub fn alpha() {}\n", "pub fn alpha() {}\n"),
+            (
+                "beta_crate",
+                "pub fn beta() {}\n",
+                "pub fn beta() {}\npub fn removed_from_beta() {}\n",
+            ),
+            (
+                "gamma_crate",
+                "pub fn gamma() {}\n",
+                "compile_error!(\"broken baseline release\");\n",
+            ),
+        ],
+    );
+
+    let assert = cargo_semver_checks()
+        .current_dir(registry.dir.join("project"))
+        .args(["semver-checks", "check-release", "--workspace"])
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "failed to build baselines together, building them one at a time",
+        ))
+        .stderr(predicates::str::contains("broken baseline release"))
+        .stderr(predicates::str::contains(
+            "aborting due to failure to build rustdoc for crate gamma_crate v1.0.0",
+        ));
+    let stdout = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(
+        stdout.contains("function beta_crate::removed_from_beta"),
+        "{stdout}"
+    );
+}
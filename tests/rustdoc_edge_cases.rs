@@ -1,4 +1,5 @@
 use assert_cmd::Command;
+use predicates::boolean::PredicateBooleanExt as _;
 
 fn cargo_semver_checks() -> Command {
     assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
@@ -324,3 +325,146 @@ fn baseline_root_bare_cargo_toml_path() {
         .assert()
         .success();
 }
+
+/// Copy the `template` test crate's rustdoc for `side` into a fresh directory,
+/// with a build flags file next to it describing how it was built.
+fn rustdoc_with_build_flags(side: &str, build_flags: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cargo-semver-checks-build-flags-{side}-{}-{:032x}",
+        std::process::id(),
+        rand::random::<u128>()
+    ));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let json = dir.join("rustdoc.json");
+    std::fs::copy(
+        format!("localdata/test_data/template/{side}/rustdoc.json"),
+        &json,
+    )
+    .expect(
+        "failed to copy rustdoc, did you forget to run ./scripts/regenerate_test_rustdocs.sh ?",
+    );
+    std::fs::write(dir.join("rustdoc.json.build-flags.json"), build_flags)
+        .expect("failed to write build flags");
+    json
+}
+
+/// Rustdoc built with different `--cfg` values on each side may make items gated on them
+/// look added or removed, so the mismatch is pointed out.
+#[test]
+fn rustdoc_files_built_with_different_cfgs() {
+    let current =
+        rustdoc_with_build_flags("new", r#"{"target_triple": "x86_64-unknown-linux-gnu"}"#);
+    let baseline = rustdoc_with_build_flags(
+        "old",
+        r#"{"target_triple": "x86_64-unknown-linux-gnu", "cfgs": ["tokio_unstable"]}"#,
+    );
+    cargo_semver_checks()
+        .args(["semver-checks", "check-release", "--current-rustdoc"])
+        .arg(&current)
+        .arg("--baseline-rustdoc")
+        .arg(&baseline)
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "were built with different flags:\n  \
+             `--cfg tokio_unstable` is only set for the baseline crate\n",
+        ));
+}
+
+/// Rustdoc built for different targets can't be meaningfully compared.
+#[test]
+fn rustdoc_files_built_for_different_targets() {
+    let current =
+        rustdoc_with_build_flags("new", r#"{"target_triple": "x86_64-unknown-linux-gnu"}"#);
+    let baseline = rustdoc_with_build_flags("old", r#"{"target_triple": "aarch64-apple-darwin"}"#);
+    cargo_semver_checks()
+        .args(["semver-checks", "check-release", "--current-rustdoc"])
+        .arg(&current)
+        .arg("--baseline-rustdoc")
+        .arg(&baseline)
+        .env_remove("RUST_BACKTRACE")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "was built for target `x86_64-unknown-linux-gnu`, \
+             but baseline rustdoc for target `aarch64-apple-darwin`",
+        ));
+}
+
+/// Copy the `template` test crate's rustdoc for `side` into a fresh directory,
+/// labeled with rustdoc JSON format `version` as if made by a different toolchain.
+fn rustdoc_in_format(side: &str, version: u32) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "cargo-semver-checks-format-{side}-{}-{:032x}",
+        std::process::id(),
+        rand::random::<u128>()
+    ));
+    std::fs::create_dir_all(&dir).expect("failed to create temp dir");
+    let rustdoc = std::fs::read(format!("localdata/test_data/template/{side}/rustdoc.json"))
+        .expect(
+            "failed to read rustdoc, did you forget to run ./scripts/regenerate_test_rustdocs.sh ?",
+        );
+    let mut rustdoc: serde_json::Value =
+        serde_json::from_slice(&rustdoc).expect("rustdoc isn't valid JSON");
+    rustdoc["format_version"] = version.into();
+    let json = dir.join("rustdoc.json");
+    std::fs::write(&json, rustdoc.to_string()).expect("failed to write rustdoc");
+    json
+}
+
+/// Rustdoc files in different formats can't be regenerated, so both are named
+/// along with the format the other side needs.
+#[test]
+fn rustdoc_files_in_different_formats() {
+    let current = rustdoc_in_format("new", 57);
+    let baseline = rustdoc_in_format("old", 60);
+    cargo_semver_checks()
+        .args(["semver-checks", "check-release", "--current-rustdoc"])
+        .arg(&current)
+        .arg("--baseline-rustdoc")
+        .arg(&baseline)
+        .env_remove("RUST_BACKTRACE")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "current rustdoc JSON format v57 cannot be compared \
+             with baseline rustdoc JSON format v60",
+        ))
+        .stderr(predicates::str::contains(format!(
+            "the current rustdoc was loaded from `{}` via `--current-rustdoc`",
+            current.display()
+        )))
+        .stderr(predicates::str::contains(format!(
+            "the baseline rustdoc was loaded from `{}` via `--baseline-rustdoc`",
+            baseline.display()
+        )));
+}
+
+/// When the current crate's sources are available they're regenerated, but that can't
+/// help with a baseline rustdoc file in a format the installed toolchain doesn't emit.
+#[test]
+fn baseline_rustdoc_file_in_another_format() {
+    let baseline = rustdoc_in_format("old", 60);
+    cargo_semver_checks()
+        .args([
+            "semver-checks",
+            "check-release",
+            "--manifest-path=test_crates/template/new/Cargo.toml",
+            "--baseline-rustdoc",
+        ])
+        .arg(&baseline)
+        .env_remove("RUST_BACKTRACE")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Regenerating current rustdoc for template",
+        ))
+        .stderr(predicates::str::contains(
+            "with baseline rustdoc JSON format v60",
+        ))
+        .stderr(predicates::str::contains(format!(
+            "the baseline rustdoc was loaded from `{}` via `--baseline-rustdoc`",
+            baseline.display()
+        )))
+        .stderr(predicates::str::contains("--current-rustdoc").not());
+}
@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use assert_cmd::Command;
+use cargo_semver_checks::{CancellationToken, Check, GlobalConfig, Rustdoc};
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// A crate whose build script takes far longer than any timeout in these tests,
+/// like one stuck waiting on a lock.
+struct HangingBuildScript {
+    dir: PathBuf,
+}
+
+impl HangingBuildScript {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-timeouts-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        std::fs::create_dir_all(dir.join("src")).expect("failed to create temp dir");
+        std::fs::write(
+            dir.join("Cargo.toml"),
+            "[package]\n\
+             name = \"hangs_in_build_script\"\n\
+             version = \"0.1.0\"\n\
+             edition = \"2021\"\n\
+             publish = false\n",
+        )
+        .expect("failed to write manifest");
+        std::fs::write(dir.join("src/lib.rs"), "pub fn function() {}\n")
+            .expect("failed to write lib.rs");
+        std::fs::write(
+            dir.join("build.rs"),
+            "fn main() { std::thread::sleep(std::time::Duration::from_secs(120)); }\n",
+        )
+        .expect("failed to write build.rs");
+        Self { dir }
+    }
+}
+
+impl Drop for HangingBuildScript {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn hanging_build_is_aborted_after_timeout() {
+    let hanging = HangingBuildScript::new();
+    let start = Instant::now();
+    cargo_semver_checks()
+        .current_dir(&hanging.dir)
+        .args([
+            "semver-checks",
+            "check-release",
+            "--baseline-root=.",
+            "--timeout=3s",
+        ])
+        .env_remove("RUST_BACKTRACE")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "aborting because 'cargo doc' on current crate hangs_in_build_script v0.1.0 \
+             timed out after 3s",
+        ))
+        .stderr(predicates::str::contains(
+            "note: pass a longer `--timeout` if the build only needs more time",
+        ));
+    assert!(
+        start.elapsed() < Duration::from_secs(60),
+        "took {:?}",
+        start.elapsed()
+    );
+}
+
+#[test]
+fn timeouts_do_not_interrupt_a_check_that_finishes_in_time() {
+    cargo_semver_checks()
+        .args([
+            "semver-checks",
+            "check-release",
+            "--manifest-path=test_crates/function_missing/new/Cargo.toml",
+            "--baseline-root=test_crates/function_missing/old",
+            "--timeout=10m",
+            "--query-timeout=10m",
+        ])
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(
+            "function function_missing::will_be_removed_fn",
+        ));
+}
+
+/// A check cancelled from another thread kills the running build and returns an error.
+#[test]
+fn hanging_build_is_cancelled_through_the_library() {
+    let hanging = HangingBuildScript::new();
+    let token = CancellationToken::new();
+    let mut check = Check::new(Rustdoc::from_root(&hanging.dir));
+    check
+        .set_baseline(Rustdoc::from_root(&hanging.dir))
+        .set_cancellation_token(token.clone());
+
+    let start = Instant::now();
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_secs(3));
+        token.cancel();
+    });
+    let err = check
+        .check_release(&mut GlobalConfig::new())
+        .expect_err("the check should be cancelled");
+    canceller.join().expect("canceller panicked");
+
+    assert!(
+        format!("{err:#}")
+            .contains("'cargo doc' on current crate hangs_in_build_script v0.1.0 was cancelled"),
+        "{err:#}"
+    );
+    assert!(
+        start.elapsed() < Duration::from_secs(60),
+        "took {:?}",
+        start.elapsed()
+    );
+}
@@ -0,0 +1,78 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// Copies of the `template` test crate's versions, next to a `rust-toolchain.toml`
+/// that pins a toolchain which isn't installed.
+struct PinnedProject {
+    dir: PathBuf,
+}
+
+impl PinnedProject {
+    fn new() -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-toolchain-flags-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        for version in ["old", "new"] {
+            let crate_dir = dir.join(version);
+            std::fs::create_dir_all(crate_dir.join("src")).expect("failed to create temp dir");
+            for file in ["Cargo.toml", "Cargo.lock", "src/lib.rs"] {
+                std::fs::copy(
+                    Path::new("test_crates/template").join(version).join(file),
+                    crate_dir.join(file),
+                )
+                .expect("failed to copy test crate");
+            }
+        }
+        std::fs::write(
+            dir.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"nonexistent-pinned\"\n",
+        )
+        .expect("failed to write rust-toolchain.toml");
+        Self { dir }
+    }
+
+    fn check(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        cargo_semver_checks()
+            .current_dir(self.dir.join("new"))
+            // Let the project's `rust-toolchain.toml` pick the toolchain, as it would for a user.
+            .env_remove("RUSTUP_TOOLCHAIN")
+            .args(["semver-checks", "check-release", "--baseline-root=../old"])
+            .args(args)
+            .assert()
+    }
+}
+
+impl Drop for PinnedProject {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[test]
+fn pinned_toolchain_is_used_by_default() {
+    let project = PinnedProject::new();
+    let output = project.check(&[]).failure().get_output().clone();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("nonexistent-pinned"), "{stderr}");
+}
+
+#[test]
+fn toolchain_flag_overrides_pinned_toolchain() {
+    let project = PinnedProject::new();
+    project.check(&["--toolchain=stable"]).success();
+}
+
+#[test]
+fn current_and_baseline_toolchains_override_pinned_toolchain() {
+    let project = PinnedProject::new();
+    project
+        .check(&["--current-toolchain=stable", "--baseline-toolchain=nightly"])
+        .success();
+}
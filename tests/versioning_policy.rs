@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+
+fn cargo_semver_checks() -> Command {
+    assert_cmd::cargo::cargo_bin_cmd!("cargo-semver-checks")
+}
+
+/// Copies of a test crate's versions, released with the given version numbers.
+struct Releases {
+    dir: PathBuf,
+}
+
+impl Releases {
+    fn new(test_crate: &str, baseline_version: &str, current_version: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!(
+            "cargo-semver-checks-versioning-policy-{}-{:032x}",
+            std::process::id(),
+            rand::random::<u128>()
+        ));
+        for (side, version) in [("old", baseline_version), ("new", current_version)] {
+            let crate_dir = Path::new("test_crates").join(test_crate).join(side);
+            std::fs::create_dir_all(dir.join(side).join("src")).expect("failed to create temp dir");
+            std::fs::copy(
+                crate_dir.join("src/lib.rs"),
+                dir.join(side).join("src/lib.rs"),
+            )
+            .expect("failed to copy test crate");
+            let manifest = std::fs::read_to_string(crate_dir.join("Cargo.toml"))
+                .expect("failed to read manifest");
+            std::fs::write(
+                dir.join(side).join("Cargo.toml"),
+                manifest.replace("version = \"0.1.0\"", &format!("version = \"{version}\"")),
+            )
+            .expect("failed to write manifest");
+        }
+        Self { dir }
+    }
+
+    /// Configure the current crate's versioning policy in its manifest.
+    fn set_manifest_policy(&self, policy: &str) {
+        let path = self.dir.join("new/Cargo.toml");
+        let mut manifest = std::fs::read_to_string(&path).expect("failed to read manifest");
+        manifest.push_str(&format!(
+            "\n[package.metadata.cargo-semver-checks]\nversioning-policy = \"{policy}\"\n"
+        ));
+        std::fs::write(&path, manifest).expect("failed to write manifest");
+    }
+
+    fn check(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        cargo_semver_checks()
+            .args(["semver-checks", "check-release", "--manifest-path"])
+            .arg(self.dir.join("new"))
+            .arg("--baseline-root")
+            .arg(self.dir.join("old"))
+            .args(args)
+            .assert()
+    }
+}
+
+impl Drop for Releases {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Deprecating a function requires a minor release, which a calendar version's
+/// patch component may be, but a regular version's can't.
+#[test]
+fn calendar_policy_allows_additions_in_any_release() {
+    let releases = Releases::new("function_marked_deprecated", "2025.3.1", "2025.3.2");
+    releases
+        .check(&[])
+        .failure()
+        .stdout(predicates::str::contains(
+            "--- failure function_marked_deprecated:",
+        ));
+    releases.check(&["--versioning-policy=calendar"]).success();
+
+    releases.set_manifest_policy("calendar");
+    releases
+        .check(&[])
+        .success()
+        .stderr(predicates::str::contains(
+            "function_marked_deprecated v2025.3.1 -> v2025.3.2 (minor change)",
+        ));
+}
+
+/// Only a change to the left-most component of a calendar version may break the API.
+#[test]
+fn calendar_policy_breaks_only_in_a_new_epoch() {
+    let releases = Releases::new("function_missing", "2025.3.1", "2025.4.0");
+    releases
+        .check(&["--versioning-policy=calendar"])
+        .failure()
+        .stdout(predicates::str::contains(
+            "function function_missing::will_be_removed_fn",
+        ));
+
+    let releases = Releases::new("function_missing", "2025.3.1", "2026.1.0");
+    releases.check(&["--versioning-policy=calendar"]).success();
+}
+
+#[test]
+fn strict_zero_ver_policy_holds_patch_releases_to_patch_rules() {
+    let releases = Releases::new("function_marked_deprecated", "0.3.1", "0.3.2");
+    releases.check(&[]).success();
+
+    releases.set_manifest_policy("strict-zero-ver");
+    releases
+        .check(&[])
+        .failure()
+        .stderr(predicates::str::contains(
+            "function_marked_deprecated v0.3.1 -> v0.3.2 (patch change)",
+        ));
+    releases.check(&["--versioning-policy=cargo"]).success();
+}